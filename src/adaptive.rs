@@ -0,0 +1,211 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::camera::Camera;
+use crate::canvas::{canvas, Canvas};
+use crate::colour::{BLACK, colour, Colour};
+use crate::parallel::{DEFAULT_TILE_SIZE, render_parallel};
+use crate::ray::Ray;
+use crate::random::random_from;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct AdaptiveSampling {
+    // largest per channel colour difference that is still considered smooth
+    pub threshold: f64,
+    // every level splits a region into four quadrants, so a refined pixel takes at most 4 + 16 + ... samples, 0 never
+    // refines
+    pub max_depth: u32,
+    // seeds the lens and time samples of camera renders
+    pub seed: u64,
+}
+
+pub struct AdaptiveRender {
+    pub image: Canvas,
+    pub sample_counts: Vec<u32>,
+}
+
+pub fn adaptive_sampling(threshold: f64, max_depth: u32) -> AdaptiveSampling {
+    AdaptiveSampling {
+        threshold,
+        max_depth,
+        seed: 0,
+    }
+}
+
+impl AdaptiveSampling {
+    pub fn set_seed(self, seed: u64) -> AdaptiveSampling {
+        AdaptiveSampling {
+            seed,
+            ..self
+        }
+    }
+
+    // `trace` receives continuous image coordinates, like `Supersampling::render_pixel`
+    pub fn render<F>(&self, width: u32, height: u32, threads: u32, trace: F) -> AdaptiveRender
+        where F: Fn(f64, f64) -> Colour + Sync {
+        let first_pass = render_parallel(width, height, threads, DEFAULT_TILE_SIZE, |x, y| {
+            trace(x as f64 + 0.5, y as f64 + 0.5)
+        });
+
+        let sample_counts: Vec<AtomicU32> = (0..width as usize * height as usize).map(|_| AtomicU32::new(1)).collect();
+        let image = render_parallel(width, height, threads, DEFAULT_TILE_SIZE, |x, y| {
+            let centre = first_pass.pixel_at(x, y);
+            if self.max_depth == 0 || !self.has_high_contrast(&first_pass, x, y) {
+                return centre;
+            }
+            // the first pass sample only decides whether to refine, the count is of the samples the colour is made of
+            let mut samples = 0;
+            let refined = self.refine(x as f64, y as f64, 1.0, 1, &mut samples, &trace);
+            sample_counts[y as usize * width as usize + x as usize].store(samples, Ordering::Relaxed);
+            refined
+        });
+
+        AdaptiveRender {
+            image,
+            sample_counts: sample_counts.into_iter().map(|count| count.into_inner()).collect(),
+        }
+    }
+
+    fn has_high_contrast(&self, first_pass: &Canvas, x: u32, y: u32) -> bool {
+        let centre = first_pass.pixel_at(x, y);
+        let neighbours = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+        neighbours.iter().any(|(dx, dy)| {
+            let nx = x as i64 + dx;
+            let ny = y as i64 + dy;
+            if nx < 0 || ny < 0 || nx >= first_pass.width as i64 || ny >= first_pass.height as i64 {
+                return false;
+            }
+            centre.difference(first_pass.pixel_at(nx as u32, ny as u32)) > self.threshold
+        })
+    }
+
+    // splits the square at (x, y) into quadrants, quadrants that still differ from the average are split again
+    fn refine<F>(&self, x: f64, y: f64, size: f64, depth: u32, samples: &mut u32, trace: &F) -> Colour
+        where F: Fn(f64, f64) -> Colour {
+        let half = size / 2.0;
+        let corners = [(x, y), (x + half, y), (x, y + half), (x + half, y + half)];
+        let mut quadrants = [BLACK; 4];
+        for (quadrant, (corner_x, corner_y)) in quadrants.iter_mut().zip(corners) {
+            *quadrant = trace(corner_x + half / 2.0, corner_y + half / 2.0);
+            *samples += 1;
+        }
+        let estimate = average(&quadrants);
+
+        if depth < self.max_depth {
+            for (quadrant, (corner_x, corner_y)) in quadrants.iter_mut().zip(corners) {
+                if quadrant.difference(estimate) > self.threshold {
+                    *quadrant = self.refine(corner_x, corner_y, half, depth + 1, samples, trace);
+                }
+            }
+        }
+        average(&quadrants)
+    }
+}
+
+impl AdaptiveRender {
+    // grey scale image of the samples spent per pixel, white is the most expensive pixel
+    pub fn sample_count_image(&self) -> Canvas {
+        let mut image = canvas(self.image.width, self.image.height);
+        let max = self.sample_counts.iter().copied().max().unwrap_or(1).max(2);
+        for (pixel, count) in image.pixels.iter_mut().zip(&self.sample_counts) {
+            let value = (*count - 1) as f64 / (max - 1) as f64;
+            *pixel = colour(value, value, value);
+        }
+        image
+    }
+}
+
+impl Camera {
+    pub fn render_adaptive<F>(&self, sampling: &AdaptiveSampling, threads: u32, trace: F) -> AdaptiveRender
+        where F: Fn(&Ray) -> Colour + Sync {
+        sampling.render(self.hsize, self.vsize, threads, |x, y| {
+            // adaptive samples have no fixed index, so the lens position is derived from the sample position
+            let mut rng = random_from(&[sampling.seed, x.to_bits(), y.to_bits()]);
+            let lens_u = rng.next_f64();
+            let lens_v = rng.next_f64();
+            let mut ray = self.ray_through_lens(x, y, lens_u, lens_v);
+            ray.time = self.shutter_time(rng.next_f64());
+            trace(&ray)
+        })
+    }
+}
+
+fn average(colours: &[Colour; 4]) -> Colour {
+    (colours[0] + colours[1] + colours[2] + colours[3]) * 0.25
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::adaptive::adaptive_sampling;
+    use crate::camera::camera;
+    use crate::colour::{BLACK, colour, WHITE};
+    use crate::ray::Ray;
+
+    #[test]
+    fn flat_images_take_one_sample_per_pixel() {
+        let result = adaptive_sampling(0.1, 3).render(8, 6, 2, |_, _| colour(0.2, 0.4, 0.6));
+        assert!(result.sample_counts.iter().all(|count| *count == 1));
+        assert_eq!(result.image.pixel_at(7, 5), colour(0.2, 0.4, 0.6));
+    }
+
+    #[test]
+    fn only_pixels_along_an_edge_are_refined() {
+        let result = adaptive_sampling(0.1, 2).render(10, 4, 2, |x, _| if x < 4.3 { WHITE } else { BLACK });
+        for y in 0..4 {
+            assert_eq!(result.sample_counts[y * 10], 1);
+            assert_eq!(result.sample_counts[y * 10 + 9], 1);
+            // the pixels on both sides of the edge are refined
+            assert!(result.sample_counts[y * 10 + 3] > 1);
+            assert!(result.sample_counts[y * 10 + 4] > 1);
+        }
+    }
+
+    #[test]
+    fn refined_pixels_approach_the_covered_area() {
+        let result = adaptive_sampling(0.01, 4).render(10, 1, 1, |x, _| if x < 4.3 { WHITE } else { BLACK });
+        let pixel = result.image.pixel_at(4, 0);
+        assert!((pixel.red - 0.3).abs() < 0.05);
+        assert_eq!(result.image.pixel_at(3, 0).red, 1.0);
+    }
+
+    #[test]
+    fn depth_limits_the_samples_per_pixel() {
+        let result = adaptive_sampling(0.0, 2).render(4, 4, 1, |x, y| colour((x * y).sin().abs(), 0.0, 0.0));
+        assert!(result.sample_counts.iter().all(|count| *count <= 4 + 16));
+    }
+
+    #[test]
+    fn refined_pixels_count_the_samples_their_colour_is_made_of() {
+        let result = adaptive_sampling(0.1, 1).render(10, 1, 1, |x, _| if x < 4.3 { WHITE } else { BLACK });
+        assert_eq!(result.sample_counts[4], 4);
+        // half of the quadrants are left of the edge
+        assert_eq!(result.image.pixel_at(4, 0).red, 0.5);
+    }
+
+    #[test]
+    fn depth_zero_keeps_one_sample_per_pixel() {
+        let result = adaptive_sampling(0.1, 0).render(10, 1, 1, |x, _| if x < 4.3 { WHITE } else { BLACK });
+        assert!(result.sample_counts.iter().all(|count| *count == 1));
+        assert_eq!(result.image.pixel_at(4, 0).red, 0.0);
+    }
+
+    #[test]
+    fn sample_count_image_highlights_expensive_pixels() {
+        let result = adaptive_sampling(0.1, 2).render(10, 1, 1, |x, _| if x < 4.3 { WHITE } else { BLACK });
+        let debug = result.sample_count_image();
+        assert_eq!(debug.pixel_at(0, 0).red, 0.0);
+        assert!(debug.pixel_at(4, 0).red > 0.0);
+        let brightest = debug.pixels.iter().map(|pixel| pixel.red).fold(0.0, f64::max);
+        assert_eq!(brightest, 1.0);
+    }
+
+    #[test]
+    fn rendering_adaptively_through_a_camera() {
+        let c = camera(9, 9, PI / 2.0);
+        let result = c.render_adaptive(&adaptive_sampling(0.1, 2), 2, |r: &Ray| if r.direction.x < 0.0 { WHITE } else { BLACK });
+        assert_eq!(result.image.width, 9);
+        assert_eq!(result.sample_counts[4 * 9], 1);
+        assert!(result.sample_counts[4 * 9 + 4] > 1);
+    }
+}
@@ -0,0 +1,501 @@
+use std::f64::consts::PI;
+
+use crate::canvas::Canvas;
+use crate::colour::Colour;
+use crate::error::Error;
+use crate::matrix::{IDENTITY_MATRIX, matrix, Matrix, translation};
+use crate::parallel::{DEFAULT_TILE_SIZE, render_parallel};
+use crate::ray::{ray, Ray};
+use crate::sampling::Supersampling;
+use crate::tuple::{cross_product, ORIGIN, point, Tuple, vector};
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Camera {
+    pub hsize: u32,
+    pub vsize: u32,
+    pub field_of_view: f64,
+    // private so the two always match, they are only set together by `set_transform`
+    transform: Matrix<4>,
+    inverse_transform: Matrix<4>,
+    pub half_width: f64,
+    pub half_height: f64,
+    pub pixel_size: f64,
+    // radius of the lens, zero gives a pinhole camera where everything is in focus
+    pub aperture: f64,
+    // distance from the camera to the plane that is in perfect focus
+    pub focal_distance: f64,
+    pub bokeh: Bokeh,
+    // rays are spread over the time between opening and closing the shutter
+    pub shutter_open: f64,
+    pub shutter_close: f64,
+    pub projection: Projection,
+}
+
+// how pixels map to rays, the camera looks along -z in its own space with +y up
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Projection {
+    Perspective,
+    // parallel rays, `width` is the width of the view in world units
+    Orthographic { width: f64 },
+    // equidistant fisheye, the angle from the view direction grows linearly with the distance from the image
+    // centre and the field of view spans the longer side of the image
+    Fisheye,
+    // the full sphere of directions, longitude across the image and latitude from the top to the bottom
+    Equirectangular,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Bokeh {
+    Disk,
+    // a lens opening formed by `blades` straight aperture blades, rotated by `rotation` radians
+    Polygon { blades: u32, rotation: f64 },
+}
+
+pub fn camera(hsize: u32, vsize: u32, field_of_view: f64) -> Camera {
+    let half_view = f64::tan(field_of_view / 2.0);
+    let aspect = hsize as f64 / vsize as f64;
+    let (half_width, half_height) = if aspect >= 1.0 {
+        (half_view, half_view / aspect)
+    } else {
+        (half_view * aspect, half_view)
+    };
+    Camera {
+        hsize,
+        vsize,
+        field_of_view,
+        transform: IDENTITY_MATRIX,
+        inverse_transform: IDENTITY_MATRIX,
+        half_width,
+        half_height,
+        pixel_size: half_width * 2.0 / hsize as f64,
+        aperture: 0.0,
+        focal_distance: 1.0,
+        bokeh: Bokeh::Disk,
+        shutter_open: 0.0,
+        shutter_close: 0.0,
+        projection: Projection::Perspective,
+    }
+}
+
+impl Bokeh {
+    // maps a point of the unit square onto the lens shape, the result lies within the unit circle
+    pub fn sample(&self, u: f64, v: f64) -> (f64, f64) {
+        match self {
+            Bokeh::Disk => concentric_disk(u, v),
+            Bokeh::Polygon { blades, rotation } => {
+                let blades = (*blades).max(3);
+                // pick one of the triangles between the centre and two neighbouring corners
+                let scaled = u * blades as f64;
+                let blade = (scaled.floor() as u32).min(blades - 1);
+                let u = scaled - blade as f64;
+                let angle = 2.0 * PI / blades as f64;
+                let (sin1, cos1) = (rotation + angle * blade as f64).sin_cos();
+                let (sin2, cos2) = (rotation + angle * (blade + 1) as f64).sin_cos();
+
+                // uniform sampling of the triangle (centre, corner1, corner2)
+                let a = u.sqrt();
+                let b = v;
+                (a * (1.0 - b) * cos1 + a * b * cos2, a * (1.0 - b) * sin1 + a * b * sin2)
+            }
+        }
+    }
+}
+
+// Shirley's concentric mapping keeps stratified samples stratified on the disk
+fn concentric_disk(u: f64, v: f64) -> (f64, f64) {
+    let a = 2.0 * u - 1.0;
+    let b = 2.0 * v - 1.0;
+    if a == 0.0 && b == 0.0 {
+        return (0.0, 0.0);
+    }
+    let (radius, angle) = if a.abs() > b.abs() {
+        (a, PI / 4.0 * (b / a))
+    } else {
+        (b, PI / 2.0 - PI / 4.0 * (a / b))
+    };
+    (radius * angle.cos(), radius * angle.sin())
+}
+
+pub fn view_transform(from: Tuple, to: Tuple, up: Tuple) -> Matrix<4> {
+    let forward = (to - from).normalize();
+    let left = cross_product(forward, up.normalize());
+    let true_up = cross_product(left, forward);
+    let orientation = matrix::<4>([
+        [left.x, left.y, left.z, 0.0],
+        [true_up.x, true_up.y, true_up.z, 0.0],
+        [-forward.x, -forward.y, -forward.z, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]);
+    orientation * translation(-from.x, -from.y, -from.z)
+}
+
+impl Camera {
+    // a camera of another size that keeps the field of view, transform, lens, shutter and projection
+    pub fn set_size(self, hsize: u32, vsize: u32) -> Camera {
+        Camera {
+            transform: self.transform,
+            inverse_transform: self.inverse_transform,
+            aperture: self.aperture,
+            focal_distance: self.focal_distance,
+            bokeh: self.bokeh,
+            shutter_open: self.shutter_open,
+            shutter_close: self.shutter_close,
+            projection: self.projection,
+            ..camera(hsize, vsize, self.field_of_view)
+        }
+    }
+
+    // fails for transformations without an inverse, like a view transform looking along the up vector
+    pub fn set_transform(self, transform: Matrix<4>) -> Result<Camera, Error> {
+        Ok(Camera {
+            transform,
+            inverse_transform: transform.invert().ok_or(Error::SingularTransform)?,
+            ..self
+        })
+    }
+
+    pub fn transform(&self) -> Matrix<4> {
+        self.transform
+    }
+
+    pub fn inverse_transform(&self) -> Matrix<4> {
+        self.inverse_transform
+    }
+
+    pub fn set_lens(self, aperture: f64, focal_distance: f64, bokeh: Bokeh) -> Camera {
+        Camera {
+            aperture,
+            focal_distance,
+            bokeh,
+            ..self
+        }
+    }
+
+    pub fn set_shutter(self, shutter_open: f64, shutter_close: f64) -> Camera {
+        Camera {
+            shutter_open,
+            shutter_close,
+            ..self
+        }
+    }
+
+    // the lens settings only affect the perspective projection
+    pub fn set_projection(self, projection: Projection) -> Camera {
+        Camera {
+            projection,
+            ..self
+        }
+    }
+
+    // maps a value in [0, 1) onto the shutter interval
+    pub fn shutter_time(&self, u: f64) -> f64 {
+        self.shutter_open + (self.shutter_close - self.shutter_open) * u
+    }
+
+    // x and y are continuous image coordinates, the centre of pixel (px, py) is at (px + 0.5, py + 0.5)
+    pub fn ray_for_pixel(&self, x: f64, y: f64) -> Ray {
+        self.ray_through_lens(x, y, 0.5, 0.5)
+    }
+
+    // lens_u and lens_v in [0, 1) pick the point on the lens the ray starts from, (0.5, 0.5) is the centre
+    pub fn ray_through_lens(&self, x: f64, y: f64, lens_u: f64, lens_v: f64) -> Ray {
+        let (origin, direction) = match self.projection {
+            Projection::Perspective => return self.perspective_ray(x, y, lens_u, lens_v),
+            Projection::Orthographic { width } => {
+                let pixel_size = width / self.hsize as f64;
+                let origin_x = width / 2.0 - x * pixel_size;
+                let origin_y = pixel_size * self.vsize as f64 / 2.0 - y * pixel_size;
+                (point(origin_x, origin_y, 0.0), vector(0.0, 0.0, -1.0))
+            }
+            Projection::Fisheye => {
+                let offset_x = self.hsize as f64 / 2.0 - x;
+                let offset_y = self.vsize as f64 / 2.0 - y;
+                let theta = offset_x.hypot(offset_y) * self.field_of_view / self.hsize.max(self.vsize) as f64;
+                let phi = offset_y.atan2(offset_x);
+                (ORIGIN, vector(theta.sin() * phi.cos(), theta.sin() * phi.sin(), -theta.cos()))
+            }
+            Projection::Equirectangular => {
+                let longitude = (0.5 - x / self.hsize as f64) * 2.0 * PI;
+                let latitude = (0.5 - y / self.vsize as f64) * PI;
+                (ORIGIN, vector(latitude.cos() * longitude.sin(), latitude.sin(), -latitude.cos() * longitude.cos()))
+            }
+        };
+        ray(self.inverse_transform * origin, (self.inverse_transform * direction).normalize())
+    }
+
+    fn perspective_ray(&self, x: f64, y: f64, lens_u: f64, lens_v: f64) -> Ray {
+        let world_x = self.half_width - x * self.pixel_size;
+        let world_y = self.half_height - y * self.pixel_size;
+
+        if self.aperture <= 0.0 {
+            let pixel = self.inverse_transform * point(world_x, world_y, -1.0);
+            let origin = self.inverse_transform * ORIGIN;
+            return ray(origin, (pixel - origin).normalize());
+        }
+
+        // every ray through the lens meets the pinhole ray on the focal plane
+        let focus = point(world_x * self.focal_distance, world_y * self.focal_distance, -self.focal_distance);
+        let (lens_x, lens_y) = self.bokeh.sample(lens_u, lens_v);
+        let lens = point(lens_x * self.aperture, lens_y * self.aperture, 0.0);
+
+        let focus = self.inverse_transform * focus;
+        let origin = self.inverse_transform * lens;
+        ray(origin, (focus - origin).normalize())
+    }
+
+    pub fn render<F>(&self, sampling: &Supersampling, threads: u32, trace: F) -> Canvas
+        where F: Fn(&Ray) -> Colour + Sync {
+        render_parallel(self.hsize, self.vsize, threads, DEFAULT_TILE_SIZE, |x, y| {
+            sampling.render_pixel_indexed(x, y, |sample_x, sample_y, sample| {
+                let mut rng = sampling.sample_random(x, y, sample);
+                let lens_u = rng.next_f64();
+                let lens_v = rng.next_f64();
+                let mut ray = self.ray_through_lens(sample_x, sample_y, lens_u, lens_v);
+                ray.time = self.shutter_time(rng.next_f64());
+                trace(&ray)
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod projection_tests {
+        use std::f64::consts::PI;
+
+        use crate::camera::{camera, Projection, view_transform};
+        use crate::tuple::{point, point_i, Tuple, vector, vector_i};
+
+        // per component with signs, unlike the tuple equality
+        fn assert_close(actual: Tuple, expected: Tuple) {
+            let differences = [actual.x - expected.x, actual.y - expected.y, actual.z - expected.z, actual.w - expected.w];
+            assert!(differences.iter().all(|difference| difference.abs() < 1e-5), "{:?} is not {:?}", actual, expected);
+        }
+
+        #[test]
+        fn default_projection_is_perspective() {
+            let c = camera(201, 101, PI / 2.0);
+            assert_eq!(c.projection, Projection::Perspective);
+            assert_close(c.ray_for_pixel(0.5, 0.5).direction, vector(0.66519, 0.33259, -0.66851));
+        }
+
+        #[test]
+        fn orthographic_rays_are_parallel() {
+            let c = camera(200, 100, PI / 2.0).set_projection(Projection::Orthographic { width: 4.0 });
+            let centre = c.ray_for_pixel(100.0, 50.0);
+            assert_close(centre.origin, point_i(0, 0, 0));
+            assert_close(centre.direction, vector_i(0, 0, -1));
+            let corner = c.ray_for_pixel(0.5, 0.5);
+            assert_close(corner.origin, point(1.99, 0.99, 0.0));
+            assert_close(corner.direction, vector_i(0, 0, -1));
+
+            let moved = c.set_transform(view_transform(point_i(0, 0, -5), point_i(0, 0, 0), vector_i(0, 1, 0))).unwrap();
+            let r = moved.ray_for_pixel(100.0, 50.0);
+            assert_close(r.origin, point_i(0, 0, -5));
+            assert_close(r.direction, vector_i(0, 0, 1));
+        }
+
+        #[test]
+        fn fisheye_angle_grows_linearly() {
+            let c = camera(200, 100, PI).set_projection(Projection::Fisheye);
+            assert_close(c.ray_for_pixel(100.0, 50.0).direction, vector_i(0, 0, -1));
+            // the field of view spans the long side, so its edges look sideways
+            assert_close(c.ray_for_pixel(0.0, 50.0).direction, vector_i(1, 0, 0));
+            // the top edge is half as far from the centre, so it looks half as far off axis
+            let r = c.ray_for_pixel(100.0, 0.0);
+            assert_close(r.direction, vector(0.0, (PI / 4.0).sin(), -(PI / 4.0).cos()));
+        }
+
+        #[test]
+        fn equirectangular_covers_every_direction() {
+            let c = camera(360, 180, PI / 2.0).set_projection(Projection::Equirectangular);
+            assert_close(c.ray_for_pixel(180.0, 90.0).direction, vector_i(0, 0, -1));
+            assert_close(c.ray_for_pixel(90.0, 90.0).direction, vector_i(1, 0, 0));
+            assert_close(c.ray_for_pixel(0.0, 90.0).direction, vector_i(0, 0, 1));
+            assert_close(c.ray_for_pixel(180.0, 0.0).direction, vector_i(0, 1, 0));
+            assert_close(c.ray_for_pixel(42.0, 180.0).direction, vector_i(0, -1, 0));
+            // the left half looks the same way as the left half of a perspective image
+            let perspective = camera(360, 180, PI / 2.0).ray_for_pixel(90.0, 90.0);
+            assert!(perspective.direction.x > 0.0);
+        }
+    }
+
+    mod camera_tests {
+        use std::f64::consts::PI;
+
+        use crate::camera::{Bokeh, camera, view_transform};
+        use crate::colour::{colour, BLACK};
+        use crate::error::Error;
+        use crate::matrix::{identity, rotation_y, scaling_i, translation_i};
+        use crate::ray::Ray;
+        use crate::sampling::{Filter, NO_SUPERSAMPLING, SamplePattern, supersampling};
+        use crate::tuple::{point, point_i, vector, vector_i};
+
+        #[test]
+        fn pixel_size_for_horizontal_canvas() {
+            let c = camera(200, 125, PI / 2.0);
+            assert!((c.pixel_size - 0.01).abs() < 1e-9);
+        }
+
+        #[test]
+        fn pixel_size_for_vertical_canvas() {
+            let c = camera(125, 200, PI / 2.0);
+            assert!((c.pixel_size - 0.01).abs() < 1e-9);
+        }
+
+        #[test]
+        fn ray_through_centre_of_canvas() {
+            let c = camera(201, 101, PI / 2.0);
+            let r = c.ray_for_pixel(100.5, 50.5);
+            assert_eq!(r.origin, point_i(0, 0, 0));
+            assert_eq!(r.direction, vector_i(0, 0, -1));
+        }
+
+        #[test]
+        fn ray_through_corner_of_canvas() {
+            let c = camera(201, 101, PI / 2.0);
+            let r = c.ray_for_pixel(0.5, 0.5);
+            assert_eq!(r.origin, point_i(0, 0, 0));
+            assert_eq!(r.direction, vector(0.66519, 0.33259, -0.66851));
+        }
+
+        #[test]
+        fn ray_when_camera_is_transformed() {
+            let c = camera(201, 101, PI / 2.0).set_transform(rotation_y(PI / 4.0) * translation_i(0, -2, 5)).unwrap();
+            let r = c.ray_for_pixel(100.5, 50.5);
+            assert_eq!(r.origin, point_i(0, 2, -5));
+            assert_eq!(r.direction, vector(f64::sqrt(2.0) / 2.0, 0.0, -f64::sqrt(2.0) / 2.0));
+        }
+
+        #[test]
+        fn view_transform_for_default_orientation() {
+            let t = view_transform(point_i(0, 0, 0), point_i(0, 0, -1), vector_i(0, 1, 0));
+            assert_eq!(t, identity());
+        }
+
+        #[test]
+        fn view_transform_looking_in_positive_z() {
+            let t = view_transform(point_i(0, 0, 0), point_i(0, 0, 1), vector_i(0, 1, 0));
+            assert_eq!(t, scaling_i(-1, 1, -1));
+        }
+
+        #[test]
+        fn arbitrary_view_transform() {
+            let t = view_transform(point_i(1, 3, 2), point_i(4, -2, 8), vector_i(1, 1, 0));
+            let expected = [
+                [-0.50709, 0.50709, 0.67612, -2.36643],
+                [0.76772, 0.60609, 0.12122, -2.82843],
+                [-0.35857, 0.59761, -0.71714, 0.00000],
+                [0.00000, 0.00000, 0.00000, 1.00000],
+            ];
+            for (row, expected_row) in expected.iter().enumerate() {
+                for (column, value) in expected_row.iter().enumerate() {
+                    assert!((t.data[row][column] - value).abs() < 1e-4);
+                }
+            }
+        }
+
+        #[test]
+        fn view_transform_moves_the_world() {
+            let t = view_transform(point_i(0, 0, 8), point_i(0, 0, 0), vector_i(0, 1, 0));
+            assert_eq!(t, translation_i(0, 0, -8));
+        }
+
+        #[test]
+        fn pinhole_camera_ignores_the_lens_sample() {
+            let c = camera(201, 101, PI / 2.0);
+            let r1 = c.ray_through_lens(20.5, 30.5, 0.0, 0.9);
+            let r2 = c.ray_for_pixel(20.5, 30.5);
+            assert_eq!(r1.origin, r2.origin);
+            assert_eq!(r1.direction, r2.direction);
+        }
+
+        #[test]
+        fn rays_through_the_lens_meet_on_the_focal_plane() {
+            let c = camera(201, 101, PI / 2.0)
+                .set_transform(translation_i(0, 0, -3)).unwrap()
+                .set_lens(0.5, 4.0, Bokeh::Disk);
+            let centre = c.ray_for_pixel(40.5, 20.5);
+            for (u, v) in [(0.1, 0.2), (0.9, 0.5), (0.3, 0.95)] {
+                let r = c.ray_through_lens(40.5, 20.5, u, v);
+                assert!((r.origin - point_i(0, 0, 3)).magnitude() <= 0.5 + 1e-9);
+                assert_ne!(r.origin, centre.origin);
+
+                // distance along each ray until it reaches the focal plane at z = -1
+                let t_lens = (-1.0 - r.origin.z) / r.direction.z;
+                let t_centre = (-1.0 - centre.origin.z) / centre.direction.z;
+                let focus = r.position(t_lens);
+                let expected = centre.position(t_centre);
+                assert!((focus - expected).magnitude() < 1e-9);
+            }
+        }
+
+        #[test]
+        fn disk_samples_stay_inside_the_unit_circle() {
+            for i in 0..10 {
+                for j in 0..10 {
+                    let (x, y) = Bokeh::Disk.sample(i as f64 / 9.0, j as f64 / 9.0);
+                    assert!(x * x + y * y <= 1.0 + 1e-9);
+                }
+            }
+            let (x, y) = Bokeh::Disk.sample(1.0, 0.5);
+            assert!((x - 1.0).abs() < 1e-9 && y.abs() < 1e-9);
+            assert_eq!(Bokeh::Disk.sample(0.5, 0.5), (0.0, 0.0));
+        }
+
+        #[test]
+        fn polygon_samples_stay_inside_the_polygon() {
+            // four blades without rotation form a diamond with corners on the axes
+            let bokeh = Bokeh::Polygon { blades: 4, rotation: 0.0 };
+            for i in 0..20 {
+                for j in 0..20 {
+                    let (x, y) = bokeh.sample(i as f64 / 20.0, j as f64 / 20.0);
+                    assert!(x.abs() + y.abs() <= 1.0 + 1e-9);
+                }
+            }
+            assert_eq!(bokeh.sample(0.0, 0.0), (0.0, 0.0));
+            let (x, y) = bokeh.sample(0.25 - 1e-12, 0.0);
+            assert!((x - 1.0).abs() < 1e-9 && y.abs() < 1e-9);
+        }
+
+        #[test]
+        fn rendering_through_a_lens_is_deterministic() {
+            let c = camera(8, 8, PI / 2.0).set_lens(0.2, 3.0, Bokeh::Polygon { blades: 6, rotation: 0.3 });
+            let trace = |r: &Ray| colour(r.origin.x.abs(), r.origin.y.abs(), 0.0);
+            let first = c.render(&supersampling(SamplePattern::Jittered, 4, Filter::Box), 3, trace);
+            let second = c.render(&supersampling(SamplePattern::Jittered, 4, Filter::Box), 1, trace);
+            assert!(first.pixels.iter().any(|pixel| pixel.red > 0.0));
+            for (a, b) in first.pixels.iter().zip(second.pixels.iter()) {
+                assert_eq!(a.red, b.red);
+                assert_eq!(a.green, b.green);
+            }
+        }
+
+        #[test]
+        fn rendering_with_and_without_supersampling() {
+            let c = camera(11, 11, PI / 2.0).set_transform(view_transform(point(0.0, 0.0, -5.0), point_i(0, 0, 0), vector_i(0, 1, 0))).unwrap();
+            // the left half of the view is white, the right half black
+            let trace = |r: &Ray| if r.direction.x < 0.0 { colour(1.0, 1.0, 1.0) } else { BLACK };
+
+            let aliased = c.render(&NO_SUPERSAMPLING, 2, trace);
+            let antialiased = c.render(&supersampling(SamplePattern::Grid, 16, Filter::Box), 2, trace);
+            assert_eq!(aliased.pixel_at(0, 5).red, 1.0);
+            assert_eq!(aliased.pixel_at(10, 5).red, 0.0);
+            // the centre column straddles the edge
+            assert!((antialiased.pixel_at(5, 5).red - 0.5).abs() < 1e-9);
+        }
+
+        #[test]
+        fn setting_the_transform_sets_its_inverse() {
+            let c = camera(11, 11, PI / 2.0).set_transform(translation_i(1, 2, 3)).unwrap().set_size(20, 10);
+            assert_eq!(c.transform().data, translation_i(1, 2, 3).data);
+            assert_eq!(c.inverse_transform().data, translation_i(-1, -2, -3).data);
+        }
+
+        #[test]
+        fn looking_along_the_up_vector_is_rejected() {
+            let transform = view_transform(point_i(0, 0, 0), point_i(0, 5, 0), vector_i(0, 1, 0));
+            assert_eq!(camera(11, 11, PI / 2.0).set_transform(transform), Err(Error::SingularTransform));
+        }
+    }
+}
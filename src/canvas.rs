@@ -1,24 +1,102 @@
 use std::ops::Add;
 
 use crate::colour::{BLACK, Colour};
+use crate::tone_mapping::{LINEAR_OUTPUT, OutputTransform};
 
 pub struct Canvas {
     pub width: u32,
     pub height: u32,
-    pub pixels: Vec<Vec<Colour>>,
+    // row-major, pixel (x, y) lives at index y * width + x
+    pub pixels: Vec<Colour>,
+    // applied to every pixel before it is quantized to 8 bits
+    pub output: OutputTransform,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct PixelOutOfBounds {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
 }
 
 pub fn canvas(width: u32, height: u32) -> Canvas {
     Canvas {
         width,
         height,
-        pixels: vec![vec![BLACK; width as usize]; height as usize],
+        pixels: vec![BLACK; width as usize * height as usize],
+        output: LINEAR_OUTPUT,
     }
 }
 
 impl Canvas {
-    pub fn write_pixel(&mut self, x: u32, y: u32, colour: Colour) {
-        self.pixels[y as usize][x as usize] = colour
+    pub fn pixel_at(&self, x: u32, y: u32) -> Colour {
+        self.pixels[self.index(x, y)]
+    }
+
+    pub fn write_pixel(&mut self, x: u32, y: u32, colour: Colour) -> Result<(), PixelOutOfBounds> {
+        if x >= self.width || y >= self.height {
+            return Err(PixelOutOfBounds { x, y, width: self.width, height: self.height });
+        }
+        let index = self.index(x, y);
+        self.pixels[index] = colour;
+        Ok(())
+    }
+
+    pub fn row(&self, y: u32) -> &[Colour] {
+        let start = self.index(0, y);
+        &self.pixels[start..start + self.width as usize]
+    }
+
+    pub fn row_mut(&mut self, y: u32) -> &mut [Colour] {
+        let start = self.index(0, y);
+        let width = self.width as usize;
+        &mut self.pixels[start..start + width]
+    }
+
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item=&[Colour]> {
+        self.pixels.chunks(self.width.max(1) as usize)
+    }
+
+    // splits the canvas into non-overlapping tiles, the tiles on the right and bottom edges are smaller
+    // if the canvas size is not a multiple of the tile size
+    pub fn tiles_mut(&mut self, tile_width: u32, tile_height: u32) -> Vec<Tile<'_>> {
+        let tile_width = tile_width.max(1);
+        let tile_height = tile_height.max(1);
+        let mut tiles: Vec<Tile> = Vec::new();
+        if self.width == 0 {
+            return tiles;
+        }
+        let tiles_per_row = self.width.div_ceil(tile_width) as usize;
+
+        for (y, row) in self.pixels.chunks_mut(self.width as usize).enumerate() {
+            let y = y as u32;
+            if y.is_multiple_of(tile_height) {
+                for tile_x in 0..tiles_per_row as u32 {
+                    let x = tile_x * tile_width;
+                    tiles.push(Tile {
+                        x,
+                        y,
+                        width: tile_width.min(self.width - x),
+                        height: tile_height.min(self.height - y),
+                        rows: Vec::new(),
+                    });
+                }
+            }
+            let first_tile = tiles.len() - tiles_per_row;
+            let mut remainder = row;
+            for tile in &mut tiles[first_tile..] {
+                let (segment, rest) = remainder.split_at_mut(tile.width as usize);
+                tile.rows.push(segment);
+                remainder = rest;
+            }
+        }
+        tiles
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        assert!(x < self.width && y < self.height, "Pixel ({}, {}) is outside of the {}x{} canvas.", x, y, self.width, self.height);
+        y as usize * self.width as usize + x as usize
     }
 
     pub fn to_ppm(&self) -> String {
@@ -30,10 +108,10 @@ impl Canvas {
         ppm = ppm.add("\n");
         ppm = ppm.add("255\n");
 
-        for height in 0..self.height as usize {
+        for row in self.rows() {
             let mut line_contents = String::new();
-            for width in 0..self.width as usize {
-                line_contents = line_contents.add(&self.pixels[height][width].to_string() as &str)
+            for pixel in row {
+                line_contents = line_contents.add(&self.output.apply(*pixel).to_string() as &str)
             }
             if line_contents.len() > 70 {
                 let mut line = String::new();
@@ -65,3 +143,197 @@ impl Canvas {
         return ppm;
     }
 }
+
+// a mutable view onto a rectangular region of a canvas, pixel coordinates are relative to the tile
+pub struct Tile<'a> {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    rows: Vec<&'a mut [Colour]>,
+}
+
+impl Tile<'_> {
+    pub fn pixel_at(&self, x: u32, y: u32) -> Colour {
+        self.rows[y as usize][x as usize]
+    }
+
+    pub fn write_pixel(&mut self, x: u32, y: u32, colour: Colour) -> Result<(), PixelOutOfBounds> {
+        if x >= self.width || y >= self.height {
+            return Err(PixelOutOfBounds { x, y, width: self.width, height: self.height });
+        }
+        self.rows[y as usize][x as usize] = colour;
+        Ok(())
+    }
+
+    pub fn row_mut(&mut self, y: u32) -> &mut [Colour] {
+        self.rows[y as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod ppm_tests {
+        use crate::canvas::canvas;
+        use crate::colour::colour;
+
+        #[test]
+        fn ppm_is_terminated_by_newline() {
+            let c = canvas(5, 3);
+            let ppm = c.to_ppm();
+            assert!(ppm.ends_with("\n"))
+        }
+
+        #[test]
+        fn splitting_long_lines_in_ppm() {
+            let mut c = canvas(10, 2);
+            for y in 0..2 {
+                for x in 0..10 {
+                    c.write_pixel(x, y, colour(1.0, 0.8, 0.6)).unwrap();
+                }
+            }
+            let ppm = c.to_ppm();
+
+            let mut line_iterator = ppm.lines();
+            let fourth_line = line_iterator.nth(3).unwrap();
+            let fifth_line = line_iterator.nth(0).unwrap();
+            let sixth_line = line_iterator.nth(0).unwrap();
+            let seventh_line = line_iterator.nth(0).unwrap();
+
+            assert_eq!(fourth_line, "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204");
+            assert_eq!(fifth_line, "153 255 204 153 255 204 153 255 204 153 255 204 153");
+            assert_eq!(sixth_line, "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204");
+            assert_eq!(seventh_line, "153 255 204 153 255 204 153 255 204 153 255 204 153");
+        }
+
+        #[test]
+        fn constructing_ppm_pixel_data() {
+            let mut c = canvas(5, 3);
+            let colour1 = colour(1.5, 0.0, 0.0);
+            let colour2 = colour(0.0, 0.5, 0.0);
+            let colour3 = colour(-0.5, 0.0, 1.0);
+            c.write_pixel(0, 0, colour1).unwrap();
+            c.write_pixel(2, 1, colour2).unwrap();
+            c.write_pixel(4, 2, colour3).unwrap();
+
+            let ppm = c.to_ppm();
+            let mut line_iterator = ppm.lines();
+            let fourth_line = line_iterator.nth(3).unwrap();
+            let fifth_line = line_iterator.nth(0).unwrap();
+            let sixth_line = line_iterator.nth(0).unwrap();
+
+            assert_eq!(fourth_line, "255 0 0 0 0 0 0 0 0 0 0 0 0 0 0");
+            assert_eq!(fifth_line, "0 0 0 0 0 0 0 128 0 0 0 0 0 0 0");
+            assert_eq!(sixth_line, "0 0 0 0 0 0 0 0 0 0 0 0 0 0 255");
+        }
+
+        #[test]
+        fn constructing_ppm_header() {
+            let c = canvas(5, 3);
+            let ppm = c.to_ppm();
+
+            let mut line_iterator = ppm.lines();
+            let first_line = line_iterator.nth(0).unwrap();
+            let second_line = line_iterator.nth(0).unwrap();
+            let third_line = line_iterator.nth(0).unwrap();
+            assert_eq!(first_line, "P3");
+            assert_eq!(second_line, "5 3");
+            assert_eq!(third_line, "255");
+        }
+    }
+
+    mod canvas_tests {
+        use crate::canvas::{canvas, PixelOutOfBounds};
+        use crate::colour::{BLACK, colour};
+
+        #[test]
+        fn creating_a_canvas() {
+            let c = canvas(10, 20);
+            assert_eq!(c.width, 10);
+            assert_eq!(c.height, 20);
+
+            for i in 0..10 {
+                for j in 0..10 {
+                    assert_eq!(c.pixel_at(i, j), BLACK);
+                }
+            }
+        }
+
+        #[test]
+        fn writing_pixels_into_canvas() {
+            let mut c = canvas(10, 20);
+            let red = colour(1.0, 0.0, 0.0);
+            c.write_pixel(2, 3, red).unwrap();
+            assert_eq!(c.pixel_at(2, 3), red)
+        }
+
+        #[test]
+        fn pixels_are_stored_contiguously_row_by_row() {
+            let mut c = canvas(4, 3);
+            c.write_pixel(1, 2, colour(0.5, 0.5, 0.5)).unwrap();
+            assert_eq!(c.pixels.len(), 12);
+            assert_eq!(c.pixels[2 * 4 + 1].red, 0.5);
+        }
+
+        #[test]
+        fn writing_pixels_outside_of_canvas_fails() {
+            let mut c = canvas(10, 20);
+            let result = c.write_pixel(10, 3, colour(1.0, 0.0, 0.0));
+            assert_eq!(result, Err(PixelOutOfBounds { x: 10, y: 3, width: 10, height: 20 }));
+            assert!(c.write_pixel(0, 20, colour(1.0, 0.0, 0.0)).is_err());
+        }
+
+        #[test]
+        fn reading_and_writing_rows() {
+            let mut c = canvas(3, 2);
+            c.row_mut(1)[2] = colour(0.0, 1.0, 0.0);
+            assert_eq!(c.row(1).len(), 3);
+            assert_eq!(c.row(1)[2].green, 1.0);
+            assert_eq!(c.pixel_at(2, 1).green, 1.0);
+            assert_eq!(c.rows().count(), 2);
+        }
+
+        #[test]
+        fn tiles_cover_the_whole_canvas_without_overlap() {
+            let mut c = canvas(10, 7);
+            let tiles = c.tiles_mut(4, 3);
+            assert_eq!(tiles.len(), 9);
+            let covered: u32 = tiles.iter().map(|tile| tile.width * tile.height).sum();
+            assert_eq!(covered, 70);
+
+            let last = &tiles[8];
+            assert_eq!((last.x, last.y, last.width, last.height), (8, 6, 2, 1));
+        }
+
+        #[test]
+        fn writing_through_tiles_from_multiple_threads() {
+            let mut c = canvas(5, 5);
+            std::thread::scope(|scope| {
+                for mut tile in c.tiles_mut(2, 2) {
+                    scope.spawn(move || {
+                        for y in 0..tile.height {
+                            for x in 0..tile.width {
+                                let value = (tile.x + x + (tile.y + y) * 5) as f64;
+                                tile.write_pixel(x, y, colour(value, 0.0, 0.0)).unwrap();
+                            }
+                        }
+                    });
+                }
+            });
+            for y in 0..5 {
+                for x in 0..5 {
+                    assert_eq!(c.pixel_at(x, y).red, (x + y * 5) as f64);
+                }
+            }
+        }
+
+        #[test]
+        fn writing_outside_of_a_tile_fails() {
+            let mut c = canvas(5, 5);
+            let mut tiles = c.tiles_mut(3, 3);
+            assert!(tiles[3].write_pixel(2, 0, colour(1.0, 1.0, 1.0)).is_err());
+            assert!(tiles[3].write_pixel(1, 1, colour(1.0, 1.0, 1.0)).is_ok());
+            assert_eq!(tiles[3].pixel_at(1, 1).red, 1.0);
+        }
+    }
+}
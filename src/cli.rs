@@ -0,0 +1,439 @@
+use std::f64::consts::PI;
+use std::time::Instant;
+
+use renderer::camera::Projection;
+use renderer::environment::Environment;
+use crate::experiments::clock::render_clock;
+use crate::experiments::first_sphere_image::{render_basic_sphere_multithreaded, render_basic_sphere_singlethreaded};
+use crate::experiments::motion_blur::render_motion_blurred_projectile;
+use crate::experiments::progressive_path_tracing::render_progressive_path_tracing;
+use crate::experiments::projectile::run_projectile_simulation;
+use renderer::integrator::{Integrator, path_tracing};
+use renderer::lights::Light;
+use renderer::objects::Object;
+use renderer::sampling::{Filter, NO_SUPERSAMPLING, SamplePattern, supersampling};
+use renderer::scene::{load_scene, Scene};
+use renderer::tone_mapping::{output_transform, ToneMapping};
+use renderer::util::write_file;
+
+pub const USAGE: &str = "\
+Usage: renderer <command> [options]
+
+Commands:
+  render <scene>        render a scene file to an image
+  experiments <name>    run one of the built in demos
+  info <scene>          print statistics about a scene file
+  help [command]        show this message or the options of a command
+
+Run 'renderer help <command>' for the options of a command.";
+
+const RENDER_USAGE: &str = "\
+Usage: renderer render <scene> [options]
+
+Scene files ending in .json are read as json, everything else as yaml.
+
+Options:
+  -o, --output <file>     image to write, .png, .ppm, .hdr or .pfm (default: out.png)
+      --width <pixels>    overrides the width of the scene's camera
+      --height <pixels>   overrides the height of the scene's camera
+      --samples <count>   samples per pixel, rounded up to a square number (default: 1)
+      --threads <count>   render threads, 0 uses every core (default: 0)
+      --integrator <name> 'whitted' or 'path' (default: whitted)
+      --max-depth <count> bounces per path for the path tracer (default: 5)
+      --exposure <stops>  brightens or darkens png and ppm output (default: 0)
+      --tone-map <name>   'clamp', 'reinhard' or 'aces' for png and ppm output (default: clamp)
+  -h, --help              show this message";
+
+const EXPERIMENTS_USAGE: &str = "\
+Usage: renderer experiments <name>
+
+Names:
+  sphere                 the first sphere, single threaded
+  sphere-multithreaded   the first sphere, supersampled on every core
+  clock                  the hours of a clock face
+  projectile             the trajectory of a projectile
+  motion-blur            a motion blurred projectile
+  progressive            a path traced scene that improves on every run";
+
+const INFO_USAGE: &str = "\
+Usage: renderer info <scene>
+
+Prints the camera, the number of objects and lights and the environment of a scene file.";
+
+const EXPERIMENTS: [&str; 6] = ["sphere", "sphere-multithreaded", "clock", "projectile", "motion-blur", "progressive"];
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Command {
+    // the usage text to print
+    Help(&'static str),
+    Render(RenderOptions),
+    Experiment(String),
+    Info(String),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct RenderOptions {
+    pub scene: String,
+    pub output: String,
+    // from the extension of `output`, checked before anything is rendered
+    pub format: ImageFormat,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub samples: u32,
+    pub threads: u32,
+    pub integrator: Integrator,
+    pub exposure: f64,
+    pub tone_mapping: ToneMapping,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ImageFormat {
+    Png,
+    Ppm,
+    Hdr,
+    Pfm,
+}
+
+// `arguments` without the program name, errors are problems with the arguments themselves
+pub fn parse_arguments(arguments: &[String]) -> Result<Command, String> {
+    let Some(command) = arguments.first() else {
+        return Err(String::from("Missing command."));
+    };
+    let rest = &arguments[1..];
+    match command.as_str() {
+        "-h" | "--help" => Ok(Command::Help(USAGE)),
+        "help" => match rest.first().map(String::as_str) {
+            None => Ok(Command::Help(USAGE)),
+            Some("render") => Ok(Command::Help(RENDER_USAGE)),
+            Some("experiments") => Ok(Command::Help(EXPERIMENTS_USAGE)),
+            Some("info") => Ok(Command::Help(INFO_USAGE)),
+            Some(other) => Err(format!("Unknown command '{}'.", other)),
+        },
+        "render" => parse_render(rest),
+        "experiments" => {
+            if wants_help(rest) {
+                return Ok(Command::Help(EXPERIMENTS_USAGE));
+            }
+            let name = single_positional(rest, "experiment name")?;
+            if !EXPERIMENTS.contains(&name.as_str()) {
+                return Err(format!("Unknown experiment '{}', expected one of: {}.", name, EXPERIMENTS.join(", ")));
+            }
+            Ok(Command::Experiment(name))
+        }
+        "info" => {
+            if wants_help(rest) {
+                return Ok(Command::Help(INFO_USAGE));
+            }
+            Ok(Command::Info(single_positional(rest, "scene file")?))
+        }
+        other => Err(format!("Unknown command '{}'.", other)),
+    }
+}
+
+fn wants_help(arguments: &[String]) -> bool {
+    arguments.iter().any(|argument| argument == "-h" || argument == "--help")
+}
+
+fn single_positional(arguments: &[String], name: &str) -> Result<String, String> {
+    match arguments {
+        [] => Err(format!("Missing {}.", name)),
+        [argument] if argument.starts_with('-') => Err(format!("Unknown option '{}'.", argument)),
+        [argument] => Ok(argument.clone()),
+        [_, extra, ..] => Err(format!("Unexpected argument '{}'.", extra)),
+    }
+}
+
+fn parse_render(arguments: &[String]) -> Result<Command, String> {
+    if wants_help(arguments) {
+        return Ok(Command::Help(RENDER_USAGE));
+    }
+    let mut scene = None;
+    let mut options = RenderOptions {
+        scene: String::new(),
+        output: String::from("out.png"),
+        format: ImageFormat::Png,
+        width: None,
+        height: None,
+        samples: 1,
+        threads: 0,
+        integrator: Integrator::Whitted,
+        exposure: 0.0,
+        tone_mapping: ToneMapping::Clamp,
+    };
+    let mut integrator = "whitted";
+    let mut max_depth = 5;
+
+    let mut remaining = arguments.iter();
+    while let Some(argument) = remaining.next() {
+        if !argument.starts_with('-') {
+            if scene.is_some() {
+                return Err(format!("Unexpected argument '{}'.", argument));
+            }
+            scene = Some(argument.clone());
+            continue;
+        }
+        // both '--width 100' and '--width=100' are accepted
+        let (name, inline_value) = match argument.split_once('=') {
+            Some((name, value)) => (name, Some(value.to_string())),
+            None => (argument.as_str(), None),
+        };
+        let mut value = || inline_value.clone().or_else(|| remaining.next().cloned())
+            .ok_or_else(|| format!("Missing value for '{}'.", name));
+        match name {
+            "-o" | "--output" => options.output = value()?,
+            "--width" => options.width = Some(positive(name, &value()?)?),
+            "--height" => options.height = Some(positive(name, &value()?)?),
+            "--samples" => options.samples = positive(name, &value()?)?,
+            "--threads" => options.threads = parse_number(name, &value()?)?,
+            "--max-depth" => max_depth = parse_number(name, &value()?)?,
+            "--exposure" => options.exposure = parse_number(name, &value()?)?,
+            "--integrator" => {
+                integrator = match value()?.as_str() {
+                    "whitted" => "whitted",
+                    "path" => "path",
+                    other => return Err(format!("Invalid value '{}' for '{}', expected 'whitted' or 'path'.", other, name)),
+                }
+            }
+            "--tone-map" => {
+                options.tone_mapping = match value()?.as_str() {
+                    "clamp" => ToneMapping::Clamp,
+                    "reinhard" => ToneMapping::Reinhard,
+                    "aces" => ToneMapping::AcesFilmic,
+                    other => return Err(format!("Invalid value '{}' for '{}', expected 'clamp', 'reinhard' or 'aces'.", other, name)),
+                }
+            }
+            _ => return Err(format!("Unknown option '{}'.", name)),
+        }
+    }
+    options.scene = scene.ok_or("Missing scene file.")?;
+    options.format = image_format(&options.output)?;
+    if integrator == "path" {
+        options.integrator = path_tracing(1, max_depth);
+    }
+    Ok(Command::Render(options))
+}
+
+fn image_format(output: &str) -> Result<ImageFormat, String> {
+    let lowercase = output.to_lowercase();
+    let formats = [(".png", ImageFormat::Png), (".ppm", ImageFormat::Ppm), (".hdr", ImageFormat::Hdr), (".pfm", ImageFormat::Pfm)];
+    formats.iter().find(|(extension, _)| lowercase.ends_with(extension)).map(|(_, format)| *format)
+        .ok_or_else(|| format!("Unknown image format of '{}', expected .png, .ppm, .hdr or .pfm.", output))
+}
+
+fn parse_number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("Invalid value '{}' for '{}'.", value, name))
+}
+
+fn positive(name: &str, value: &str) -> Result<u32, String> {
+    match parse_number(name, value)? {
+        0 => Err(format!("'{}' must be at least 1.", name)),
+        number => Ok(number),
+    }
+}
+
+// errors are failures while running the command, like unreadable scene files
+pub fn run(command: &Command) -> Result<(), String> {
+    match command {
+        Command::Help(usage) => println!("{}", usage),
+        Command::Render(options) => render(options)?,
+        Command::Experiment(name) => match name.as_str() {
+            "sphere" => render_basic_sphere_singlethreaded(),
+            "sphere-multithreaded" => render_basic_sphere_multithreaded(0),
+            "clock" => render_clock(),
+            "projectile" => run_projectile_simulation(),
+            "motion-blur" => render_motion_blurred_projectile(),
+            "progressive" => render_progressive_path_tracing(),
+            other => return Err(format!("Unknown experiment '{}'.", other)),
+        }.map_err(|error| error.to_string())?,
+        Command::Info(path) => print!("{}", scene_info(&load_scene(path).map_err(|error| error.to_string())?)),
+    }
+    Ok(())
+}
+
+fn render(options: &RenderOptions) -> Result<(), String> {
+    let start = Instant::now();
+    let scene = load_scene(&options.scene).map_err(|error| error.to_string())?;
+    let camera = scene.camera.set_size(options.width.unwrap_or(scene.camera.hsize), options.height.unwrap_or(scene.camera.vsize));
+    let sampling = if options.samples > 1 {
+        supersampling(SamplePattern::Sobol, options.samples, Filter::Box)
+    } else {
+        NO_SUPERSAMPLING
+    };
+
+    let mut image = camera.render_world(&scene.world, &sampling, &options.integrator, options.threads);
+    let data = match options.format {
+        ImageFormat::Hdr => image.to_hdr(),
+        ImageFormat::Pfm => image.to_pfm(),
+        ImageFormat::Ppm | ImageFormat::Png => {
+            image.output = output_transform(options.exposure, options.tone_mapping, true);
+            if options.format == ImageFormat::Ppm { image.to_ppm().into_bytes() } else { image.to_png() }
+        }
+    };
+    write_file(&options.output, data).map_err(|error| error.to_string())?;
+    println!("Rendered {}x{} to '{}' in {:.2?}.", camera.hsize, camera.vsize, options.output, start.elapsed());
+    Ok(())
+}
+
+pub fn scene_info(scene: &Scene) -> String {
+    let camera = &scene.camera;
+    let projection = match camera.projection {
+        Projection::Perspective => "perspective",
+        Projection::Orthographic { .. } => "orthographic",
+        Projection::Fisheye => "fisheye",
+        Projection::Equirectangular => "equirectangular",
+    };
+    let objects = &scene.world.objects;
+    let spheres = objects.iter().filter(|object| matches!(object, Object::Sphere { .. })).count();
+    let emissive = scene.world.emitters().len();
+    let moving = objects.iter().filter(|object| match object {
+        Object::Sphere { end_transformation, .. } |
+        Object::Triangle { end_transformation, .. } => end_transformation.is_some(),
+    }).count();
+
+    let lights = &scene.world.lights;
+    let count = |kind: fn(&Light) -> bool| lights.iter().filter(|light| kind(light)).count();
+    let light_kinds = [
+        ("point", count(|light| matches!(light, Light::Point { .. }))),
+        ("directional", count(|light| matches!(light, Light::Directional { .. }))),
+        ("spot", count(|light| matches!(light, Light::Spot { .. }))),
+        ("area", count(|light| matches!(light, Light::Rectangle { .. }))),
+        ("spherical", count(|light| matches!(light, Light::Spherical { .. }))),
+    ];
+    let light_summary: Vec<String> = light_kinds.iter().filter(|(_, count)| *count > 0)
+        .map(|(kind, count)| format!("{} {}", count, kind)).collect();
+
+    let environment = match &scene.world.environment {
+        Environment::Constant(colour) => format!("constant ({}, {}, {})", colour.red, colour.green, colour.blue),
+        Environment::Gradient { .. } => String::from("gradient"),
+        Environment::Image(map) => format!("{}x{} panorama", map.width, map.height),
+        Environment::Sky { sky, .. } => format!("sky with turbidity {}, sun {:.1} degrees above the horizon",
+                                                sky.turbidity, sky.sun_direction.y.clamp(-1.0, 1.0).asin() * 180.0 / PI),
+    };
+
+    let mut info = String::new();
+    info.push_str(&format!("Camera: {}x{}, {} with a field of view of {:.1} degrees\n", camera.hsize, camera.vsize,
+                           projection, camera.field_of_view * 180.0 / PI));
+    info.push_str(&format!("Objects: {} ({} spheres, {} triangles, {} emissive, {} moving)\n", objects.len(), spheres,
+                           objects.len() - spheres, emissive, moving));
+    if light_summary.is_empty() {
+        info.push_str("Lights: 0\n");
+    } else {
+        info.push_str(&format!("Lights: {} ({})\n", lights.len(), light_summary.join(", ")));
+    }
+    info.push_str(&format!("Environment: {}\n", environment));
+    info
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::{Command, ImageFormat, parse_arguments, run, scene_info, USAGE};
+    use renderer::integrator::{Integrator, path_tracing};
+    use renderer::scene::parse_scene;
+    use renderer::tone_mapping::ToneMapping;
+    use renderer::util::{read_file, write_file};
+
+    const SCENE: &str = "
+- add: camera
+  width: 8
+  height: 4
+  field-of-view: pi/2
+  from: [0, 0, -5]
+  to: [0, 0, 0]
+  up: [0, 1, 0]
+- add: light
+  at: [-10, 10, -10]
+  intensity: [1, 1, 1]
+- add: spot-light
+  at: [0, 10, 0]
+  direction: [0, -1, 0]
+  inner-angle: 0.1
+  outer-angle: 0.2
+  intensity: [1, 1, 1]
+- add: sphere
+- add: triangle
+  p1: [0, 0, 0]
+  p2: [1, 0, 0]
+  p3: [0, 1, 0]
+  material:
+    emission: [1, 1, 1]
+";
+
+    fn arguments(text: &str) -> Vec<String> {
+        text.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parsing_render_options() {
+        let Ok(Command::Render(options)) = parse_arguments(&arguments("render scene.yml")) else { panic!("expected render") };
+        assert_eq!((options.scene.as_str(), options.output.as_str()), ("scene.yml", "out.png"));
+        assert_eq!((options.width, options.samples, options.threads), (None, 1, 0));
+        assert_eq!(options.integrator, Integrator::Whitted);
+
+        let command = parse_arguments(&arguments(
+            "render -o x.hdr --width 20 --height=10 scene.json --samples 9 --threads 2 --integrator path --max-depth 3 --exposure -1.5 --tone-map aces"));
+        let Ok(Command::Render(options)) = command else { panic!("expected render") };
+        assert_eq!((options.output.as_str(), options.width, options.height), ("x.hdr", Some(20), Some(10)));
+        assert_eq!(options.format, ImageFormat::Hdr);
+        assert_eq!((options.samples, options.threads, options.exposure), (9, 2, -1.5));
+        assert_eq!(options.integrator, path_tracing(1, 3));
+        assert_eq!(options.tone_mapping, ToneMapping::AcesFilmic);
+    }
+
+    #[test]
+    fn parsing_other_commands() {
+        assert_eq!(parse_arguments(&arguments("--help")), Ok(Command::Help(USAGE)));
+        assert_eq!(parse_arguments(&arguments("help")), Ok(Command::Help(USAGE)));
+        assert!(matches!(parse_arguments(&arguments("render --width 5 -h")), Ok(Command::Help(text)) if text.contains("--samples")));
+        assert!(matches!(parse_arguments(&arguments("help info")), Ok(Command::Help(text)) if text.contains("info <scene>")));
+        assert_eq!(parse_arguments(&arguments("experiments clock")), Ok(Command::Experiment(String::from("clock"))));
+        assert_eq!(parse_arguments(&arguments("info a.yml")), Ok(Command::Info(String::from("a.yml"))));
+    }
+
+    #[test]
+    fn argument_errors() {
+        let error = |text: &str| parse_arguments(&arguments(text)).unwrap_err();
+        assert_eq!(error(""), "Missing command.");
+        assert_eq!(error("draw"), "Unknown command 'draw'.");
+        assert_eq!(error("render"), "Missing scene file.");
+        assert_eq!(error("render a.yml b.yml"), "Unexpected argument 'b.yml'.");
+        assert_eq!(error("render a.yml --width"), "Missing value for '--width'.");
+        assert_eq!(error("render a.yml --width ten"), "Invalid value 'ten' for '--width'.");
+        assert_eq!(error("render a.yml --samples 0"), "'--samples' must be at least 1.");
+        assert_eq!(error("render a.yml --fast"), "Unknown option '--fast'.");
+        assert_eq!(error("render a.yml -o out.bmp"), "Unknown image format of 'out.bmp', expected .png, .ppm, .hdr or .pfm.");
+        assert_eq!(error("render a.yml --integrator photon"), "Invalid value 'photon' for '--integrator', expected 'whitted' or 'path'.");
+        assert_eq!(error("info"), "Missing scene file.");
+        assert_eq!(error("info a.yml b.yml"), "Unexpected argument 'b.yml'.");
+        assert!(error("experiments teapot").starts_with("Unknown experiment 'teapot'"));
+    }
+
+    #[test]
+    fn scene_statistics() {
+        let info = scene_info(&parse_scene(SCENE).unwrap());
+        assert_eq!(info, "Camera: 8x4, perspective with a field of view of 90.0 degrees\n\
+                          Objects: 2 (1 spheres, 1 triangles, 1 emissive, 0 moving)\n\
+                          Lights: 2 (1 point, 1 spot)\n\
+                          Environment: constant (0, 0, 0)\n");
+    }
+
+    #[test]
+    fn rendering_a_scene_file() {
+        let path = |extension: &str| {
+            let path = std::env::temp_dir().join(format!("cli-{}.{}", std::process::id(), extension));
+            path.to_string_lossy().into_owned()
+        };
+        let (scene, output) = (path("yml"), path("png"));
+        let written = write_file(&scene, SCENE);
+        // the paths are passed as whole arguments, the temporary directory may contain spaces
+        let mut command = vec![String::from("render"), scene.clone(), String::from("-o"), output.clone()];
+        command.extend(arguments("--width 6 --samples 4"));
+        let result = parse_arguments(&command).and_then(|command| run(&command));
+        let image = read_file(&output);
+        let _ = std::fs::remove_file(&scene);
+        let _ = std::fs::remove_file(&output);
+        written.unwrap();
+        assert_eq!(result, Ok(()));
+        assert!(image.unwrap().starts_with(b"\x89PNG"));
+        assert!(run(&Command::Info(String::from("does-not-exist.yml"))).is_err());
+    }
+}
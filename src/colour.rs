@@ -25,6 +25,12 @@ impl Colour {
         string_builder.add(" ")
     }
 
+    // largest absolute per channel difference between two colours
+    pub fn difference(&self, other: Colour) -> f64 {
+        let delta = *self - other;
+        delta.red.abs().max(delta.green.abs()).max(delta.blue.abs())
+    }
+
     fn normalize_value(value: f64) -> u32 {
         let value = value * 255.0;
         let mut value = value.round().trunc();
@@ -120,3 +126,45 @@ impl PartialEq<Colour> for Colour {
             !a_equal_b(self.green, other.green)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::colour::colour;
+
+    #[test]
+    fn creating_a_colour() {
+        let c = colour(-0.5, 0.4, 1.7);
+        assert_eq!(c.red, -0.5);
+        assert_eq!(c.green, 0.4);
+        assert_eq!(c.blue, 1.7);
+    }
+
+    #[test]
+    fn adding_colours() {
+        let c1 = colour(0.9, 0.6, 0.75);
+        let c2 = colour(0.7, 0.1, 0.25);
+        assert_eq!(c1 + c2, colour(1.6, 0.7, 1.0));
+    }
+
+    #[test]
+    fn subtracting_colours() {
+        let c1 = colour(0.9, 0.6, 0.75);
+        let c2 = colour(0.7, 0.1, 0.25);
+        assert_eq!(c1 - c2, colour(0.2, 0.5, 0.5));
+    }
+
+    #[test]
+    fn difference_between_colours() {
+        let c1 = colour(1.0, 0.2, 0.4);
+        let c2 = colour(0.9, 1.0, 0.1);
+        assert_eq!(c1.difference(c2), 0.8);
+        assert_eq!(c1.difference(c1), 0.0);
+    }
+
+    #[test]
+    fn multiplying_colours() {
+        let c1 = colour(1.0, 0.2, 0.4);
+        let c2 = colour(0.9, 1.0, 0.1);
+        assert_eq!(c1 * c2, colour(0.9, 0.2, 0.04));
+    }
+}
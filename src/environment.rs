@@ -0,0 +1,384 @@
+use std::f64::consts::PI;
+
+use crate::canvas::{canvas, Canvas};
+use crate::colour::{Colour, BLACK};
+use crate::error::Error;
+use crate::hdr::{canvas_from_hdr, canvas_from_pfm};
+use crate::sky::Sky;
+use crate::tuple::{Tuple, vector};
+use crate::util::read_file;
+
+// what rays that miss every object see
+pub enum Environment {
+    Constant(Colour),
+    // blends from `bottom` straight down to `top` straight up
+    Gradient { bottom: Colour, top: Colour },
+    // an equirectangular panorama, see `EnvironmentMap`
+    Image(EnvironmentMap),
+    // an analytic daylight sky, `map` is a coarse rendering of it that is only used for importance sampling
+    Sky { sky: Sky, map: EnvironmentMap },
+}
+
+// the top row of the image is straight up, the centre column looks along -z and `rotation` turns the panorama
+// around the y axis, in radians
+pub struct EnvironmentMap {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<Colour>,
+    pub intensity: f64,
+    pub rotation: f64,
+    // cumulative distributions for importance sampling, one over the rows and one per row over its pixels
+    row_cdf: Vec<f64>,
+    pixel_cdfs: Vec<f64>,
+}
+
+// a direction picked by `Environment::sample`, `pdf` is with respect to solid angle
+pub struct EnvironmentSample {
+    pub direction: Tuple,
+    pub radiance: Colour,
+    pub pdf: f64,
+}
+
+pub fn constant_environment(colour: Colour) -> Environment {
+    Environment::Constant(colour)
+}
+
+pub fn gradient_environment(bottom: Colour, top: Colour) -> Environment {
+    Environment::Gradient { bottom, top }
+}
+
+pub fn image_environment(image: &Canvas, intensity: f64, rotation: f64) -> Environment {
+    Environment::Image(environment_map(image, intensity, rotation))
+}
+
+pub fn sky_environment(sky: Sky) -> Environment {
+    let (width, height) = (128, 64);
+    let mut image = canvas(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let phi = ((x as f64 + 0.5) / width as f64 - 0.5) * 2.0 * PI;
+            let theta = (y as f64 + 0.5) / height as f64 * PI;
+            let direction = vector(theta.sin() * phi.sin(), theta.cos(), -theta.sin() * phi.cos());
+            image.pixels[(y * width + x) as usize] = sky.radiance(direction);
+        }
+    }
+    Environment::Sky { sky, map: environment_map(&image, 1.0, 0.0) }
+}
+
+fn environment_map(image: &Canvas, intensity: f64, rotation: f64) -> EnvironmentMap {
+    let width = image.width.max(1);
+    let height = image.height.max(1);
+    let mut pixels = image.pixels.clone();
+    pixels.resize((width * height) as usize, BLACK);
+
+    // brighter pixels and pixels closer to the horizon (which cover a larger solid angle) are picked more often
+    let mut pixel_cdfs = Vec::with_capacity(pixels.len());
+    let mut row_cdf = Vec::with_capacity(height as usize);
+    let mut total = 0.0;
+    for (y, row) in pixels.chunks(width as usize).enumerate() {
+        let sin_theta = (PI * (y as f64 + 0.5) / height as f64).sin();
+        let mut row_total = 0.0;
+        for pixel in row {
+            row_total += luminance(*pixel) * sin_theta;
+            pixel_cdfs.push(row_total);
+        }
+        total += row_total;
+        row_cdf.push(total);
+    }
+
+    EnvironmentMap {
+        width,
+        height,
+        pixels,
+        intensity,
+        rotation,
+        row_cdf,
+        pixel_cdfs,
+    }
+}
+
+// reads an equirectangular panorama from a radiance .hdr or a .pfm file
+pub fn load_environment(filepath: &str, intensity: f64, rotation: f64) -> Result<Environment, Error> {
+    let data = read_file(filepath)?;
+    let image = if filepath.to_lowercase().ends_with(".pfm") {
+        canvas_from_pfm(&data)
+    } else {
+        canvas_from_hdr(&data)
+    }?;
+    Ok(image_environment(&image, intensity, rotation))
+}
+
+impl Environment {
+    pub fn colour(&self, direction: Tuple) -> Colour {
+        match self {
+            Environment::Constant(colour) => *colour,
+            Environment::Gradient { bottom, top } => {
+                let t = (direction.normalize().y + 1.0) / 2.0;
+                *bottom * (1.0 - t) + *top * t
+            }
+            Environment::Image(map) => {
+                let (x, y) = map.pixel_for(direction);
+                map.pixels[(y * map.width + x) as usize] * map.intensity
+            }
+            Environment::Sky { sky, .. } => sky.radiance(direction),
+        }
+    }
+
+    pub fn is_black(&self) -> bool {
+        match self {
+            Environment::Constant(colour) => luminance(*colour) <= 0.0,
+            Environment::Gradient { bottom, top } => luminance(*bottom) <= 0.0 && luminance(*top) <= 0.0,
+            Environment::Image(map) |
+            Environment::Sky { map, .. } => map.intensity <= 0.0 || map.row_cdf.last().is_none_or(|total| *total <= 0.0),
+        }
+    }
+
+    // `u` and `v` in [0, 1) pick a direction, images are importance sampled and the others sampled uniformly
+    pub fn sample(&self, u: f64, v: f64) -> EnvironmentSample {
+        let direction = match self {
+            Environment::Image(map) |
+            Environment::Sky { map, .. } if !self.is_black() => map.sample_direction(u, v),
+            _ => {
+                let z = 1.0 - 2.0 * u;
+                let radius = (1.0 - z * z).max(0.0).sqrt();
+                let angle = 2.0 * PI * v;
+                vector(radius * angle.cos(), radius * angle.sin(), z)
+            }
+        };
+        EnvironmentSample {
+            direction,
+            radiance: self.colour(direction),
+            pdf: self.pdf(direction),
+        }
+    }
+
+    pub fn pdf(&self, direction: Tuple) -> f64 {
+        match self {
+            Environment::Image(map) |
+            Environment::Sky { map, .. } if !self.is_black() => map.pdf(direction),
+            _ => 1.0 / (4.0 * PI),
+        }
+    }
+}
+
+impl EnvironmentMap {
+    fn pixel_for(&self, direction: Tuple) -> (u32, u32) {
+        let direction = direction.normalize();
+        let phi = direction.x.atan2(-direction.z) - self.rotation;
+        let theta = direction.y.clamp(-1.0, 1.0).acos();
+        let u = (phi / (2.0 * PI) + 0.5).rem_euclid(1.0);
+        let v = theta / PI;
+        let x = ((u * self.width as f64) as u32).min(self.width - 1);
+        let y = ((v * self.height as f64) as u32).min(self.height - 1);
+        (x, y)
+    }
+
+    fn sample_direction(&self, u: f64, v: f64) -> Tuple {
+        let total = *self.row_cdf.last().unwrap_or(&0.0);
+        let (y, row_u) = pick(&self.row_cdf, u * total);
+        let start = (y * self.width) as usize;
+        let row = &self.pixel_cdfs[start..start + self.width as usize];
+        let before = if y == 0 { 0.0 } else { self.row_cdf[y as usize - 1] };
+        let row_total = self.row_cdf[y as usize] - before;
+        let (x, pixel_v) = pick(row, v * row_total);
+
+        // uniform within the pixel, in the angles of the panorama
+        let phi = ((x as f64 + pixel_v) / self.width as f64 - 0.5) * 2.0 * PI + self.rotation;
+        let theta = (y as f64 + row_u) / self.height as f64 * PI;
+        vector(theta.sin() * phi.sin(), theta.cos(), -theta.sin() * phi.cos())
+    }
+
+    fn pdf(&self, direction: Tuple) -> f64 {
+        let total = *self.row_cdf.last().unwrap_or(&0.0);
+        let (x, y) = self.pixel_for(direction);
+        let index = (y * self.width + x) as usize;
+        let before = if x == 0 { 0.0 } else { self.pixel_cdfs[index - 1] };
+        let weight = self.pixel_cdfs[index] - before;
+        let cos_theta = direction.normalize().y;
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        if weight <= 0.0 || sin_theta <= 0.0 {
+            return 0.0;
+        }
+        // directions are uniform in the angles within a pixel, sin(theta) converts that to solid angle
+        let pixel_area = (2.0 * PI / self.width as f64) * (PI / self.height as f64);
+        weight / total / (pixel_area * sin_theta)
+    }
+}
+
+// finds the bucket of a cumulative distribution `value` falls into, and where in the bucket it is, in [0, 1)
+fn pick(cdf: &[f64], value: f64) -> (u32, f64) {
+    let index = cdf.partition_point(|bound| *bound <= value).min(cdf.len() - 1);
+    let low = if index == 0 { 0.0 } else { cdf[index - 1] };
+    let high = cdf[index];
+    let offset = if high > low { ((value - low) / (high - low)).clamp(0.0, 1.0 - 1e-12) } else { 0.5 };
+    (index as u32, offset)
+}
+
+fn luminance(colour: Colour) -> f64 {
+    (0.2126 * colour.red + 0.7152 * colour.green + 0.0722 * colour.blue).max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::canvas::canvas;
+    use crate::colour::{BLACK, colour, WHITE};
+    use crate::environment::{constant_environment, gradient_environment, image_environment, load_environment};
+    use crate::error::Error;
+    use crate::hdr::canvas_from_hdr;
+    use crate::integrator::{Integrator, path_tracing};
+    use crate::materials::{material, pbr_material};
+    use crate::objects::sphere;
+    use crate::ray::ray;
+    use crate::tuple::{point_i, vector, vector_i};
+    use crate::util::write_file;
+    use crate::world::{default_world, world};
+
+    #[test]
+    fn default_environment_is_black() {
+        let w = default_world();
+        let c = w.colour_at(&ray(point_i(0, 0, -5), vector_i(0, 1, 0)));
+        assert_eq!((c.red, c.green, c.blue), (0.0, 0.0, 0.0));
+        assert!(w.environment.is_black());
+    }
+
+    #[test]
+    fn rays_that_miss_see_the_environment() {
+        let mut w = default_world();
+        w.environment = constant_environment(colour(0.2, 0.4, 0.6));
+        let c = w.colour_at(&ray(point_i(0, 0, -5), vector_i(0, 1, 0)));
+        assert_eq!((c.red, c.green, c.blue), (0.2, 0.4, 0.6));
+    }
+
+    #[test]
+    fn gradient_sky() {
+        let sky = gradient_environment(BLACK, colour(0.0, 0.0, 1.0));
+        assert_eq!(sky.colour(vector_i(0, 1, 0)).blue, 1.0);
+        assert_eq!(sky.colour(vector_i(0, -3, 0)).blue, 0.0);
+        assert_eq!(sky.colour(vector_i(1, 0, 0)).blue, 0.5);
+    }
+
+    #[test]
+    fn panorama_lookup() {
+        let mut image = canvas(4, 2);
+        for x in 0..4 {
+            image.write_pixel(x, 0, colour(x as f64, 1.0, 0.0)).unwrap();
+            image.write_pixel(x, 1, colour(x as f64, 0.0, 1.0)).unwrap();
+        }
+        let environment = image_environment(&image, 2.0, 0.0);
+        // -z is the centre of the image, +x a quarter to the right
+        let forward = environment.colour(vector(0.0, 0.1, -1.0));
+        assert_eq!((forward.red, forward.green, forward.blue), (4.0, 2.0, 0.0));
+        assert_eq!(environment.colour(vector(1.0, 0.1, 0.0)).red, 6.0);
+        assert_eq!(environment.colour(vector(-1.0, -0.1, 0.0)).blue, 2.0);
+        assert_eq!(environment.colour(vector(-1.0, -0.1, 0.0)).red, 2.0);
+
+        let rotated = image_environment(&image, 1.0, PI / 2.0);
+        assert_eq!(rotated.colour(vector(1.0, 0.1, 0.0)).red, 2.0);
+    }
+
+    #[test]
+    fn sampling_a_panorama_prefers_bright_pixels() {
+        let mut image = canvas(8, 4);
+        image.write_pixel(5, 1, colour(100.0, 100.0, 100.0)).unwrap();
+        image.write_pixel(0, 2, colour(1.0, 1.0, 1.0)).unwrap();
+        let environment = image_environment(&image, 1.0, 0.3);
+
+        let steps = 32;
+        let mut bright = 0;
+        for i in 0..steps {
+            for j in 0..steps {
+                let sample = environment.sample((i as f64 + 0.5) / steps as f64, (j as f64 + 0.5) / steps as f64);
+                assert!((sample.direction.magnitude() - 1.0).abs() < 1e-9);
+                assert!((sample.pdf - environment.pdf(sample.direction)).abs() < 1e-9 * sample.pdf);
+                if sample.radiance.red == 100.0 {
+                    bright += 1;
+                }
+            }
+        }
+        assert!(bright > steps * steps * 9 / 10);
+    }
+
+    #[test]
+    fn importance_sampling_estimates_the_total_light() {
+        let mut image = canvas(6, 3);
+        for (i, pixel) in image.pixels.iter_mut().enumerate() {
+            *pixel = colour(i as f64 + 1.0, 0.0, 0.0);
+        }
+        let environment = image_environment(&image, 1.0, 0.0);
+
+        // every pixel covers 2pi/6 of longitude and a band of latitude
+        let mut expected = 0.0;
+        for y in 0..3 {
+            let band = (PI * y as f64 / 3.0).cos() - (PI * (y + 1) as f64 / 3.0).cos();
+            for x in 0..6 {
+                expected += image.pixel_at(x, y).red * band * 2.0 * PI / 6.0;
+            }
+        }
+
+        let steps = 200;
+        let mut total = 0.0;
+        for i in 0..steps {
+            for j in 0..steps {
+                let sample = environment.sample((i as f64 + 0.5) / steps as f64, (j as f64 + 0.5) / steps as f64);
+                total += sample.radiance.red / sample.pdf;
+            }
+        }
+        let estimate = total / (steps * steps) as f64;
+        assert!((estimate - expected).abs() < 1e-2 * expected);
+    }
+
+    #[test]
+    fn furnace_test() {
+        for max_depth in [0, 3] {
+            let mut w = world();
+            w.environment = constant_environment(WHITE);
+            let mut grey = material();
+            grey.colour = colour(0.5, 0.5, 0.5);
+            grey.diffuse = 1.0;
+            w.objects.push(sphere().set_material(grey));
+            let c = path_tracing(4000, max_depth).colour_at(&w, &ray(point_i(0, 0, -5), vector_i(0, 0, 1)));
+            assert!((c.red - 0.5).abs() < 0.02);
+        }
+    }
+
+    #[test]
+    fn smooth_metal_reflects_the_environment() {
+        let mut w = world();
+        w.environment = gradient_environment(colour(0.0, 0.0, 0.0), colour(0.0, 0.0, 1.0));
+        let mut metal = pbr_material(WHITE, 1.0, 0.05);
+        metal.ambient = 0.0;
+        w.objects.push(sphere().set_material(metal));
+        // the top of the sphere reflects the sky straight back up
+        let r = ray(point_i(0, 5, 0), vector_i(0, -1, 0));
+        let c = path_tracing(1000, 2).colour_at(&w, &r);
+        assert!(c.blue > 0.95 && c.blue < 1.02);
+        assert!(Integrator::Whitted.colour_at(&w, &r).blue < 0.1);
+    }
+
+    #[test]
+    fn loading_a_panorama() {
+        let mut image = canvas(8, 4);
+        image.write_pixel(3, 1, colour(2.0, 4.0, 8.0)).unwrap();
+        let path = |extension: &str| {
+            let path = std::env::temp_dir().join(format!("environment-{}.{}", std::process::id(), extension));
+            path.to_string_lossy().into_owned()
+        };
+        let (pfm, hdr) = (path("pfm"), path("hdr"));
+        let written = write_file(&pfm, image.to_pfm()).and(write_file(&hdr, image.to_hdr()));
+        let from_pfm = load_environment(&pfm, 1.0, 0.0);
+        let from_hdr = load_environment(&hdr, 1.0, 0.0);
+        let _ = std::fs::remove_file(&pfm);
+        let _ = std::fs::remove_file(&hdr);
+        written.unwrap();
+        let (from_pfm, from_hdr) = (from_pfm.unwrap(), from_hdr.unwrap());
+
+        // pixel (3, 1) is just left of straight ahead, a bit above the horizon
+        let direction = vector(-0.2, 0.3, -1.0);
+        assert_eq!(from_pfm.colour(direction).blue, 8.0);
+        assert!((from_hdr.colour(direction).blue - 8.0).abs() < 0.1);
+        assert!(canvas_from_hdr(b"nonsense").is_err());
+        assert!(matches!(load_environment("does-not-exist.hdr", 1.0, 0.0), Err(Error::Io(_))));
+    }
+}
@@ -0,0 +1,58 @@
+use std::fmt;
+
+use crate::canvas::PixelOutOfBounds;
+
+// everything that can go wrong while building, loading or saving a scene
+#[derive(Debug, PartialEq, Clone)]
+pub enum Error {
+    // a transformation without an inverse, like a scale by zero, which would make every ray test against the object fail
+    SingularTransform,
+    // a file could not be read or written, the message names the file
+    Io(String),
+    // a scene, model or image file with invalid contents, the message says where if the format has lines
+    Parse(String),
+    // two images or buffers that have to be the same size are not
+    SizeMismatch(String),
+    // a scene that cannot be written out, like a panorama that does not remember its file
+    Unsupported(String),
+    PixelOutOfBounds(PixelOutOfBounds),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::SingularTransform => write!(f, "The transform cannot be inverted."),
+            Error::Io(message) | Error::Parse(message) | Error::SizeMismatch(message) | Error::Unsupported(message) =>
+                write!(f, "{}", message),
+            Error::PixelOutOfBounds(PixelOutOfBounds { x, y, width, height }) =>
+                write!(f, "Pixel ({}, {}) is outside of the {}x{} canvas.", x, y, width, height),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<PixelOutOfBounds> for Error {
+    fn from(error: PixelOutOfBounds) -> Error {
+        Error::PixelOutOfBounds(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canvas::canvas;
+    use crate::colour::WHITE;
+    use crate::error::Error;
+    use crate::util::read_file;
+
+    #[test]
+    fn errors_describe_themselves() {
+        assert_eq!(Error::SingularTransform.to_string(), "The transform cannot be inverted.");
+        let out_of_bounds: Error = canvas(4, 3).write_pixel(4, 1, WHITE).unwrap_err().into();
+        assert_eq!(out_of_bounds.to_string(), "Pixel (4, 1) is outside of the 4x3 canvas.");
+        match read_file("does-not-exist.txt") {
+            Err(Error::Io(message)) => assert!(message.starts_with("Could not read 'does-not-exist.txt': ")),
+            other => panic!("expected an i/o error, got {:?}", other),
+        }
+    }
+}
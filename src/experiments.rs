@@ -1,20 +1,21 @@
 pub mod first_sphere_image {
     use std::f64::consts::PI;
-    use std::sync::mpsc;
-    use std::thread;
     use std::time::{Instant};
 
-    use crate::canvas::{canvas, Canvas};
-    use crate::colour::{GREEN, RED};
-    use crate::matrix::identity;
-    use crate::objects::{Object, sphere};
-    use crate::ray::ray;
-    use crate::tuple::{point, point_i, Tuple};
-    use crate::util::write_file;
+    use renderer::canvas::{canvas, Canvas};
+    use renderer::colour::{BLACK, GREEN, RED};
+    use renderer::error::Error;
+    use renderer::matrix::identity;
+    use renderer::objects::{Object, sphere};
+    use renderer::parallel::{DEFAULT_TILE_SIZE, render_parallel};
+    use renderer::ray::ray;
+    use renderer::sampling::{Filter, SamplePattern, supersampling};
+    use renderer::tuple::{point, point_i, Tuple};
+    use renderer::util::write_file;
 
-    pub fn render_basic_sphere_singlethreaded() {
+    pub fn render_basic_sphere_singlethreaded() -> Result<(), Error> {
         let start = Instant::now();
-        let sphere = sphere().set_transform(identity().scale(1.0, 0.5, 1.0).rotate_z(PI / 4.0));
+        let sphere = sphere().set_transform(identity().scale(1.0, 0.5, 1.0).rotate_z(PI / 4.0))?;
         let canvas_pixels = 1000;
         let mut canvas = canvas(canvas_pixels, canvas_pixels);
         let ray_origin = point_i(0, 0, -5);
@@ -23,116 +24,83 @@ pub mod first_sphere_image {
         let pixel_size = wall_size / canvas_pixels as f64;
         let half = wall_size / 2.0;
 
-        canvas = calculate_canvas(canvas_pixels, canvas_pixels, pixel_size, half, wall_z, ray_origin, sphere);
+        canvas = calculate_canvas(canvas_pixels, canvas_pixels, pixel_size, half, wall_z, ray_origin, sphere)?;
 
         let calculation_time = start.elapsed();
         let start2 = Instant::now();
         println!("Starting to write file.");
-        write_file("basic-sphere-render-single.ppm", &canvas.to_ppm()).expect("Failure during file write.");
+        write_file("basic-sphere-render-single.ppm", canvas.to_ppm())?;
         println!("Total time: {:?}", start.elapsed());
         println!("Calculation time: {:?}", calculation_time);
         println!("Writing file time: {:?}", start2.elapsed());
+        Ok(())
     }
 
-    pub fn render_basic_sphere_multithreaded(threads: u32) {
+    pub fn render_basic_sphere_multithreaded(threads: u32) -> Result<(), Error> {
         let start = Instant::now();
-        let sphere = sphere().set_transform(identity().scale(1.0, 0.5, 1.0).rotate_z(PI / 4.0));
+        let sphere = sphere().set_transform(identity().scale(1.0, 0.5, 1.0).rotate_z(PI / 4.0))?;
         let canvas_pixels = 1000;
-        let mut canvas = canvas(canvas_pixels, canvas_pixels);
         let ray_origin = point_i(0, 0, -5);
         let wall_z = 10;
         let wall_size = 7.0;
         let pixel_size = wall_size / canvas_pixels as f64;
         let half = wall_size / 2.0;
 
-        let rows_per_thread = canvas_pixels / threads;
-        let (tx, rx) = mpsc::channel();
-        for i in 0..threads {
-            let transmitter = tx.clone();
-            thread::spawn(move || {
-                let subcanvas = calculate_subcanvas(i, rows_per_thread, canvas_pixels, pixel_size, half, wall_z, ray_origin, sphere);
-                transmitter.send(SubcanvasMessage {
-                    subcanvas,
-                    thread: i,
-                }).unwrap();
-            });
-        }
+        let sampling = supersampling(SamplePattern::RotatedGrid, 4, Filter::Tent);
+        let canvas = render_parallel(canvas_pixels, canvas_pixels, threads, DEFAULT_TILE_SIZE, |column, row| {
+            sampling.render_pixel(column, row, |x, y| {
+                let world_y = half - pixel_size * y;
+                let world_x = -half + pixel_size * x;
+                let wall_position_target = point(world_x, world_y, wall_z as f64);
 
-        let mut threads_answered = 0;
-        for received in rx {
-            println!("Got result from thread {}", received.thread);
-            for row in 0..received.subcanvas.height {
-                for column in 0..received.subcanvas.width {
-                    canvas.pixels[((rows_per_thread * received.thread) + row) as usize][column as usize] = received.subcanvas.pixels[row as usize][column as usize];
+                let ray = ray(ray_origin, (wall_position_target - ray_origin).normalize());
+                match ray.intersect(&sphere).hit() {
+                    Some(..) => GREEN,
+                    _ => BLACK
                 }
-            }
-            threads_answered += 1;
-            if threads_answered == threads {
-                break;
-            }
-        }
+            })
+        });
 
         let calculation_time = start.elapsed();
         let start2 = Instant::now();
         println!("Starting to write file.");
-        write_file("basic-sphere-render-multi.ppm", &canvas.to_ppm()).expect("Failure during file write.");
+        write_file("basic-sphere-render-multi.ppm", canvas.to_ppm())?;
         println!("Total time: {:?}, using {} threads", start.elapsed(), threads);
         println!("Calculation time: {:?}", calculation_time);
         println!("Writing file time: {:?}", start2.elapsed());
+        Ok(())
     }
 
-    struct SubcanvasMessage {
-        subcanvas: Canvas,
-        thread: u32,
-    }
-
-    fn calculate_canvas(rows: u32, columns: u32, pixel_size: f64, half: f64, wall_z: i32, ray_origin: Tuple, sphere: Object) -> Canvas {
+    fn calculate_canvas(rows: u32, columns: u32, pixel_size: f64, half: f64, wall_z: i32, ray_origin: Tuple, sphere: Object) -> Result<Canvas, Error> {
         let mut canvas = canvas(columns, rows);
         for row in 0..rows {
-            let world_y = half - pixel_size * row as f64;
-            for column in 0..columns {
-                let world_x = -half + pixel_size * column as f64;
-                let wall_position_target = point(world_x, world_y, wall_z as f64);
-
-                let ray = ray(ray_origin, (wall_position_target - ray_origin).normalize());
-                let intersects = ray.intersect(&sphere);
-                match intersects.hit() {
-                    Some(..) => canvas.write_pixel(column, row, RED),
-                    _ => ()
-                }
-            }
-        }
-        canvas
-    }
-
-    fn calculate_subcanvas(thread: u32, rows: u32, columns: u32, pixel_size: f64, half: f64, wall_z: i32, ray_origin: Tuple, sphere: Object) -> Canvas {
-        let mut canvas = canvas(columns, rows);
-        for row in (rows * thread)..(rows + (rows * thread)) {
-            let world_y = half - pixel_size * row as f64;
+            // aim at the centre of each pixel
+            let world_y = half - pixel_size * (row as f64 + 0.5);
             for column in 0..columns {
-                let world_x = -half + pixel_size * column as f64;
+                let world_x = -half + pixel_size * (column as f64 + 0.5);
                 let wall_position_target = point(world_x, world_y, wall_z as f64);
 
                 let ray = ray(ray_origin, (wall_position_target - ray_origin).normalize());
                 let intersects = ray.intersect(&sphere);
                 match intersects.hit() {
-                    Some(..) => canvas.write_pixel(column, row - (rows * thread), GREEN),
+                    Some(..) => canvas.write_pixel(column, row, RED)?,
                     _ => ()
                 }
             }
         }
-        canvas
+        Ok(canvas)
     }
 }
 
 pub mod clock {
-    use crate::canvas::canvas;
-    use crate::colour::WHITE;
-    use crate::matrix::identity;
-    use crate::tuple::point_i;
-    use crate::util::{normalize_coordinate, radians, write_file};
+    use renderer::canvas::canvas;
+    use renderer::colour::WHITE;
+    use renderer::error::Error;
+    use renderer::matrix::identity;
+    use renderer::tuple::point_i;
+    use renderer::util::{normalize_coordinate, radians, write_file};
 
-    pub fn render_clock() {
+    pub fn render_clock() -> Result<(), Error> {
         let mut canvas = canvas(800, 800);
         let p = point_i(0, 1, 0);
         for time in 0..12 {
@@ -141,19 +109,20 @@ pub mod clock {
             normalize_coordinate(transformed_point.x, canvas.width);
             let x = normalize_coordinate(transformed_point.x, canvas.width);
             let y = normalize_coordinate(transformed_point.y, canvas.height);
-            canvas.write_pixel(x, y, WHITE);
+            canvas.write_pixel(x, y, WHITE)?;
         }
-        write_file("clock-face.ppm", &canvas.to_ppm()).expect("Failure during file write.")
+        write_file("clock-face.ppm", canvas.to_ppm())
     }
 }
 
 pub mod projectile {
-    use crate::canvas::canvas;
-    use crate::colour::colour;
-    use crate::tuple::{point_i, Tuple, vector};
-    use crate::util::write_file;
+    use renderer::canvas::canvas;
+    use renderer::colour::colour;
+    use renderer::error::Error;
+    use renderer::tuple::{point_i, Tuple, vector};
+    use renderer::util::write_file;
 
-    pub fn run_projectile_simulation() {
+    pub fn run_projectile_simulation() -> Result<(), Error> {
         let mut proj = Projectile { position: point_i(0, 2, 0), velocity: vector(1.0, 1.8, 0.0).normalize() * 11.25 };
         let environ = Environment { gravity: vector(0.0, -0.1, 0.0), wind: vector(-0.01, 0.0, 0.0) };
         let mut canvas = canvas(900, 550);
@@ -164,13 +133,13 @@ pub mod projectile {
             tick(&environ, &mut proj);
             let x = proj.position.x.round() as u32;
             let y = 550 - proj.position.y.round() as u32;
-            if x < 900 && y < 550 {
-                canvas.write_pixel(proj.position.x.round() as u32, 550 - proj.position.y.round() as u32, colour(1.0, 0.0, 0.0));
-            }
+            // positions outside of the canvas are simply not drawn
+            let _ = canvas.write_pixel(x, y, colour(1.0, 0.0, 0.0));
             println!("{:?}", proj)
         }
-        write_file("projectile-simulation.ppm", &canvas.to_ppm()).expect("Failure during file write.");
-        println!("Projectile flew for {} ticks.", tick_counter)
+        write_file("projectile-simulation.ppm", canvas.to_ppm())?;
+        println!("Projectile flew for {} ticks.", tick_counter);
+        Ok(())
     }
 
     #[derive(Debug)]
@@ -196,3 +165,94 @@ pub mod projectile {
     }
 }
 
+
+pub mod motion_blur {
+    use std::f64::consts::PI;
+
+    use renderer::camera::{camera, view_transform};
+    use renderer::colour::{BLACK, colour};
+    use renderer::error::Error;
+    use renderer::matrix::identity;
+    use renderer::objects::sphere;
+    use renderer::ray::Ray;
+    use renderer::sampling::{Filter, SamplePattern, supersampling};
+    use renderer::tuple::{point_i, vector_i};
+    use renderer::util::write_file;
+
+    pub fn render_motion_blurred_projectile() -> Result<(), Error> {
+        // the projectile travels along an arc during the exposure
+        let projectile = sphere()
+            .set_transform(identity().scale(0.5, 0.5, 0.5).translate(-3.0, -1.0, 0.0))?
+            .set_motion(identity().scale(0.5, 0.5, 0.5).rotate_z(PI / 2.0).translate(1.0, 1.5, 0.0))?;
+        let camera = camera(400, 200, PI / 3.0)
+            .set_transform(view_transform(point_i(0, 0, -10), point_i(0, 0, 0), vector_i(0, 1, 0)))?
+            .set_shutter(0.0, 1.0);
+
+        let sampling = supersampling(SamplePattern::Jittered, 16, Filter::Tent);
+        let canvas = camera.render(&sampling, 0, |ray: &Ray| {
+            match ray.intersect(&projectile).hit() {
+                Some(..) => colour(1.0, 0.0, 0.0),
+                _ => BLACK
+            }
+        });
+        write_file("motion-blurred-projectile.ppm", canvas.to_ppm())
+    }
+}
+
+pub mod progressive_path_tracing {
+    use std::f64::consts::PI;
+    use std::time::Duration;
+
+    use renderer::camera::{camera, view_transform};
+    use renderer::colour::{colour, WHITE};
+    use renderer::error::Error;
+    use renderer::integrator::path_tracing;
+    use renderer::lights::area_light;
+    use renderer::matrix::identity;
+    use renderer::objects::sphere;
+    use renderer::progressive::{accumulation, accumulation_from_bytes, progressive_render};
+    use renderer::tone_mapping::{output_transform, ToneMapping};
+    use renderer::tuple::{point, point_i, vector_i};
+    use renderer::util::{read_file, write_file};
+    use renderer::world::world;
+
+    const ACCUMULATION_FILE: &str = "progressive.accum";
+
+    // renders for a minute or up to 256 samples per pixel, continuing from the accumulation file of a previous run
+    pub fn render_progressive_path_tracing() -> Result<(), Error> {
+        let mut world = world();
+        let floor = sphere().set_transform(identity().scale(1000.0, 1000.0, 1000.0).translate(0.0, -1000.0, 0.0))?;
+        let mut red = floor.material();
+        red.colour = colour(0.9, 0.2, 0.2);
+        world.objects.push(floor);
+        world.objects.push(sphere().set_transform(identity().translate(0.0, 1.0, 0.0))?.set_material(red));
+        world.lights.push(area_light(point(-3.0, 6.0, -3.0), vector_i(2, 0, 0), 4, vector_i(0, 0, 2), 4, WHITE));
+
+        let camera = camera(320, 240, PI / 3.0)
+            .set_transform(view_transform(point_i(0, 2, -6), point_i(0, 1, 0), vector_i(0, 1, 0)))?;
+        let integrator = path_tracing(1, 8);
+        let settings = progressive_render(Some(256), Some(Duration::from_secs(60)));
+
+        // an accumulation of another size is left over from a different camera and starts over
+        let mut progress = read_file(ACCUMULATION_FILE).ok()
+            .and_then(|data| accumulation_from_bytes(&data).ok())
+            .filter(|progress| progress.width == camera.hsize && progress.height == camera.vsize)
+            .unwrap_or_else(|| accumulation(camera.hsize, camera.vsize));
+        let emitters = world.emitters();
+        camera.render_progressive(&settings, &mut progress, 0, |ray| integrator.colour_with_emitters(&world, &emitters, ray), |progress| {
+            if progress.samples.is_power_of_two() {
+                let mut snapshot = progress.snapshot();
+                snapshot.output = output_transform(0.0, ToneMapping::AcesFilmic, true);
+                // a missed snapshot is no reason to stop rendering, the final image is written below
+                if let Err(error) = write_file("progressive-path-tracing.png", snapshot.to_png()) {
+                    eprintln!("{}", error);
+                }
+            }
+        })?;
+
+        write_file(ACCUMULATION_FILE, progress.to_bytes())?;
+        let mut image = progress.snapshot();
+        image.output = output_transform(0.0, ToneMapping::AcesFilmic, true);
+        write_file("progressive-path-tracing.png", image.to_png())
+    }
+}
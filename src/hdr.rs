@@ -0,0 +1,452 @@
+use crate::canvas::{canvas, Canvas};
+use crate::colour::{colour, Colour};
+use crate::error::Error;
+
+const MIN_RLE_WIDTH: u32 = 8;
+const MAX_RLE_WIDTH: u32 = 0x7fff;
+const MIN_RUN_LENGTH: usize = 4;
+const MAX_RUN_LENGTH: usize = 127;
+const MAX_LITERAL_LENGTH: usize = 128;
+// a run of 255 old style pixels in 4 bytes is the densest encoding that is accepted, images that claim more pixels
+// than their data could hold are rejected before anything is allocated
+const MAX_PIXELS_PER_BYTE: usize = 64;
+
+impl Canvas {
+    pub fn to_hdr(&self) -> Vec<u8> {
+        let mut hdr = Vec::new();
+        hdr.extend_from_slice(b"#?RADIANCE\n");
+        hdr.extend_from_slice(b"FORMAT=32-bit_rle_rgbe\n\n");
+        hdr.extend_from_slice(format!("-Y {} +X {}\n", self.height, self.width).as_bytes());
+
+        for row in self.rows() {
+            let scanline: Vec<[u8; 4]> = row.iter().map(|pixel| to_rgbe(*pixel)).collect();
+            if self.width < MIN_RLE_WIDTH || self.width > MAX_RLE_WIDTH {
+                // run length encoding is not allowed for these widths, write flat pixels instead
+                for rgbe in scanline {
+                    hdr.extend_from_slice(&rgbe);
+                }
+                continue;
+            }
+            hdr.extend_from_slice(&[2, 2, (self.width >> 8) as u8, (self.width & 0xff) as u8]);
+            for channel in 0..4 {
+                let values: Vec<u8> = scanline.iter().map(|rgbe| rgbe[channel]).collect();
+                encode_rle_channel(&values, &mut hdr);
+            }
+        }
+        hdr
+    }
+
+    pub fn to_pfm(&self) -> Vec<u8> {
+        let mut pfm = Vec::new();
+        pfm.extend_from_slice(format!("PF\n{} {}\n-1.0\n", self.width, self.height).as_bytes());
+
+        // pfm stores its scanlines from bottom to top
+        for row in self.rows().rev() {
+            for pixel in row {
+                pfm.extend_from_slice(&(pixel.red as f32).to_le_bytes());
+                pfm.extend_from_slice(&(pixel.green as f32).to_le_bytes());
+                pfm.extend_from_slice(&(pixel.blue as f32).to_le_bytes());
+            }
+        }
+        pfm
+    }
+}
+
+pub fn canvas_from_hdr(data: &[u8]) -> Result<Canvas, Error> {
+    let mut position = 0;
+    let first_line = read_line(data, &mut position)?;
+    if !first_line.starts_with("#?") {
+        return Err(Error::Parse(String::from("Missing radiance signature.")));
+    }
+    loop {
+        let line = read_line(data, &mut position)?;
+        if line.is_empty() {
+            break;
+        }
+        if let Some(format) = line.strip_prefix("FORMAT=") {
+            if format != "32-bit_rle_rgbe" {
+                return Err(Error::Parse(format!("Unsupported pixel format '{}'.", format)));
+            }
+        }
+    }
+
+    let resolution = read_line(data, &mut position)?;
+    let parts: Vec<&str> = resolution.split_whitespace().collect();
+    if parts.len() != 4 || parts[0] != "-Y" || parts[2] != "+X" {
+        return Err(Error::Parse(format!("Unsupported resolution line '{}'.", resolution)));
+    }
+    let height = parse_dimension(parts[1])?;
+    let width = parse_dimension(parts[3])?;
+    let capacity = (data.len() - position).saturating_mul(MAX_PIXELS_PER_BYTE);
+    if (width as usize).checked_mul(height as usize).is_none_or(|pixels| pixels > capacity) {
+        return Err(Error::Parse(format!("The data is too short for an image of size {}x{}.", width, height)));
+    }
+
+    let mut canvas = canvas(width, height);
+    let mut scanline = vec![[0u8; 4]; width as usize];
+    for y in 0..height {
+        read_scanline(data, &mut position, &mut scanline)?;
+        for (pixel, rgbe) in canvas.row_mut(y).iter_mut().zip(&scanline) {
+            *pixel = from_rgbe(*rgbe);
+        }
+    }
+    Ok(canvas)
+}
+
+pub fn canvas_from_pfm(data: &[u8]) -> Result<Canvas, Error> {
+    let mut position = 0;
+    let channels = match read_token(data, &mut position)?.as_str() {
+        "PF" => 3,
+        "Pf" => 1,
+        other => return Err(Error::Parse(format!("Unsupported pfm type '{}'.", other))),
+    };
+    let width = parse_dimension(&read_token(data, &mut position)?)?;
+    let height = parse_dimension(&read_token(data, &mut position)?)?;
+    let scale = read_token(data, &mut position)?;
+    let scale: f32 = scale.parse().map_err(|_| Error::Parse(format!("Invalid pfm scale '{}'.", scale)))?;
+    // exactly one whitespace character separates the header from the raster
+    position += 1;
+
+    let little_endian = scale < 0.0;
+    let expected = (width as usize).checked_mul(height as usize).and_then(|pixels| pixels.checked_mul(channels * 4));
+    if expected.is_none_or(|expected| data.len().saturating_sub(position) < expected) {
+        return Err(Error::Parse(String::from("Unexpected end of pfm data.")));
+    }
+
+    let mut canvas = canvas(width, height);
+    for y in (0..height).rev() {
+        for pixel in canvas.row_mut(y) {
+            let mut values = [0.0; 3];
+            for value in values.iter_mut().take(channels) {
+                let bytes = [data[position], data[position + 1], data[position + 2], data[position + 3]];
+                *value = if little_endian { f32::from_le_bytes(bytes) } else { f32::from_be_bytes(bytes) } as f64;
+                position += 4;
+            }
+            *pixel = if channels == 1 {
+                colour(values[0], values[0], values[0])
+            } else {
+                colour(values[0], values[1], values[2])
+            };
+        }
+    }
+    Ok(canvas)
+}
+
+fn to_rgbe(colour: Colour) -> [u8; 4] {
+    let max = colour.red.max(colour.green).max(colour.blue);
+    if max < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+    // same as frexp: max = mantissa * 2^exponent with mantissa in [0.5, 1)
+    let mut exponent = max.log2().floor() as i32 + 1;
+    if max / 2.0_f64.powi(exponent) >= 1.0 {
+        exponent += 1;
+    }
+    let scale = 256.0 / 2.0_f64.powi(exponent);
+    [
+        (colour.red.max(0.0) * scale) as u8,
+        (colour.green.max(0.0) * scale) as u8,
+        (colour.blue.max(0.0) * scale) as u8,
+        (exponent + 128) as u8,
+    ]
+}
+
+fn from_rgbe(rgbe: [u8; 4]) -> Colour {
+    if rgbe[3] == 0 {
+        return colour(0.0, 0.0, 0.0);
+    }
+    let factor = 2.0_f64.powi(rgbe[3] as i32 - (128 + 8));
+    colour(
+        (rgbe[0] as f64 + 0.5) * factor,
+        (rgbe[1] as f64 + 0.5) * factor,
+        (rgbe[2] as f64 + 0.5) * factor,
+    )
+}
+
+fn encode_rle_channel(values: &[u8], output: &mut Vec<u8>) {
+    let mut current = 0;
+    while current < values.len() {
+        // find the start of the next run that is long enough to be worth encoding
+        let mut run_start = current;
+        let mut run_length = 0;
+        while run_start < values.len() {
+            run_length = 1;
+            while run_start + run_length < values.len()
+                && run_length < MAX_RUN_LENGTH
+                && values[run_start + run_length] == values[run_start] {
+                run_length += 1;
+            }
+            if run_length >= MIN_RUN_LENGTH {
+                break;
+            }
+            run_start += run_length;
+        }
+
+        // everything before the run is written as literals
+        while current < run_start {
+            let literal_length = (run_start - current).min(MAX_LITERAL_LENGTH);
+            output.push(literal_length as u8);
+            output.extend_from_slice(&values[current..current + literal_length]);
+            current += literal_length;
+        }
+
+        if run_length >= MIN_RUN_LENGTH {
+            output.push(128 + run_length as u8);
+            output.push(values[run_start]);
+            current += run_length;
+        }
+    }
+}
+
+fn read_scanline(data: &[u8], position: &mut usize, scanline: &mut [[u8; 4]]) -> Result<(), Error> {
+    let width = scanline.len() as u32;
+    let header = read_bytes::<4>(data, position)?;
+    let is_rle = (MIN_RLE_WIDTH..=MAX_RLE_WIDTH).contains(&width)
+        && header[0] == 2 && header[1] == 2 && header[2] & 0x80 == 0;
+    if !is_rle {
+        *position -= 4;
+        return read_flat_scanline(data, position, scanline);
+    }
+    if ((header[2] as u32) << 8 | header[3] as u32) != width {
+        return Err(Error::Parse(String::from("Scanline width does not match image width.")));
+    }
+
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < scanline.len() {
+            let count = read_bytes::<1>(data, position)?[0] as usize;
+            if count > 128 {
+                let count = count - 128;
+                let value = read_bytes::<1>(data, position)?[0];
+                if x + count > scanline.len() {
+                    return Err(Error::Parse(String::from("Run exceeds scanline width.")));
+                }
+                for rgbe in &mut scanline[x..x + count] {
+                    rgbe[channel] = value;
+                }
+                x += count;
+            } else {
+                if count == 0 || x + count > scanline.len() {
+                    return Err(Error::Parse(String::from("Invalid literal length in scanline.")));
+                }
+                for rgbe in &mut scanline[x..x + count] {
+                    rgbe[channel] = read_bytes::<1>(data, position)?[0];
+                }
+                x += count;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_flat_scanline(data: &[u8], position: &mut usize, scanline: &mut [[u8; 4]]) -> Result<(), Error> {
+    let mut x = 0;
+    let mut shift = 0;
+    while x < scanline.len() {
+        let rgbe = read_bytes::<4>(data, position)?;
+        if rgbe[0] == 1 && rgbe[1] == 1 && rgbe[2] == 1 {
+            // old style run length encoding, repeats the previous pixel
+            if x == 0 {
+                return Err(Error::Parse(String::from("Run without a previous pixel.")));
+            }
+            // a run right after another one counts 256 times as much
+            let count = (rgbe[3] as usize).checked_shl(shift).unwrap_or(usize::MAX);
+            if count > scanline.len() - x {
+                return Err(Error::Parse(String::from("Run exceeds scanline width.")));
+            }
+            let previous = scanline[x - 1];
+            for pixel in &mut scanline[x..x + count] {
+                *pixel = previous;
+            }
+            x += count;
+            shift += 8;
+        } else {
+            scanline[x] = rgbe;
+            x += 1;
+            shift = 0;
+        }
+    }
+    Ok(())
+}
+
+fn read_bytes<const COUNT: usize>(data: &[u8], position: &mut usize) -> Result<[u8; COUNT], Error> {
+    if *position + COUNT > data.len() {
+        return Err(Error::Parse(String::from("Unexpected end of hdr data.")));
+    }
+    let mut bytes = [0; COUNT];
+    bytes.copy_from_slice(&data[*position..*position + COUNT]);
+    *position += COUNT;
+    Ok(bytes)
+}
+
+fn read_line(data: &[u8], position: &mut usize) -> Result<String, Error> {
+    let start = *position;
+    while *position < data.len() && data[*position] != b'\n' {
+        *position += 1;
+    }
+    if *position >= data.len() {
+        return Err(Error::Parse(String::from("Unexpected end of header.")));
+    }
+    let line = String::from_utf8_lossy(&data[start..*position]).into_owned();
+    *position += 1;
+    Ok(line)
+}
+
+fn read_token(data: &[u8], position: &mut usize) -> Result<String, Error> {
+    while *position < data.len() && data[*position].is_ascii_whitespace() {
+        *position += 1;
+    }
+    let start = *position;
+    while *position < data.len() && !data[*position].is_ascii_whitespace() {
+        *position += 1;
+    }
+    if start == *position {
+        return Err(Error::Parse(String::from("Unexpected end of header.")));
+    }
+    Ok(String::from_utf8_lossy(&data[start..*position]).into_owned())
+}
+
+fn parse_dimension(value: &str) -> Result<u32, Error> {
+    value.parse().map_err(|_| Error::Parse(format!("Invalid image dimension '{}'.", value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canvas::canvas;
+    use crate::colour::colour;
+    use crate::hdr::{canvas_from_hdr, canvas_from_pfm};
+
+    #[test]
+    fn constructing_hdr_header() {
+        let c = canvas(10, 5);
+        let hdr = c.to_hdr();
+        let header = String::from_utf8_lossy(&hdr[0..52]);
+
+        let mut line_iterator = header.lines();
+        assert_eq!(line_iterator.next().unwrap(), "#?RADIANCE");
+        assert_eq!(line_iterator.next().unwrap(), "FORMAT=32-bit_rle_rgbe");
+        assert_eq!(line_iterator.next().unwrap(), "");
+        assert_eq!(line_iterator.next().unwrap(), "-Y 5 +X 10");
+    }
+
+    #[test]
+    fn hdr_keeps_values_above_one() {
+        let mut c = canvas(12, 3);
+        c.write_pixel(0, 0, colour(4.5, 0.25, 1.0)).unwrap();
+        c.write_pixel(11, 2, colour(120.0, 60.0, 0.001)).unwrap();
+
+        let loaded = canvas_from_hdr(&c.to_hdr()).unwrap();
+        assert_eq!(loaded.width, 12);
+        assert_eq!(loaded.height, 3);
+        let pixel = loaded.pixel_at(0, 0);
+        assert!((pixel.red - 4.5).abs() < 4.5 / 128.0);
+        assert!((pixel.green - 0.25).abs() < 4.5 / 128.0);
+        assert!((pixel.blue - 1.0).abs() < 4.5 / 128.0);
+        let pixel = loaded.pixel_at(11, 2);
+        assert!((pixel.red - 120.0).abs() < 120.0 / 128.0);
+        assert!((pixel.green - 60.0).abs() < 120.0 / 128.0);
+        assert_eq!(loaded.pixel_at(5, 1).red, 0.0);
+    }
+
+    #[test]
+    fn hdr_scanlines_are_run_length_encoded() {
+        let mut c = canvas(100, 2);
+        for y in 0..2 {
+            for x in 0..100 {
+                c.write_pixel(x, y, colour(2.0, 1.0, 0.5)).unwrap();
+            }
+        }
+        let hdr = c.to_hdr();
+        // 4 byte scanline header and a single two byte run per channel
+        assert_eq!(hdr.len(), 47 + 2 * (4 + 4 * 2));
+
+        let loaded = canvas_from_hdr(&hdr).unwrap();
+        assert!((loaded.pixel_at(99, 1).red - 2.0).abs() < 2.0 / 128.0);
+        assert!((loaded.pixel_at(99, 1).blue - 0.5).abs() < 2.0 / 128.0);
+    }
+
+    #[test]
+    fn hdr_with_mixed_runs_and_literals_round_trips() {
+        let mut c = canvas(300, 1);
+        for x in 0..300 {
+            let value = if x % 50 < 20 { 1.0 } else { x as f64 / 300.0 };
+            c.write_pixel(x, 0, colour(value, value * 0.5, 0.0)).unwrap();
+        }
+        let loaded = canvas_from_hdr(&c.to_hdr()).unwrap();
+        for x in 0..300 {
+            let expected = c.pixel_at(x, 0).red;
+            assert!((loaded.pixel_at(x, 0).red - expected).abs() < 1.0 / 128.0);
+        }
+    }
+
+    #[test]
+    fn narrow_hdr_images_are_written_flat() {
+        let mut c = canvas(3, 2);
+        c.write_pixel(2, 1, colour(0.5, 0.5, 0.5)).unwrap();
+        let hdr = c.to_hdr();
+        assert_eq!(hdr.len(), 45 + 3 * 2 * 4);
+
+        let loaded = canvas_from_hdr(&hdr).unwrap();
+        assert!((loaded.pixel_at(2, 1).red - 0.5).abs() < 1.0 / 128.0);
+    }
+
+    #[test]
+    fn loading_hdr_without_signature_fails() {
+        assert!(canvas_from_hdr(b"P3\n5 3\n255\n").is_err());
+    }
+
+    #[test]
+    fn hdr_sizes_are_checked_against_the_data() {
+        let huge = canvas_from_hdr(b"#?RADIANCE\n\n-Y 100000 +X 100000\n\x01\x02\x03\x04");
+        assert_eq!(huge.err().unwrap().to_string(), "The data is too short for an image of size 100000x100000.");
+        // a chain of empty old style runs keeps multiplying the run length
+        let mut chained = b"#?RADIANCE\n\n-Y 1 +X 4\n\x01\x02\x03\x04".to_vec();
+        for _ in 0..10 {
+            chained.extend_from_slice(&[1, 1, 1, 0]);
+        }
+        chained.extend_from_slice(&[1, 1, 1, 1]);
+        assert!(canvas_from_hdr(&chained).is_err());
+    }
+
+    #[test]
+    fn constructing_pfm_header() {
+        let c = canvas(5, 3);
+        let pfm = c.to_pfm();
+        assert!(pfm.starts_with(b"PF\n5 3\n-1.0\n"));
+        assert_eq!(pfm.len(), 12 + 5 * 3 * 12);
+    }
+
+    #[test]
+    fn pfm_stores_rows_bottom_to_top() {
+        let mut c = canvas(2, 2);
+        c.write_pixel(0, 1, colour(7.5, 0.0, 0.0)).unwrap();
+        let pfm = c.to_pfm();
+        assert_eq!(&pfm[12..16], &7.5_f32.to_le_bytes());
+    }
+
+    #[test]
+    fn pfm_round_trips() {
+        let mut c = canvas(4, 3);
+        c.write_pixel(1, 2, colour(1500.25, -0.5, 0.125)).unwrap();
+        let loaded = canvas_from_pfm(&c.to_pfm()).unwrap();
+        assert_eq!(loaded.pixel_at(1, 2).red, 1500.25);
+        assert_eq!(loaded.pixel_at(1, 2).green, -0.5);
+        assert_eq!(loaded.pixel_at(1, 2).blue, 0.125);
+    }
+
+    #[test]
+    fn reading_big_endian_greyscale_pfm() {
+        let mut pfm = b"Pf\n1 1\n1.0\n".to_vec();
+        pfm.extend_from_slice(&3.0_f32.to_be_bytes());
+        let loaded = canvas_from_pfm(&pfm).unwrap();
+        assert_eq!(loaded.pixel_at(0, 0).red, 3.0);
+        assert_eq!(loaded.pixel_at(0, 0).green, 3.0);
+        assert_eq!(loaded.pixel_at(0, 0).blue, 3.0);
+    }
+
+    #[test]
+    fn truncated_pfm_fails() {
+        assert!(canvas_from_pfm(b"PF\n2 2\n-1.0\n").is_err());
+        assert!(canvas_from_pfm(b"PF\n4294967295 4294967295\n-1.0\n").is_err());
+    }
+}
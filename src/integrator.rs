@@ -0,0 +1,368 @@
+use std::f64::consts::PI;
+
+use crate::camera::Camera;
+use crate::canvas::Canvas;
+use crate::colour::{BLACK, Colour, WHITE};
+use crate::lights::Light;
+use crate::materials::Material;
+use crate::ray::{hit, Ray, timed_ray};
+use crate::random::{random_from, Random};
+use crate::sampling::{cosine_hemisphere, Supersampling};
+use crate::tuple::{dot_product, Tuple};
+use crate::util::EPSILON;
+use crate::world::{prepare_computations, World};
+
+// paths are never cut short by russian roulette before this many bounces
+const ROULETTE_START_DEPTH: u32 = 3;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Integrator {
+    // direct phong lighting with shadows, no indirect light
+    Whitted,
+    // `paths` paths are traced for every camera ray, each bouncing at most `max_depth` times
+    PathTracing { paths: u32, max_depth: u32 },
+}
+
+pub fn path_tracing(paths: u32, max_depth: u32) -> Integrator {
+    Integrator::PathTracing {
+        paths: paths.max(1),
+        max_depth,
+    }
+}
+
+impl Integrator {
+    pub fn colour_at(&self, world: &World, ray: &Ray) -> Colour {
+        self.colour_with_emitters(world, &world.emitters(), ray)
+    }
+
+    // `emitters` are the indices from `World::emitters`, renders look them up once instead of for every ray
+    pub fn colour_with_emitters(&self, world: &World, emitters: &[usize], ray: &Ray) -> Colour {
+        match self {
+            Integrator::Whitted => world.colour_at(ray),
+            Integrator::PathTracing { paths, max_depth } => {
+                // the camera ray itself seeds the random numbers, so every sample of a pixel gets its own paths
+                let mut rng = random_from(&[
+                    ray.origin.x.to_bits(), ray.origin.y.to_bits(), ray.origin.z.to_bits(),
+                    ray.direction.x.to_bits(), ray.direction.y.to_bits(), ray.direction.z.to_bits(),
+                ]);
+                let mut total = BLACK;
+                for _ in 0..*paths {
+                    total = total + trace_path(world, emitters, ray, *max_depth, &mut rng);
+                }
+                total * (1.0 / *paths as f64)
+            }
+        }
+    }
+}
+
+impl Camera {
+    pub fn render_world(&self, world: &World, sampling: &Supersampling, integrator: &Integrator, threads: u32) -> Canvas {
+        let emitters = world.emitters();
+        self.render(sampling, threads, |ray| integrator.colour_with_emitters(world, &emitters, ray))
+    }
+}
+
+// surfaces without a pbr material are treated as lambertian with albedo `colour * diffuse`, light intensities are
+// the irradiance at normal incidence like in the phong model, so direct lighting matches the classic shader without
+// ambient and specular
+fn trace_path(world: &World, emitters: &[usize], camera_ray: &Ray, max_depth: u32, rng: &mut Random) -> Colour {
+    let mut radiance = BLACK;
+    let mut throughput = WHITE;
+    let mut ray = timed_ray(camera_ray.origin, camera_ray.direction, camera_ray.time);
+    let sample_environment = !world.environment.is_black();
+    // density with which the surface picked the direction of the current ray, used to weigh the environment
+    let mut bsdf_pdf = 0.0;
+
+    for depth in 0..=max_depth {
+        let intersections = world.intersect(&ray);
+        let intersection = match hit(&intersections) {
+            Some(intersection) => intersection,
+            None => {
+                // camera rays see the environment as is, bounced rays share it with sampling the environment directly
+                let weight = if depth == 0 { 1.0 } else { mis_weight(bsdf_pdf, world.environment.pdf(ray.direction)) };
+                radiance = radiance + throughput * world.environment.colour(ray.direction) * weight;
+                break;
+            }
+        };
+        let comps = prepare_computations(&intersection, &ray);
+        let material = comps.object.material();
+        // emitters hit by later bounces are already accounted for by sampling them directly
+        if depth == 0 {
+            radiance = radiance + material.emission;
+        }
+        if !emitters.is_empty() {
+            let index = ((rng.next_f64() * emitters.len() as f64) as usize).min(emitters.len() - 1);
+            let emitter = &world.objects[emitters[index]];
+            // by identity, equal looking emitters in different places still light each other
+            if !std::ptr::eq(emitter, intersection.object) {
+                let sample = emitter.sample_surface(rng.next_f64(), rng.next_f64(), ray.time);
+                let to_emitter = sample.point - comps.over_point;
+                let distance = to_emitter.magnitude();
+                let lightv = to_emitter.normalize();
+                let cos = dot_product(lightv, comps.normalv);
+                // emitters glow from both sides
+                let cos_emitter = dot_product(lightv, sample.normal).abs();
+                if cos > 0.0 && sample.pdf > 0.0
+                    && !world.is_occluded(comps.over_point, lightv, distance - EPSILON, ray.time) {
+                    // the emitted radiance over the solid angle of the sample, divided by pi like the light intensities and by the
+                    // chance of picking this point of this emitter
+                    let irradiance = emitter.material().emission
+                        * (cos_emitter * emitters.len() as f64 / (PI * distance * distance * sample.pdf));
+                    radiance = radiance + throughput * surface_response(&material, comps.normalv, comps.eyev, lightv) * irradiance;
+                }
+            }
+        }
+
+        if sample_environment {
+            let sample = world.environment.sample(rng.next_f64(), rng.next_f64());
+            let cos = dot_product(sample.direction, comps.normalv);
+            if cos > 0.0 && sample.pdf > 0.0
+                && !world.is_occluded(comps.over_point, sample.direction, f64::INFINITY, ray.time) {
+                // the last bounce is never followed, so the environment has to account for all of it
+                let weight = if depth == max_depth {
+                    1.0
+                } else {
+                    mis_weight(sample.pdf, surface_pdf(&material, comps.normalv, comps.eyev, sample.direction))
+                };
+                let irradiance = sample.radiance * (weight / (PI * sample.pdf));
+                radiance = radiance + throughput * surface_response(&material, comps.normalv, comps.eyev, sample.direction) * irradiance;
+            }
+        }
+
+        // next event estimation, light arriving straight from the lights
+        for light in &world.lights {
+            let samples = light.sample_points(comps.over_point);
+            let mut direct = BLACK;
+            for sample in &samples {
+                let (lightv, distance) = match light {
+                    Light::Directional { .. } => light.direction_from(comps.over_point),
+                    _ => {
+                        let v = *sample - comps.over_point;
+                        (v.normalize(), v.magnitude())
+                    }
+                };
+                let cos = dot_product(lightv, comps.normalv);
+                if cos <= 0.0 || world.is_occluded(comps.over_point, lightv, distance, ray.time) {
+                    continue;
+                }
+                direct = direct + light.intensity() * surface_response(&material, comps.normalv, comps.eyev, lightv);
+            }
+            let falloff = light.falloff(comps.over_point) / samples.len() as f64;
+            radiance = radiance + throughput * direct * falloff;
+        }
+
+        if depth == max_depth {
+            break;
+        }
+
+        let (direction, weight) = match material.pbr {
+            Some(pbr) => match pbr.sample(comps.normalv, comps.eyev, rng.next_f64(), rng.next_f64(), rng.next_f64()) {
+                Some(sample) => sample,
+                None => break,
+            },
+            // the cosine weighted pdf cancels the cosine and 1 / pi of the lambertian brdf
+            None => (cosine_hemisphere(comps.normalv, rng.next_f64(), rng.next_f64()), material.colour * material.diffuse),
+        };
+        throughput = throughput * weight;
+        bsdf_pdf = surface_pdf(&material, comps.normalv, comps.eyev, direction);
+        if depth >= ROULETTE_START_DEPTH {
+            let survival = throughput.red.max(throughput.green).max(throughput.blue).clamp(0.05, 0.95);
+            if rng.next_f64() >= survival {
+                break;
+            }
+            throughput = throughput * (1.0 / survival);
+        }
+
+        ray = timed_ray(comps.over_point, direction, ray.time);
+    }
+    radiance
+}
+
+// light reflected towards the eye for unit irradiance arriving along `lightv`
+fn surface_response(material: &Material, normalv: Tuple, eyev: Tuple, lightv: Tuple) -> Colour {
+    let cos = dot_product(lightv, normalv);
+    match material.pbr {
+        Some(pbr) => pbr.brdf(normalv, eyev, lightv) * (PI * cos),
+        None => material.colour * material.diffuse * cos,
+    }
+}
+
+// density of the bounce directions picked for the material, with respect to solid angle
+fn surface_pdf(material: &Material, normalv: Tuple, eyev: Tuple, lightv: Tuple) -> f64 {
+    match material.pbr {
+        Some(pbr) => pbr.pdf(normalv, eyev, lightv),
+        None => dot_product(lightv, normalv).max(0.0) / PI,
+    }
+}
+
+// balance heuristic for combining two sampling strategies
+fn mis_weight(pdf: f64, other_pdf: f64) -> f64 {
+    if pdf + other_pdf <= 0.0 {
+        return 0.0;
+    }
+    pdf / (pdf + other_pdf)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::camera::camera;
+    use crate::colour::{colour, WHITE};
+    use crate::integrator::{Integrator, path_tracing};
+    use crate::lights::{directional_light, point_light};
+    use crate::materials::material;
+    use crate::matrix::{scaling, translation};
+    use crate::objects::{sphere, triangle};
+    use crate::ray::ray;
+    use crate::sampling::NO_SUPERSAMPLING;
+    use crate::tuple::{point, point_i, vector, vector_i};
+    use crate::world::{default_world, world};
+
+    #[test]
+    fn whitted_integrator_is_the_classic_shader() {
+        let w = default_world();
+        let r = ray(point_i(0, 0, -5), vector_i(0, 0, 1));
+        let c = Integrator::Whitted.colour_at(&w, &r);
+        let expected = w.colour_at(&r);
+        assert_eq!(c.red, expected.red);
+        assert_eq!(c.green, expected.green);
+        assert_eq!(c.blue, expected.blue);
+    }
+
+    #[test]
+    fn path_tracing_a_miss_is_black() {
+        let w = default_world();
+        let r = ray(point_i(0, 0, -5), vector_i(0, 1, 0));
+        let c = path_tracing(4, 4).colour_at(&w, &r);
+        assert_eq!(c.red, 0.0);
+    }
+
+    #[test]
+    fn direct_lighting_matches_lambert_without_bounces() {
+        let mut w = world();
+        let mut m = material();
+        m.colour = colour(0.5, 0.25, 1.0);
+        m.diffuse = 0.8;
+        w.objects.push(sphere().set_material(m));
+        w.lights.push(directional_light(vector_i(0, 0, 1), WHITE));
+        let r = ray(point_i(0, 0, -5), vector_i(0, 0, 1));
+        let c = path_tracing(1, 0).colour_at(&w, &r);
+        assert!((c.red - 0.4).abs() < 1e-9);
+        assert!((c.green - 0.2).abs() < 1e-9);
+        assert!((c.blue - 0.8).abs() < 1e-9);
+    }
+
+    #[test]
+    fn path_tracing_is_deterministic() {
+        let w = default_world();
+        let r = ray(point(0.1, 0.2, -5.0), vector_i(0, 0, 1));
+        let integrator = path_tracing(8, 5);
+        let a = integrator.colour_at(&w, &r);
+        let b = integrator.colour_at(&w, &r);
+        assert_eq!(a.red, b.red);
+        assert_eq!(a.green, b.green);
+        assert_eq!(a.blue, b.blue);
+    }
+
+    #[test]
+    fn indirect_light_reaches_shadowed_points() {
+        // a floor lit from above with a blocker in between, only bounced light from the lit floor around
+        // and the blocker can reach the point below it
+        let mut w = world();
+        w.objects.push(sphere().set_transform(translation(0.0, -1001.0, 0.0) * scaling(1000.0, 1000.0, 1000.0)).unwrap());
+        w.objects.push(sphere().set_transform(translation(0.0, 1.0, 0.0)).unwrap());
+        w.lights.push(point_light(point_i(0, 10, 0), WHITE));
+        let r = ray(point(0.0, -0.5, -5.0), vector(0.0, -0.1, 1.0).normalize());
+        let direct_only = path_tracing(64, 0).colour_at(&w, &r);
+        let traced = path_tracing(64, 4).colour_at(&w, &r);
+        assert_eq!(direct_only.red, 0.0);
+        assert!(traced.red > 0.0);
+    }
+
+    #[test]
+    fn identical_emitters_light_each_other() {
+        // the two glowing spheres only differ in their position
+        let mut glowing = material();
+        glowing.emission = colour(1.0, 1.0, 1.0);
+        let mut w = world();
+        w.objects.push(sphere().set_material(glowing));
+        w.objects.push(sphere().set_material(glowing).set_transform(translation(3.0, 0.0, 0.0)).unwrap());
+        // hits the side of the second sphere that faces the first
+        let c = path_tracing(64, 0).colour_at(&w, &ray(point(1.5, 0.0, 0.0), vector_i(1, 0, 0)));
+        assert!(c.red > 1.0);
+    }
+
+    #[test]
+    fn both_integrators_render_the_same_world() {
+        let w = default_world();
+        let c = camera(8, 6, std::f64::consts::PI / 3.0).set_transform(crate::camera::view_transform(
+            point_i(0, 0, -5), point_i(0, 0, 0), vector_i(0, 1, 0))).unwrap();
+        let classic = c.render_world(&w, &NO_SUPERSAMPLING, &Integrator::Whitted, 2);
+        let traced = c.render_world(&w, &NO_SUPERSAMPLING, &path_tracing(4, 3), 2);
+        assert_eq!(classic.width, traced.width);
+        assert_eq!(classic.height, traced.height);
+        assert!(traced.pixel_at(4, 3).red > 0.0);
+        assert_eq!(traced.pixel_at(0, 0).red, 0.0);
+    }
+
+    #[test]
+    fn materials_do_not_glow_by_default() {
+        assert_eq!(material().emission.red, 0.0);
+        assert!(default_world().emitters().is_empty());
+    }
+
+    #[test]
+    fn classic_shader_adds_the_emission() {
+        let mut w = world();
+        let mut m = material();
+        m.emission = colour(2.0, 1.0, 0.5);
+        m.ambient = 0.0;
+        w.objects.push(sphere().set_material(m));
+        let r = ray(point_i(0, 0, -5), vector_i(0, 0, 1));
+        let c = Integrator::Whitted.colour_at(&w, &r);
+        assert_eq!((c.red, c.green, c.blue), (2.0, 1.0, 0.5));
+        assert_eq!(w.emitters().len(), 1);
+    }
+
+    #[test]
+    fn emitters_seen_by_the_camera_glow_in_the_path_tracer() {
+        let mut w = world();
+        let mut m = material();
+        m.emission = colour(0.0, 3.0, 0.0);
+        w.objects.push(triangle(point_i(0, 1, 0), point_i(-1, 0, 0), point_i(1, 0, 0)).set_material(m));
+        let r = ray(point(0.0, 0.5, -2.0), vector_i(0, 0, 1));
+        let c = path_tracing(4, 2).colour_at(&w, &r);
+        assert_eq!(c.green, 3.0);
+    }
+
+    #[test]
+    fn surrounding_emitter_gives_albedo_times_radiance() {
+        let mut w = world();
+        let mut glow = material();
+        glow.emission = WHITE;
+        w.objects.push(sphere().set_transform(scaling(10.0, 10.0, 10.0)).unwrap().set_material(glow));
+        let mut grey = material();
+        grey.colour = colour(0.5, 0.5, 0.5);
+        grey.diffuse = 1.0;
+        w.objects.push(sphere().set_material(grey));
+
+        let r = ray(point_i(0, 0, -5), vector_i(0, 0, 1));
+        let c = path_tracing(4000, 0).colour_at(&w, &r);
+        assert!((c.red - 0.5).abs() < 0.025);
+    }
+
+    #[test]
+    fn mesh_lights_light_the_floor() {
+        let mut w = world();
+        let mut panel = material();
+        panel.emission = colour(5.0, 5.0, 5.0);
+        w.objects.push(triangle(point(-1.0, 2.0, -1.0), point(1.0, 2.0, -1.0), point(0.0, 2.0, 1.0)).set_material(panel));
+        w.objects.push(triangle(point(-10.0, 0.0, -10.0), point(10.0, 0.0, -10.0), point(0.0, 0.0, 10.0)));
+
+        let r = ray(point_i(0, 1, -5), point_i(0, 0, 0) - point_i(0, 1, -5));
+        let traced = path_tracing(64, 0).colour_at(&w, &r);
+        assert!(traced.red > 0.0);
+        // without lights the classic shader only sees the ambient term of the floor, which needs a light
+        let classic = Integrator::Whitted.colour_at(&w, &r);
+        assert_eq!(classic.red, 0.0);
+    }
+}
@@ -0,0 +1,421 @@
+use crate::error::Error;
+use crate::yaml::{Node, position_error, Value};
+
+// reads json into the same nodes as the scene files, numbers, booleans and null become scalars holding their text
+pub fn parse_json(text: &str) -> Result<Node, Error> {
+    let mut parser = Parser {
+        characters: text.chars().collect(),
+        index: 0,
+        line: 1,
+        column: 1,
+    };
+    let node = parser.value()?;
+    parser.skip_whitespace();
+    if parser.index < parser.characters.len() {
+        return Err(parser.error("Unexpected characters after the document."));
+    }
+    Ok(node)
+}
+
+// indented by two spaces, scalars that are valid json numbers are written as numbers and everything else as strings
+pub fn to_json(node: &Node) -> String {
+    let mut text = String::new();
+    write_node(node, 0, &mut text);
+    text.push('\n');
+    text
+}
+
+fn write_node(node: &Node, indent: usize, text: &mut String) {
+    match &node.value {
+        Value::Scalar(value) if is_number(value) => text.push_str(value),
+        Value::Scalar(value) => write_string(value, text),
+        Value::List(items) if items.is_empty() => text.push_str("[]"),
+        // lists of scalars, like coordinates and colours, stay on one line
+        Value::List(items) if items.iter().all(|item| matches!(item.value, Value::Scalar(_))) => {
+            text.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    text.push_str(", ");
+                }
+                write_node(item, indent, text);
+            }
+            text.push(']');
+        }
+        Value::List(items) => {
+            text.push_str("[\n");
+            for (index, item) in items.iter().enumerate() {
+                text.push_str(&" ".repeat(indent + 2));
+                write_node(item, indent + 2, text);
+                text.push_str(if index + 1 < items.len() { ",\n" } else { "\n" });
+            }
+            text.push_str(&" ".repeat(indent));
+            text.push(']');
+        }
+        Value::Map(entries) if entries.is_empty() => text.push_str("{}"),
+        Value::Map(entries) => {
+            text.push_str("{\n");
+            for (index, (key, value)) in entries.iter().enumerate() {
+                text.push_str(&" ".repeat(indent + 2));
+                write_string(key, text);
+                text.push_str(": ");
+                write_node(value, indent + 2, text);
+                text.push_str(if index + 1 < entries.len() { ",\n" } else { "\n" });
+            }
+            text.push_str(&" ".repeat(indent));
+            text.push('}');
+        }
+    }
+}
+
+fn write_string(value: &str, text: &mut String) {
+    text.push('"');
+    for character in value.chars() {
+        match character {
+            '"' => text.push_str("\\\""),
+            '\\' => text.push_str("\\\\"),
+            '\n' => text.push_str("\\n"),
+            '\r' => text.push_str("\\r"),
+            '\t' => text.push_str("\\t"),
+            character if (character as u32) < 0x20 => text.push_str(&format!("\\u{:04x}", character as u32)),
+            character => text.push(character),
+        }
+    }
+    text.push('"');
+}
+
+// the number grammar of json, which unlike rust's float parsing has no inf, nan or leading '+'
+fn is_number(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    let mut index = 0;
+    let digits = |index: &mut usize| {
+        let start = *index;
+        while *index < bytes.len() && bytes[*index].is_ascii_digit() {
+            *index += 1;
+        }
+        *index > start
+    };
+    if bytes.first() == Some(&b'-') {
+        index += 1;
+    }
+    if bytes.get(index) == Some(&b'0') {
+        index += 1;
+    } else if !digits(&mut index) {
+        return false;
+    }
+    if bytes.get(index) == Some(&b'.') {
+        index += 1;
+        if !digits(&mut index) {
+            return false;
+        }
+    }
+    if matches!(bytes.get(index), Some(b'e' | b'E')) {
+        index += 1;
+        if matches!(bytes.get(index), Some(b'+' | b'-')) {
+            index += 1;
+        }
+        if !digits(&mut index) {
+            return false;
+        }
+    }
+    index == bytes.len()
+}
+
+struct Parser {
+    characters: Vec<char>,
+    index: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> Error {
+        position_error(self.line, self.column, message)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.characters.get(self.index).copied()
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let character = self.peek()?;
+        self.index += 1;
+        if character == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(character)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.advance();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), Error> {
+        self.skip_whitespace();
+        if self.peek() != Some(expected) {
+            return Err(self.error(&format!("Expected '{}'.", expected)));
+        }
+        self.advance();
+        Ok(())
+    }
+
+    fn value(&mut self) -> Result<Node, Error> {
+        self.skip_whitespace();
+        let (line, column) = (self.line, self.column);
+        let value = match self.peek() {
+            None => return Err(self.error("Unexpected end of the document.")),
+            Some('{') => {
+                self.advance();
+                let mut entries: Vec<(String, Node)> = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some('}') {
+                    self.advance();
+                } else {
+                    loop {
+                        self.skip_whitespace();
+                        let (key_line, key_column) = (self.line, self.column);
+                        if self.peek() != Some('"') {
+                            return Err(self.error("Expected a string key."));
+                        }
+                        let key = self.string()?;
+                        if entries.iter().any(|(existing, _)| *existing == key) {
+                            return Err(position_error(key_line, key_column, &format!("Duplicate key '{}'.", key)));
+                        }
+                        self.expect(':')?;
+                        let value = self.value()?;
+                        entries.push((key, value));
+                        self.skip_whitespace();
+                        match self.peek() {
+                            Some(',') => {}
+                            Some('}') => {
+                                self.advance();
+                                break;
+                            }
+                            _ => return Err(self.error("Expected ',' or '}'.")),
+                        }
+                        self.advance();
+                    }
+                }
+                Value::Map(entries)
+            }
+            Some('[') => {
+                self.advance();
+                let mut items = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some(']') {
+                    self.advance();
+                } else {
+                    loop {
+                        items.push(self.value()?);
+                        self.skip_whitespace();
+                        match self.peek() {
+                            Some(',') => {}
+                            Some(']') => {
+                                self.advance();
+                                break;
+                            }
+                            _ => return Err(self.error("Expected ',' or ']'.")),
+                        }
+                        self.advance();
+                    }
+                }
+                Value::List(items)
+            }
+            Some('"') => Value::Scalar(self.string()?),
+            Some(_) => {
+                let start = self.index;
+                while self.peek().is_some_and(|character| character.is_ascii_alphanumeric() || "+-.".contains(character)) {
+                    self.advance();
+                }
+                let text: String = self.characters[start..self.index].iter().collect();
+                if !(is_number(&text) || text == "true" || text == "false" || text == "null") {
+                    return Err(position_error(line, column, "Expected a value."));
+                }
+                Value::Scalar(text)
+            }
+        };
+        Ok(Node { value, line, column })
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        let (line, column) = (self.line, self.column);
+        self.advance();
+        let mut text = String::new();
+        loop {
+            match self.advance() {
+                None => return Err(position_error(line, column, "Unclosed string.")),
+                Some('"') => return Ok(text),
+                Some('\\') => {
+                    let escaped = match self.advance() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let first = self.hex()?;
+                            // characters outside the basic plane are written as a surrogate pair
+                            let code = if (0xd800..0xdc00).contains(&first) && self.peek() == Some('\\') {
+                                self.advance();
+                                if self.advance() != Some('u') {
+                                    return Err(self.error("Expected a low surrogate."));
+                                }
+                                let second = self.hex()?;
+                                if !(0xdc00..0xe000).contains(&second) {
+                                    return Err(self.error("Expected a low surrogate."));
+                                }
+                                0x10000 + ((first - 0xd800) << 10) + (second - 0xdc00)
+                            } else {
+                                first
+                            };
+                            char::from_u32(code).ok_or_else(|| self.error("Invalid unicode escape."))?
+                        }
+                        _ => return Err(self.error("Unknown escape sequence.")),
+                    };
+                    text.push(escaped);
+                }
+                Some(character) if (character as u32) < 0x20 => return Err(self.error("Control characters must be escaped.")),
+                Some(character) => text.push(character),
+            }
+        }
+    }
+
+    fn hex(&mut self) -> Result<u32, Error> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self.advance().and_then(|character| character.to_digit(16))
+                .ok_or_else(|| self.error("Expected four hexadecimal digits."))?;
+            code = code * 16 + digit;
+        }
+        Ok(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::camera::{Bokeh, camera, Projection, view_transform};
+    use crate::colour::colour;
+    use crate::environment::{gradient_environment, image_environment, sky_environment};
+    use crate::error::Error;
+    use crate::canvas::canvas;
+    use crate::json::{parse_json, to_json};
+    use crate::lights::{area_light, attenuation, directional_light, point_light, spherical_light, spot_light};
+    use crate::materials::{material, pbr_material};
+    use crate::matrix::{identity, scaling, translation};
+    use crate::objects::{sphere, triangle};
+    use crate::scene::{load_scene, parse_scene, parse_scene_json, save_scene, Scene, scene_to_json};
+    use crate::sky::sky;
+    use crate::tuple::{point, point_i, vector, vector_i};
+    use crate::world::world;
+    use crate::yaml::Value;
+
+    #[test]
+    fn reading_and_writing_json() {
+        let text = "{\"a\": [1, -2.5e3, true, null], \"b\": {\"c\": \"line\\n\\u00e9\\ud83d\\ude00\"}, \"d\": []}";
+        let node = parse_json(text).unwrap();
+        let Value::Map(entries) = &node.value else { panic!("expected an object") };
+        let Value::List(items) = &entries[0].1.value else { panic!("expected an array") };
+        assert_eq!(items[1].value, Value::Scalar(String::from("-2.5e3")));
+        assert_eq!((items[1].line, items[1].column), (1, 11));
+        let Value::Map(nested) = &entries[1].1.value else { panic!("expected an object") };
+        assert_eq!(nested[0].1.value, Value::Scalar(String::from("line\n\u{e9}\u{1f600}")));
+
+        let written = to_json(&node);
+        assert_eq!(written, "{\n  \"a\": [1, -2.5e3, \"true\", \"null\"],\n  \"b\": {\n    \"c\": \"line\\n\u{e9}\u{1f600}\"\n  },\n  \"d\": []\n}\n");
+        assert_eq!(to_json(&parse_json(&written).unwrap()), written);
+    }
+
+    #[test]
+    fn json_errors_have_positions() {
+        assert_eq!(parse_json("[1, 2").unwrap_err().to_string(), "Line 1, column 6: Expected ',' or ']'.");
+        assert_eq!(parse_json("{\n  \"a\": 1,\n  \"a\": 2\n}").unwrap_err().to_string(), "Line 3, column 3: Duplicate key 'a'.");
+        assert_eq!(parse_json("[01]").unwrap_err().to_string(), "Line 1, column 2: Expected a value.");
+        assert_eq!(parse_json("[1] 2").unwrap_err().to_string(), "Line 1, column 5: Unexpected characters after the document.");
+        assert_eq!(parse_json("\"abc").unwrap_err().to_string(), "Line 1, column 1: Unclosed string.");
+        assert_eq!(parse_json("{1: 2}").unwrap_err().to_string(), "Line 1, column 2: Expected a string key.");
+        // scene errors point into the json as well
+        assert_eq!(parse_scene_json("[\n  {\"add\": \"sphere\", \"size\": 2}\n]").err().unwrap().to_string(),
+                   "Line 2, column 29: Unknown attribute 'size'.");
+    }
+
+    fn full_scene() -> Scene {
+        let mut w = world();
+        w.environment = sky_environment(sky(vector(1.0, 0.4, -0.3), 4.5).set_intensity(0.2));
+        w.lights.push(point_light(point(-10.0, 10.0, -10.0), colour(1.0, 0.9, 0.8)).set_attenuation(attenuation(1.0, 0.1, 0.01)));
+        w.lights.push(directional_light(vector(0.3, -1.0, 0.2), colour(0.5, 0.5, 0.5)));
+        w.lights.push(spot_light(point_i(0, 5, 0), vector_i(0, -1, 0), PI / 8.0, PI / 6.0, colour(1.0, 1.0, 1.0)));
+        w.lights.push(area_light(point_i(-1, 5, -1), vector(2.0, 0.0, 0.0), 3, vector(0.0, 0.0, 2.0), 7, colour(0.3, 0.3, 0.3)));
+        w.lights.push(spherical_light(point_i(2, 3, 4), 0.25, 4, colour(0.1, 0.2, 0.3)));
+
+        let mut shiny = material();
+        shiny.colour = colour(0.1, 0.2, 0.3);
+        shiny.shininess = 17.25;
+        shiny.emission = colour(0.0, 0.5, 0.0);
+        w.objects.push(sphere().set_material(shiny).set_transform(identity().scale(1.0, 0.5, 1.0).rotate_z(PI / 7.0).translate(0.1, 0.2, 0.3)).unwrap());
+        let mut metal = pbr_material(colour(0.9, 0.6, 0.2), 1.0, 0.35);
+        metal.pbr = metal.pbr.map(|mut parameters| {
+            parameters.specular = 0.8;
+            parameters.base_colour = colour(0.3, 0.3, 0.3);
+            parameters
+        });
+        w.objects.push(triangle(point(0.0, 0.1, 0.0), point(1.0 / 3.0, 0.0, 0.0), point(0.0, 2.0, 1e-9)).set_material(metal)
+            .set_motion(translation(0.0, 1.0, 0.0)).unwrap());
+        w.objects.push(sphere().set_transform(translation(1.5, -2.0, 0.25) * scaling(0.75, 0.75, 0.75)).unwrap());
+
+        let c = camera(64, 48, 1.1)
+            .set_transform(view_transform(point(0.3, 1.5, -5.0), point(0.0, 1.0, 0.0), vector(0.0, 1.0, 0.0))).unwrap()
+            .set_lens(0.05, 4.5, Bokeh::Polygon { blades: 6, rotation: 0.2 })
+            .set_shutter(0.1, 0.6)
+            .set_projection(Projection::Orthographic { width: 3.5 });
+        Scene { camera: c, world: w }
+    }
+
+    #[test]
+    fn scenes_round_trip() {
+        // the debug output has every field and every digit, unlike the lenient comparisons it tells 1 from -1
+        let exact = |value: &dyn std::fmt::Debug| format!("{:?}", value);
+        let scene = full_scene();
+        let text = scene_to_json(&scene).unwrap();
+        let loaded = parse_scene_json(&text).unwrap();
+        assert_eq!(exact(&loaded.camera), exact(&scene.camera));
+        assert_eq!(exact(&loaded.world.lights), exact(&scene.world.lights));
+        assert_eq!(exact(&loaded.world.objects), exact(&scene.world.objects));
+        // the sky normalises its sun direction again when it is loaded, which can change the last digit
+        let direction = vector(0.2, 0.5, -0.4);
+        let (sky, loaded_sky) = (scene.world.environment.colour(direction), loaded.world.environment.colour(direction));
+        assert!([sky.red - loaded_sky.red, sky.green - loaded_sky.green, sky.blue - loaded_sky.blue].iter().all(|d| d.abs() < 1e-12));
+
+        // a centre and radius load as the same unit sphere with a transform
+        let centred = parse_scene("- add: camera\n  width: 1\n  height: 1\n  field-of-view: 1\n  from: [0, 0, -5]\n  to: [0, 0, 0]\n  up: [0, 1, 0]\n\
+                                   - add: sphere\n  centre: [1.5, -2, 0.25]\n  radius: 0.75\n").unwrap();
+        assert_eq!(exact(&centred.world.objects[0]), exact(&scene.world.objects[2]));
+
+        let path = std::env::temp_dir().join(format!("scene-round-trip-{}.json", std::process::id()));
+        let path = path.to_string_lossy();
+        let saved = save_scene(&scene, &path);
+        let from_file = load_scene(&path);
+        let _ = std::fs::remove_file(&*path);
+        saved.unwrap();
+        assert_eq!(exact(&from_file.unwrap().world.objects), exact(&scene.world.objects));
+    }
+
+    #[test]
+    fn saving_unsupported_scenes() {
+        let mut scene = full_scene();
+        scene.world.environment = image_environment(&canvas(2, 1), 1.0, 0.0);
+        assert!(matches!(scene_to_json(&scene), Err(Error::Unsupported(_))));
+        scene.world.environment = gradient_environment(colour(0.0, 0.0, 0.0), colour(0.0, 0.0, 1.0));
+        assert!(scene_to_json(&scene).is_ok());
+        scene.world.lights.push(point_light(point(f64::INFINITY, 0.0, 0.0), colour(1.0, 1.0, 1.0)));
+        assert!(matches!(scene_to_json(&scene), Err(Error::Unsupported(_))));
+    }
+}
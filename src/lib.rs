@@ -0,0 +1,74 @@
+// the renderer as a library, `prelude` has what most programs need to build and render a scene
+
+pub mod tuple;
+pub mod colour;
+pub mod util;
+pub mod error;
+pub mod canvas;
+pub mod matrix;
+pub mod ray;
+pub mod objects;
+pub mod materials;
+pub mod lights;
+pub mod world;
+pub mod hdr;
+pub mod tone_mapping;
+pub mod parallel;
+pub mod camera;
+pub mod sampling;
+pub mod adaptive;
+pub mod random;
+pub mod png;
+pub mod progressive;
+pub mod pbr;
+pub mod obj;
+pub mod environment;
+pub mod sky;
+pub mod stereo;
+pub mod yaml;
+pub mod json;
+pub mod scene;
+pub mod integrator;
+pub mod motion;
+
+pub mod prelude {
+    pub use crate::camera::{Bokeh, camera, Camera, Projection, view_transform};
+    pub use crate::canvas::{canvas, Canvas};
+    pub use crate::colour::{BLACK, BLUE, colour, Colour, GREEN, RED, WHITE};
+    pub use crate::environment::{constant_environment, Environment, gradient_environment, image_environment,
+                                 load_environment, sky_environment};
+    pub use crate::error::Error;
+    pub use crate::integrator::{Integrator, path_tracing};
+    pub use crate::lights::{area_light, directional_light, Light, point_light, spherical_light, spot_light};
+    pub use crate::materials::{Material, material, pbr_material};
+    pub use crate::matrix::{identity, Matrix, rotation_x, rotation_y, rotation_z, scaling, shearing, translation};
+    pub use crate::objects::{Object, sphere, triangle};
+    pub use crate::obj::parse_obj;
+    pub use crate::ray::{ray, Ray};
+    pub use crate::sampling::{Filter, NO_SUPERSAMPLING, SamplePattern, supersampling};
+    pub use crate::scene::{load_scene, parse_scene, save_scene, Scene};
+    pub use crate::sky::{sky, Sky, sun_direction};
+    pub use crate::tone_mapping::{output_transform, ToneMapping};
+    pub use crate::tuple::{point, Tuple, vector};
+    pub use crate::world::{world, World};
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn rendering_with_the_prelude_alone() {
+        let mut w = world();
+        let mut m = material();
+        m.colour = colour(1.0, 0.2, 1.0);
+        w.objects.push(sphere().set_material(m));
+        w.lights.push(point_light(point(-10.0, 10.0, -10.0), WHITE));
+        let c = camera(11, 11, PI / 2.0).set_transform(view_transform(point(0.0, 0.0, -5.0), point(0.0, 0.0, 0.0), vector(0.0, 1.0, 0.0))).unwrap();
+        let image = c.render(&NO_SUPERSAMPLING, 1, |r| w.colour_at(r));
+        assert!(image.pixel_at(5, 5).red > 0.1);
+        assert_eq!(image.pixel_at(0, 0), BLACK);
+    }
+}
@@ -0,0 +1,455 @@
+use std::f64::consts::PI;
+
+use crate::colour::Colour;
+use crate::random::{random_from, Random};
+use crate::tuple::{cross_product, dot_product, point, Tuple, vector};
+
+pub const NO_ATTENUATION: Attenuation = Attenuation {
+    constant: 1.0,
+    linear: 0.0,
+    quadratic: 0.0,
+};
+
+// directional lights have no position, this is how far away from the origin they are placed when one is needed
+const DIRECTIONAL_DISTANCE: f64 = 1e6;
+
+// the light reaching a point at distance d is divided by constant + linear * d + quadratic * d^2
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Attenuation {
+    pub constant: f64,
+    pub linear: f64,
+    pub quadratic: f64,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Light {
+    Point {
+        position: Tuple,
+        intensity: Colour,
+        attenuation: Attenuation,
+    },
+    // parallel rays travelling along `direction`, like the sun
+    Directional {
+        direction: Tuple,
+        intensity: Colour,
+    },
+    // full intensity inside `inner_angle` around `direction`, fading out smoothly until `outer_angle`
+    Spot {
+        position: Tuple,
+        direction: Tuple,
+        inner_angle: f64,
+        outer_angle: f64,
+        intensity: Colour,
+        attenuation: Attenuation,
+    },
+    // rectangle spanned by `uvec` and `vvec` from `corner`, split into usteps * vsteps cells
+    Rectangle {
+        corner: Tuple,
+        uvec: Tuple,
+        usteps: u32,
+        vvec: Tuple,
+        vsteps: u32,
+        intensity: Colour,
+    },
+    // sampled on the disk of the sphere that faces the lit point, with steps * steps cells
+    Spherical {
+        centre: Tuple,
+        radius: f64,
+        steps: u32,
+        intensity: Colour,
+    },
+}
+
+pub fn point_light(position: Tuple, intensity: Colour) -> Light {
+    Light::Point {
+        position,
+        intensity,
+        attenuation: NO_ATTENUATION,
+    }
+}
+
+pub fn directional_light(direction: Tuple, intensity: Colour) -> Light {
+    Light::Directional {
+        direction: direction.normalize(),
+        intensity,
+    }
+}
+
+// the angles are measured from `direction` to the edge of the cone, in radians
+pub fn spot_light(position: Tuple, direction: Tuple, inner_angle: f64, outer_angle: f64, intensity: Colour) -> Light {
+    Light::Spot {
+        position,
+        direction: direction.normalize(),
+        inner_angle: inner_angle.min(outer_angle),
+        outer_angle,
+        intensity,
+        attenuation: NO_ATTENUATION,
+    }
+}
+
+pub fn attenuation(constant: f64, linear: f64, quadratic: f64) -> Attenuation {
+    Attenuation {
+        constant,
+        linear,
+        quadratic,
+    }
+}
+
+pub fn area_light(corner: Tuple, full_uvec: Tuple, usteps: u32, full_vvec: Tuple, vsteps: u32, intensity: Colour) -> Light {
+    let usteps = usteps.max(1);
+    let vsteps = vsteps.max(1);
+    Light::Rectangle {
+        corner,
+        uvec: full_uvec / usteps as f64,
+        usteps,
+        vvec: full_vvec / vsteps as f64,
+        vsteps,
+        intensity,
+    }
+}
+
+pub fn spherical_light(centre: Tuple, radius: f64, steps: u32, intensity: Colour) -> Light {
+    Light::Spherical {
+        centre,
+        radius,
+        steps: steps.max(1),
+        intensity,
+    }
+}
+
+impl Light {
+    pub fn intensity(&self) -> Colour {
+        match self {
+            Light::Point { intensity, .. } |
+            Light::Directional { intensity, .. } |
+            Light::Spot { intensity, .. } |
+            Light::Rectangle { intensity, .. } |
+            Light::Spherical { intensity, .. } => *intensity,
+        }
+    }
+
+    pub fn position(&self) -> Tuple {
+        match self {
+            Light::Point { position, .. } |
+            Light::Spot { position, .. } => *position,
+            Light::Directional { direction, .. } => point(0.0, 0.0, 0.0) - *direction * DIRECTIONAL_DISTANCE,
+            Light::Rectangle { corner, uvec, usteps, vvec, vsteps, .. } => {
+                *corner + *uvec * (*usteps as f64 / 2.0) + *vvec * (*vsteps as f64 / 2.0)
+            }
+            Light::Spherical { centre, .. } => *centre,
+        }
+    }
+
+    // only point and spot lights can be attenuated, other lights are returned unchanged
+    pub fn set_attenuation(self, new_attenuation: Attenuation) -> Light {
+        let mut light = self;
+        match &mut light {
+            Light::Point { attenuation, .. } |
+            Light::Spot { attenuation, .. } => *attenuation = new_attenuation,
+            _ => {}
+        }
+        light
+    }
+
+    // the direction from `point` towards the light and the distance to it, infinite for directional lights
+    pub fn direction_from(&self, point: Tuple) -> (Tuple, f64) {
+        match self {
+            Light::Directional { direction, .. } => (-*direction, f64::INFINITY),
+            _ => {
+                let v = self.position() - point;
+                (v.normalize(), v.magnitude())
+            }
+        }
+    }
+
+    // how much of the light's intensity reaches `point` because of distance attenuation and the cone of spot
+    // lights, shadows are not taken into account
+    pub fn falloff(&self, point: Tuple) -> f64 {
+        match self {
+            Light::Point { position, attenuation, .. } => attenuation.factor((*position - point).magnitude()),
+            Light::Spot { position, direction, inner_angle, outer_angle, attenuation, .. } => {
+                let to_point = point - *position;
+                let distance = to_point.magnitude();
+                // a point at the light itself has no direction, it is treated as inside the cone
+                if distance == 0.0 {
+                    return attenuation.factor(0.0);
+                }
+                let cos_angle = dot_product(to_point / distance, *direction);
+                let cos_inner = inner_angle.cos();
+                let cos_outer = outer_angle.cos();
+                let cone = if cos_angle >= cos_inner {
+                    1.0
+                } else if cos_angle <= cos_outer {
+                    0.0
+                } else {
+                    let t = (cos_angle - cos_outer) / (cos_inner - cos_outer);
+                    t * t * (3.0 - 2.0 * t)
+                };
+                cone * attenuation.factor(distance)
+            }
+            _ => 1.0,
+        }
+    }
+
+    // jittered points on the light as seen from `point`, the random numbers are seeded from `point` so the
+    // same point always sees the same samples
+    pub fn sample_points(&self, point: Tuple) -> Vec<Tuple> {
+        match self {
+            Light::Point { position, .. } |
+            Light::Spot { position, .. } => vec![*position],
+            // one unit towards the light, only the direction matters
+            Light::Directional { direction, .. } => vec![point - *direction],
+            Light::Rectangle { corner, uvec, usteps, vvec, vsteps, .. } => {
+                let mut rng = point_random(point);
+                let mut points = Vec::with_capacity((usteps * vsteps) as usize);
+                for v in 0..*vsteps {
+                    for u in 0..*usteps {
+                        let jitter_u = rng.next_f64();
+                        let jitter_v = rng.next_f64();
+                        points.push(*corner + *uvec * (u as f64 + jitter_u) + *vvec * (v as f64 + jitter_v));
+                    }
+                }
+                points
+            }
+            Light::Spherical { centre, radius, steps, .. } => {
+                // the disk is perpendicular to the direction from the lit point to the centre of the light
+                let axis = (point - *centre).normalize();
+                let helper = if axis.x.abs() > 0.9 { vector(0.0, 1.0, 0.0) } else { vector(1.0, 0.0, 0.0) };
+                let tangent = cross_product(axis, helper).normalize();
+                let bitangent = cross_product(axis, tangent);
+
+                let mut rng = point_random(point);
+                let mut points = Vec::with_capacity((steps * steps) as usize);
+                for v in 0..*steps {
+                    for u in 0..*steps {
+                        let s = (u as f64 + rng.next_f64()) / *steps as f64;
+                        let t = (v as f64 + rng.next_f64()) / *steps as f64;
+                        // equal area mapping of the cell onto the disk
+                        let r = radius * s.sqrt();
+                        let angle = 2.0 * PI * t;
+                        points.push(*centre + tangent * (r * angle.cos()) + bitangent * (r * angle.sin()));
+                    }
+                }
+                points
+            }
+        }
+    }
+}
+
+impl Attenuation {
+    pub fn factor(&self, distance: f64) -> f64 {
+        let denominator = self.constant + self.linear * distance + self.quadratic * distance * distance;
+        if denominator <= 0.0 {
+            return 1.0;
+        }
+        1.0 / denominator
+    }
+}
+
+fn point_random(point: Tuple) -> Random {
+    random_from(&[point.x.to_bits(), point.y.to_bits(), point.z.to_bits()])
+}
+
+#[cfg(test)]
+mod tests {
+    mod light_types_tests {
+        use std::f64::consts::PI;
+
+        use crate::colour::WHITE;
+        use crate::lights::{attenuation, directional_light, NO_ATTENUATION, point_light, spot_light};
+        use crate::materials::{lighting, material};
+        use crate::objects::sphere;
+        use crate::matrix::translation_i;
+        use crate::tuple::{point, point_i, vector_i};
+        use crate::world::{default_world, world};
+
+        #[test]
+        fn point_lights_are_not_attenuated_by_default() {
+            let light = point_light(point_i(0, 0, -10), WHITE);
+            assert_eq!(light.falloff(point_i(0, 0, 100)), 1.0);
+        }
+
+        #[test]
+        fn attenuation_divides_by_distance_terms() {
+            assert_eq!(NO_ATTENUATION.factor(5.0), 1.0);
+            assert_eq!(attenuation(1.0, 0.5, 0.0).factor(2.0), 0.5);
+            assert_eq!(attenuation(0.0, 0.0, 1.0).factor(4.0), 1.0 / 16.0);
+            let light = point_light(point_i(0, 0, 0), WHITE).set_attenuation(attenuation(1.0, 0.0, 1.0));
+            assert_eq!(light.falloff(point_i(0, 3, 0)), 0.1);
+        }
+
+        #[test]
+        fn directional_light_has_no_falloff() {
+            let light = directional_light(vector_i(0, -2, 0), WHITE);
+            assert_eq!(light.set_attenuation(attenuation(0.0, 1.0, 0.0)), light);
+            assert_eq!(light.falloff(point_i(100, -100, 3)), 1.0);
+            let (direction, distance) = light.direction_from(point_i(5, 5, 5));
+            assert_eq!(direction, vector_i(0, 1, 0));
+            assert!(distance.is_infinite());
+        }
+
+        #[test]
+        fn directional_light_rays_are_parallel() {
+            let light = directional_light(vector_i(0, 0, 1), WHITE);
+            let m = material();
+            let near = lighting(&m, &light, point_i(0, 0, 0), vector_i(0, 0, -1), vector_i(0, 0, -1), 1.0);
+            let far = lighting(&m, &light, point_i(50, -20, 1000), vector_i(0, 0, -1), vector_i(0, 0, -1), 1.0);
+            assert!((near.red - 1.9).abs() < 1e-9);
+            assert!((far.red - 1.9).abs() < 1e-9);
+        }
+
+        #[test]
+        fn directional_light_is_blocked_by_objects_at_any_distance() {
+            let mut w = world();
+            w.objects.push(sphere().set_transform(translation_i(0, 1000, 0)).unwrap());
+            let light = directional_light(vector_i(0, -1, 0), WHITE);
+            w.lights.push(light);
+            assert_eq!(w.intensity_at(&light, point_i(0, 0, 0), 0.0), 0.0);
+            assert_eq!(w.intensity_at(&light, point_i(5, 0, 0), 0.0), 1.0);
+        }
+
+        #[test]
+        fn spot_light_cone_falloff() {
+            let light = spot_light(point_i(0, 10, 0), vector_i(0, -1, 0), PI / 8.0, PI / 4.0, WHITE);
+            assert_eq!(light.falloff(point_i(0, 0, 0)), 1.0);
+            assert_eq!(light.falloff(point(10.0 * (PI / 10.0).tan(), 0.0, 0.0)), 1.0);
+            assert_eq!(light.falloff(point_i(20, 0, 0)), 0.0);
+            assert_eq!(light.falloff(point_i(0, 20, 0)), 0.0);
+            let edge = light.falloff(point(10.0 * (3.0 * PI / 16.0).tan(), 0.0, 0.0));
+            assert!(edge > 0.0 && edge < 1.0);
+            let nearer_edge = light.falloff(point(10.0 * (5.0 * PI / 32.0).tan(), 0.0, 0.0));
+            assert!(nearer_edge > edge);
+        }
+
+        #[test]
+        fn spot_light_can_be_attenuated() {
+            let light = spot_light(point_i(0, 2, 0), vector_i(0, -1, 0), 0.3, 0.5, WHITE)
+                .set_attenuation(attenuation(0.0, 0.5, 0.0));
+            assert_eq!(light.falloff(point_i(0, 0, 0)), 1.0);
+            assert_eq!(light.falloff(point_i(0, -2, 0)), 0.5);
+        }
+
+        #[test]
+        fn spot_light_falloff_at_its_own_position() {
+            let light = spot_light(point_i(0, 2, 0), vector_i(0, -1, 0), 0.3, 0.5, WHITE);
+            assert_eq!(light.falloff(point_i(0, 2, 0)), 1.0);
+            let light = light.set_attenuation(attenuation(2.0, 0.5, 0.0));
+            assert_eq!(light.falloff(point_i(0, 2, 0)), 0.5);
+        }
+
+        #[test]
+        fn spot_light_outside_the_cone_only_gives_ambient() {
+            let light = spot_light(point_i(0, 0, -10), vector_i(0, 1, 0), 0.2, 0.4, WHITE);
+            let c = lighting(&material(), &light, point_i(0, 0, 0), vector_i(0, 0, -1), vector_i(0, 0, -1), 1.0);
+            assert!((c.red - 0.1).abs() < 1e-9);
+        }
+
+        #[test]
+        fn spot_light_casts_shadows() {
+            let w = default_world();
+            let light = spot_light(point_i(-10, 10, -10), vector_i(1, -1, 1), 0.5, 0.6, WHITE);
+            assert_eq!(w.intensity_at(&light, point_i(10, -10, 10), 0.0), 0.0);
+            assert_eq!(w.intensity_at(&light, point_i(0, 10, 0), 0.0), 1.0);
+        }
+    }
+
+    mod area_light_tests {
+        use crate::colour::{colour, WHITE};
+        use crate::lights::{area_light, Light, point_light, spherical_light};
+        use crate::materials::{lighting, material};
+        use crate::matrix::scaling;
+        use crate::objects::sphere;
+        use crate::tuple::{point, point_i, vector, vector_i};
+        use crate::world::{default_world, world};
+
+        #[test]
+        fn creating_an_area_light() {
+            let light = area_light(point_i(0, 0, 0), vector_i(2, 0, 0), 4, vector_i(0, 0, 1), 2, WHITE);
+            match light {
+                Light::Rectangle { corner, uvec, usteps, vvec, vsteps, intensity } => {
+                    assert_eq!(corner, point_i(0, 0, 0));
+                    assert_eq!(uvec, vector(0.5, 0.0, 0.0));
+                    assert_eq!(usteps, 4);
+                    assert_eq!(vvec, vector(0.0, 0.0, 0.5));
+                    assert_eq!(vsteps, 2);
+                    assert_eq!(intensity, WHITE);
+                }
+                _ => panic!("expected a rectangular light"),
+            }
+            assert_eq!(light.position(), point(1.0, 0.0, 0.5));
+        }
+
+        #[test]
+        fn area_light_samples_lie_in_their_cells() {
+            let light = area_light(point_i(0, 0, 0), vector_i(2, 0, 0), 4, vector_i(0, 0, 1), 2, WHITE);
+            let samples = light.sample_points(point_i(3, 4, 5));
+            assert_eq!(samples.len(), 8);
+            for (i, sample) in samples.iter().enumerate() {
+                let u = (i % 4) as f64;
+                let v = (i / 4) as f64;
+                assert!(sample.x >= u * 0.5 && sample.x <= (u + 1.0) * 0.5);
+                assert!(sample.z >= v * 0.5 && sample.z <= (v + 1.0) * 0.5);
+                assert_eq!(sample.y, 0.0);
+            }
+            assert_eq!(samples, light.sample_points(point_i(3, 4, 5)));
+        }
+
+        #[test]
+        fn spherical_light_samples_face_the_point() {
+            let light = spherical_light(point_i(0, 5, 0), 1.0, 3, WHITE);
+            let samples = light.sample_points(point_i(0, 0, 0));
+            assert_eq!(samples.len(), 9);
+            for sample in samples {
+                // the disk faces straight down, towards the lit point
+                assert!((sample.y - 5.0).abs() < 1e-9);
+                assert!(sample.x * sample.x + sample.z * sample.z <= 1.0 + 1e-9);
+            }
+        }
+
+        #[test]
+        fn point_light_intensity_is_all_or_nothing() {
+            let w = default_world();
+            let light = w.lights[0];
+            for (pt, expected) in [(point(0.0, 1.0001, 0.0), 1.0), (point(-1.0001, 0.0, 0.0), 1.0), (point(0.0, 0.0, -1.0001), 1.0),
+                (point(0.0, 0.0, 1.0001), 0.0), (point(1.0001, 0.0, 0.0), 0.0), (point(0.0, -1.0001, 0.0), 0.0), (point(0.0, 0.0, 0.0), 0.0)] {
+                assert_eq!(w.intensity_at(&light, pt, 0.0), expected);
+            }
+        }
+
+        #[test]
+        fn area_light_intensity_is_fractional_in_the_penumbra() {
+            let mut w = world();
+            w.objects.push(sphere().set_transform(scaling(0.5, 0.5, 0.5)).unwrap());
+            let light = area_light(point(-2.0, 4.0, -2.0), vector_i(4, 0, 0), 8, vector_i(0, 0, 4), 8, WHITE);
+            w.lights.push(light);
+
+            // directly below the blocker everything is shadowed, far away nothing is
+            assert_eq!(w.intensity_at(&light, point(0.0, -0.6, 0.0), 0.0), 0.0);
+            assert_eq!(w.intensity_at(&light, point(6.0, -0.6, 0.0), 0.0), 1.0);
+            let penumbra = w.intensity_at(&light, point(0.5, -0.6, 0.0), 0.0);
+            assert!(penumbra > 0.0 && penumbra < 1.0);
+        }
+
+        #[test]
+        fn lighting_averages_over_the_area_light() {
+            let light = area_light(point(-0.5, -0.5, -5.0), vector_i(1, 0, 0), 2, vector_i(0, 1, 0), 2, WHITE);
+            let mut m = material();
+            m.ambient = 0.1;
+            m.diffuse = 0.9;
+            m.specular = 0.0;
+            m.colour = colour(1.0, 1.0, 1.0);
+            let pt = point(0.0, 0.0, -1.0);
+            let eyev = (point_i(0, 0, -5) - pt).normalize();
+            let normalv = vector_i(0, 0, -1);
+
+            let full = lighting(&m, &light, pt, eyev, normalv, 1.0);
+            let half = lighting(&m, &light, pt, eyev, normalv, 0.5);
+            // every sample is almost straight in front of the point
+            assert!(full.red > 0.95 && full.red <= 1.0);
+            assert!((half.red - 0.1 - (full.red - 0.1) / 2.0).abs() < 1e-9);
+
+            let point_full = lighting(&m, &point_light(point_i(0, 0, -5), WHITE), pt, eyev, normalv, 1.0);
+            assert!(point_full.red >= full.red);
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use std::f64::consts::PI;
+
+use crate::colour::{BLACK, Colour, WHITE};
+use crate::lights::Light;
+use crate::pbr::{pbr, Pbr};
+use crate::tuple::{dot_product, Tuple};
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Material {
+    pub colour: Colour,
+    pub ambient: f64,
+    pub diffuse: f64,
+    pub specular: f64,
+    pub shininess: f64,
+    // when set the surface is shaded with the microfacet brdf instead of phong, `colour` and `ambient` still apply
+    pub pbr: Option<Pbr>,
+    // light given off by the surface itself, independent of any light source
+    pub emission: Colour,
+}
+
+pub fn material() -> Material {
+    Material {
+        colour: WHITE,
+        ambient: 0.1,
+        diffuse: 0.9,
+        specular: 0.9,
+        shininess: 200.0,
+        pbr: None,
+        emission: BLACK,
+    }
+}
+
+pub fn pbr_material(base_colour: Colour, metallic: f64, roughness: f64) -> Material {
+    Material {
+        colour: base_colour,
+        pbr: Some(pbr(base_colour, metallic, roughness)),
+        ..material()
+    }
+}
+
+// phong shading, diffuse and specular light are averaged over the sample points of the light and scaled by
+// `intensity`, the fraction of the light that is not in shadow, and by the falloff of the light. light intensities
+// are irradiance at normal incidence, so the microfacet brdf is scaled by pi to match the phong diffuse term
+pub fn lighting(material: &Material, light: &Light, point: Tuple, eyev: Tuple, normalv: Tuple, intensity: f64) -> Colour {
+    let effective_colour = material.colour * light.intensity();
+    let ambient = effective_colour * material.ambient;
+    if intensity <= 0.0 {
+        return ambient;
+    }
+
+    let light_samples = light.sample_points(point);
+    let mut sum = BLACK;
+    for sample in &light_samples {
+        let lightv = (*sample - point).normalize();
+        let light_dot_normal = dot_product(lightv, normalv);
+        if light_dot_normal < 0.0 {
+            continue;
+        }
+        if let Some(pbr) = material.pbr {
+            sum = sum + pbr.brdf(normalv, eyev, lightv) * light.intensity() * (PI * light_dot_normal);
+            continue;
+        }
+        let diffuse = effective_colour * material.diffuse * light_dot_normal;
+
+        let reflectv = reflect(-lightv, normalv);
+        let reflect_dot_eye = dot_product(reflectv, eyev);
+        let specular = if reflect_dot_eye <= 0.0 {
+            BLACK
+        } else {
+            light.intensity() * material.specular * reflect_dot_eye.powf(material.shininess)
+        };
+        sum = sum + diffuse + specular;
+    }
+    ambient + sum * (intensity * light.falloff(point) / light_samples.len() as f64)
+}
+
+pub fn reflect(incoming: Tuple, normal: Tuple) -> Tuple {
+    incoming - normal * 2.0 * dot_product(incoming, normal)
+}
+
+#[cfg(test)]
+mod tests {
+    mod lighting_tests {
+        use crate::colour::{colour, WHITE};
+        use crate::lights::point_light;
+        use crate::materials::{lighting, material, reflect};
+        use crate::objects::sphere;
+        use crate::tuple::{point, point_i, vector, vector_i};
+
+        #[test]
+        fn reflecting_a_vector_approaching_at_45_degrees() {
+            let r = reflect(vector_i(1, -1, 0), vector_i(0, 1, 0));
+            assert_eq!(r, vector_i(1, 1, 0));
+        }
+
+        #[test]
+        fn reflecting_a_vector_off_a_slanted_surface() {
+            let r = reflect(vector_i(0, -1, 0), vector(f64::sqrt(2.0) / 2.0, f64::sqrt(2.0) / 2.0, 0.0));
+            assert_eq!(r, vector_i(1, 0, 0));
+        }
+
+        #[test]
+        fn default_material() {
+            let m = material();
+            assert_eq!(m.colour, WHITE);
+            assert_eq!(m.ambient, 0.1);
+            assert_eq!(m.diffuse, 0.9);
+            assert_eq!(m.specular, 0.9);
+            assert_eq!(m.shininess, 200.0);
+        }
+
+        #[test]
+        fn sphere_may_be_assigned_a_material() {
+            let mut m = material();
+            m.ambient = 1.0;
+            let s = sphere().set_material(m);
+            assert_eq!(s.material(), m);
+        }
+
+        #[test]
+        fn eye_between_light_and_surface() {
+            let light = point_light(point_i(0, 0, -10), WHITE);
+            let c = lighting(&material(), &light, point_i(0, 0, 0), vector_i(0, 0, -1), vector_i(0, 0, -1), 1.0);
+            assert!((c.red - 1.9).abs() < 1e-9);
+            assert!((c.blue - 1.9).abs() < 1e-9);
+        }
+
+        #[test]
+        fn eye_offset_45_degrees() {
+            let light = point_light(point_i(0, 0, -10), WHITE);
+            let eyev = vector(0.0, f64::sqrt(2.0) / 2.0, -f64::sqrt(2.0) / 2.0);
+            let c = lighting(&material(), &light, point_i(0, 0, 0), eyev, vector_i(0, 0, -1), 1.0);
+            assert!((c.red - 1.0).abs() < 1e-9);
+        }
+
+        #[test]
+        fn light_offset_45_degrees() {
+            let light = point_light(point_i(0, 10, -10), WHITE);
+            let c = lighting(&material(), &light, point_i(0, 0, 0), vector_i(0, 0, -1), vector_i(0, 0, -1), 1.0);
+            assert!((c.red - 0.7364).abs() < 1e-4);
+        }
+
+        #[test]
+        fn eye_in_path_of_reflection() {
+            let light = point_light(point_i(0, 10, -10), WHITE);
+            let eyev = vector(0.0, -f64::sqrt(2.0) / 2.0, -f64::sqrt(2.0) / 2.0);
+            let c = lighting(&material(), &light, point_i(0, 0, 0), eyev, vector_i(0, 0, -1), 1.0);
+            assert!((c.red - 1.6364).abs() < 1e-4);
+        }
+
+        #[test]
+        fn light_behind_surface() {
+            let light = point_light(point_i(0, 0, 10), WHITE);
+            let c = lighting(&material(), &light, point_i(0, 0, 0), vector_i(0, 0, -1), vector_i(0, 0, -1), 1.0);
+            assert!((c.red - 0.1).abs() < 1e-9);
+        }
+
+        #[test]
+        fn lighting_uses_light_intensity_to_attenuate_colour() {
+            let mut m = material();
+            m.ambient = 0.1;
+            m.diffuse = 0.9;
+            m.specular = 0.0;
+            m.colour = colour(1.0, 1.0, 1.0);
+            let light = point_light(point(0.0, 0.0, -10.0), WHITE);
+            let pt = point_i(0, 0, -1);
+            let c = lighting(&m, &light, pt, vector_i(0, 0, -1), vector_i(0, 0, -1), 0.5);
+            assert!((c.red - 0.55).abs() < 1e-9);
+            let c = lighting(&m, &light, pt, vector_i(0, 0, -1), vector_i(0, 0, -1), 0.0);
+            assert!((c.red - 0.1).abs() < 1e-9);
+        }
+    }
+}
@@ -59,7 +59,7 @@ pub fn rotation_x(rotation: f64) -> Matrix<4> {
 pub fn rotation_y(rotation: f64) -> Matrix<4> {
     let mut tranform = IDENTITY_MATRIX;
     tranform.data[0][0] = f64::cos(rotation);
-    tranform.data[0][2] = -f64::sin(rotation);
+    tranform.data[0][2] = f64::sin(rotation);
     tranform.data[2][0] = -f64::sin(rotation);
     tranform.data[2][2] = f64::cos(rotation);
     return tranform;
@@ -112,32 +112,26 @@ impl<const SIZE: usize> Matrix<SIZE> {
         }
         matrix::<SIZE>(rows)
     }
+}
 
-    pub fn submatrix(&self, delete_row: usize, delete_column: usize) -> Matrix<{ SIZE - 1 }> {
-        let mut submatrix = matrix_empty::<{ SIZE - 1 }>();
-        let mut column_modifier = 0;
-        let mut row_modifier = 0;
-        for row in 0..SIZE {
-            if delete_row != row {
-                let mut new_row = [0.0; SIZE - 1];
-                for column in 0..SIZE {
-                    if delete_column != column {
-                        new_row[column - column_modifier] = self.data[row][column]
-                    } else {
-                        column_modifier = 1;
-                    }
-                }
-                column_modifier = 0;
-                submatrix.data[row - row_modifier] = new_row;
-            } else {
-                row_modifier = 1;
-            }
+// copies `data` without one row and one column, `SMALLER` has to be one less than `SIZE`
+fn submatrix<const SIZE: usize, const SMALLER: usize>(data: &[[f64; SIZE]; SIZE], delete_row: usize, delete_column: usize) -> Matrix<SMALLER> {
+    let mut submatrix = matrix_empty::<SMALLER>();
+    let rows = (0..SIZE).filter(|row| *row != delete_row);
+    for (new_row, row) in submatrix.data.iter_mut().zip(rows) {
+        let columns = (0..SIZE).filter(|column| *column != delete_column);
+        for (value, column) in new_row.iter_mut().zip(columns) {
+            *value = data[row][column];
         }
-        submatrix
     }
+    submatrix
 }
 
 impl Matrix<4> {
+    pub fn submatrix(&self, delete_row: usize, delete_column: usize) -> Matrix<3> {
+        submatrix(&self.data, delete_row, delete_column)
+    }
+
     pub fn rotate_x(self, rotation: f64) -> Matrix<4> {
         let transform = rotation_x(rotation);
         transform * self
@@ -202,6 +196,10 @@ impl Matrix<4> {
 }
 
 impl Matrix<3> {
+    pub fn submatrix(&self, delete_row: usize, delete_column: usize) -> Matrix<2> {
+        submatrix(&self.data, delete_row, delete_column)
+    }
+
     pub fn invert(&self) -> Option<Matrix<3>> {
         if self.determinant() == 0.0 {
             return None;
@@ -305,3 +303,580 @@ impl Mul<Tuple> for Matrix<4> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    mod transformations {
+        use std::f64::consts::PI;
+
+        use crate::matrix::{identity, rotation_x, scaling_i, translation_i};
+        use crate::tuple::point_i;
+
+        #[test]
+        fn chained_transformations_must_be_applied_in_reverse_order() {
+            let p = point_i(1, 0, 1);
+            let rot = rotation_x(PI / 2.0);
+            let scale = scaling_i(5, 5, 5);
+            let translate = translation_i(10, 5, 7);
+            let transform = identity().rotate_x(PI / 2.0).scale(5.0, 5.0, 5.0).translate(10.0, 5.0, 7.0);
+
+            let chained_transformation = translate * scale * rot;
+            assert_eq!(chained_transformation * p, point_i(15, 0, 7));
+            assert_eq!(transform * p, point_i(15, 0, 7));
+        }
+
+        #[test]
+        fn individual_transformations_are_applied_in_sequence() {
+            let p = point_i(1, 0, 1);
+            let rot = rotation_x(PI / 2.0);
+            let scale = scaling_i(5, 5, 5);
+            let translate = translation_i(10, 5, 7);
+
+            // apply rotation first
+            let p = rot * p;
+            assert_eq!(p, point_i(1, -1, 0));
+
+            // then the scaling
+            let p = scale * p;
+            assert_eq!(p, point_i(5, -5, 0));
+
+            // then the translation
+            let p = translate * p;
+            assert_eq!(p, point_i(15, 0, 7));
+        }
+
+        mod shearing {
+            use crate::matrix::shearing_i;
+            use crate::tuple::point_i;
+
+            #[test]
+            fn shearing_transformation_moves_z_in_proportion_to_y() {
+                let transform = shearing_i(0, 0, 0, 0, 0, 1);
+                let p = point_i(2, 3, 4);
+
+                assert_eq!(transform * p, point_i(2, 3, 7));
+            }
+
+            #[test]
+            fn shearing_transformation_moves_z_in_proportion_to_x() {
+                let transform = shearing_i(0, 0, 0, 0, 1, 0);
+                let p = point_i(2, 3, 4);
+
+                assert_eq!(transform * p, point_i(2, 3, 6));
+            }
+
+            #[test]
+            fn shearing_transformation_moves_y_in_proportion_to_z() {
+                let transform = shearing_i(0, 0, 0, 1, 0, 0);
+                let p = point_i(2, 3, 4);
+
+                assert_eq!(transform * p, point_i(2, 7, 4));
+            }
+
+            #[test]
+            fn shearing_transformation_moves_y_in_proportion_to_x() {
+                let transform = shearing_i(0, 0, 1, 0, 0, 0);
+                let p = point_i(2, 3, 4);
+
+                assert_eq!(transform * p, point_i(2, 5, 4));
+            }
+
+            #[test]
+            fn shearing_transformation_moves_x_in_proportion_to_z() {
+                let transform = shearing_i(0, 1, 0, 0, 0, 0);
+                let p = point_i(2, 3, 4);
+
+                assert_eq!(transform * p, point_i(6, 3, 4));
+            }
+
+            #[test]
+            fn shearing_transformation_moves_x_in_proportion_to_y() {
+                let transform = shearing_i(1, 0, 0, 0, 0, 0);
+                let p = point_i(2, 3, 4);
+
+                assert_eq!(transform * p, point_i(5, 3, 4));
+            }
+        }
+
+        mod rotation {
+            use std::f64::consts::PI;
+
+            use crate::matrix::{rotation_x, rotation_y, rotation_z};
+            use crate::tuple::{point, point_i};
+
+            #[test]
+            fn rotating_point_around_z_axis() {
+                let p = point_i(0, 1, 0);
+                let half_quarter = rotation_z(PI / 4.0);
+                let full_quarter = rotation_z(PI / 2.0);
+
+                assert_eq!(half_quarter * p, point(-f64::sqrt(2.0) / 2.0, f64::sqrt(2.0) / 2.0, 0.0));
+                assert_eq!(full_quarter * p, point_i(-1, 0, 0));
+            }
+
+            #[test]
+            fn rotating_point_around_y_axis() {
+                let p = point_i(0, 0, 1);
+                let half_quarter = rotation_y(PI / 4.0);
+                let full_quarter = rotation_y(PI / 2.0);
+
+                assert_eq!(half_quarter * p, point(f64::sqrt(2.0) / 2.0, 0.0, f64::sqrt(2.0) / 2.0));
+                assert_eq!(full_quarter * p, point_i(1, 0, 0));
+            }
+
+            #[test]
+            fn rotation_around_y_axis_has_the_right_handedness() {
+                // compared per component, the tuple equality does not look at signs
+                let z_to_x = rotation_y(PI / 2.0) * point_i(0, 0, 1);
+                assert!((z_to_x.x - 1.0).abs() < 1e-12 && z_to_x.y.abs() < 1e-12 && z_to_x.z.abs() < 1e-12);
+                let x_to_minus_z = rotation_y(PI / 2.0) * point_i(1, 0, 0);
+                assert!(x_to_minus_z.x.abs() < 1e-12 && x_to_minus_z.y.abs() < 1e-12 && (x_to_minus_z.z + 1.0).abs() < 1e-12);
+                assert!((rotation_y(0.3).determinant() - 1.0).abs() < 1e-12);
+            }
+
+            #[test]
+            fn rotating_point_around_x_axis() {
+                let p = point_i(0, 1, 0);
+                let half_quarter = rotation_x(PI / 4.0);
+                let full_quarter = rotation_x(PI / 2.0);
+
+                assert_eq!(half_quarter * p, point(0.0, f64::sqrt(2.0) / 2.0, f64::sqrt(2.0) / 2.0));
+                assert_eq!(full_quarter * p, point_i(0, 0, 1));
+            }
+
+            #[test]
+            fn inverse_of_x_rotation_rotates_in_opposite_direction() {
+                let p = point_i(0, 1, 0);
+                let half_quarter = rotation_x(PI / 4.0);
+                let inverse = half_quarter.invert().unwrap();
+
+                assert_eq!(inverse * p, point(0.0, f64::sqrt(2.0) / 2.0, -f64::sqrt(2.0) / 2.0));
+            }
+        }
+
+        mod scaling {
+            use crate::matrix::scaling_i;
+            use crate::tuple::{point_i, vector_i};
+
+            #[test]
+            fn reflection_is_scaling_by_negative_value() {
+                let transform = scaling_i(-1, 1, 1);
+                let p = point_i(2, 3, 4);
+                assert_eq!(transform * p, point_i(-2, 3, 4))
+            }
+
+            #[test]
+            fn multiplying_by_inverse_of_scaling_matrix() {
+                let transform = scaling_i(2, 3, 4);
+                let inverted = transform.invert().unwrap();
+                let v = vector_i(-4, 6, 8);
+                assert_eq!(inverted * v, vector_i(-2, 2, 2))
+            }
+
+            #[test]
+            fn scaling_matrix_applied_to_vector() {
+                let transform = scaling_i(2, 3, 4);
+                let v = vector_i(-4, 6, 8);
+                assert_eq!(transform * v, vector_i(-8, 18, 32))
+            }
+
+            #[test]
+            fn scaling_matrix_applied_to_point() {
+                let transform = scaling_i(2, 3, 4);
+                let p = point_i(-4, 6, 8);
+                assert_eq!(transform * p, point_i(-8, 18, 32))
+            }
+        }
+
+        mod translation {
+            use crate::matrix::translation_i;
+            use crate::tuple::{point_i, vector_i};
+
+            #[test]
+            fn translation_does_not_affect_vectors() {
+                let transform = translation_i(5, -3, 2);
+                let v = vector_i(-3, 4, 5);
+                assert_eq!(transform * v, v);
+            }
+
+            #[test]
+            fn multiplying_by_inverse_of_translation_matrix() {
+                let transform = translation_i(5, -3, 2);
+                let inverse = transform.invert().unwrap();
+                let p = point_i(-3, 4, 5);
+                assert_eq!(inverse * p, point_i(-8, 7, 3));
+            }
+
+            #[test]
+            fn multiplying_by_a_translation_matrix() {
+                let transform = translation_i(5, -3, 2);
+                let p = point_i(-3, 4, 5);
+                assert_eq!(transform * p, point_i(2, 1, 7));
+            }
+        }
+    }
+
+    mod matrix_tests {
+        use crate::matrix::{IDENTITY_MATRIX, matrix};
+        use crate::tuple::Tuple;
+
+        #[test]
+        fn multiplying_product_by_its_inverse() {
+            let m1 = matrix::<4>([
+                [3.0, -9.0, 7.0, 3.0],
+                [3.0, -8.0, 2.0, -9.0],
+                [-4.0, 4.0, 4.0, 1.0],
+                [-6.0, 5.0, -1.0, 1.0],
+            ]);
+            let m2 = matrix::<4>([
+                [8.0, 2.0, 2.0, 2.0],
+                [3.0, -1.0, 7.0, 0.0],
+                [7.0, 0.0, 5.0, 4.0, ],
+                [6.0, -2.0, 0.0, 5.0],
+            ]);
+
+            let product = m1 * m2;
+            assert_eq!(product * m2.invert().unwrap(), m1)
+        }
+
+        #[test]
+        fn calculating_inverse_of_a_matrix() {
+            let m = matrix::<4>([
+                [-5.0, 2.0, 6.0, -8.0],
+                [1.0, -5.0, 1.0, 8.0],
+                [7.0, 7.0, -6.0, -7.0],
+                [1.0, -3.0, 7.0, 4.0],
+            ]);
+            let m_inverted = m.invert().unwrap();
+
+            assert_eq!(m.determinant(), 532.0);
+            assert_eq!(m.cofactor(2, 3), -160.0);
+            assert_eq!(m_inverted.data[3][2], (-160.0 / 532.0));
+            assert_eq!(m.cofactor(3, 2), 105.0);
+            assert_eq!(m_inverted.data[2][3], (105.0 / 532.0));
+            assert_eq!(m_inverted, matrix::<4>([
+                [0.21805, 0.45113, 0.24060, -0.04511],
+                [-0.80827, -1.45677, -0.44361, 0.52068],
+                [-0.07895, -0.22368, -0.05263, 0.19737],
+                [-0.52256, -0.81391, -0.30075, 0.30639],
+            ]));
+
+            let m2 = matrix::<4>([
+                [8.0, -5.0, 9.0, 2.0],
+                [7.0, 5.0, 6.0, 1.0],
+                [-6.0, 0.0, 9.0, 6.0],
+                [-3.0, 0.0, -9.0, -4.0],
+            ]);
+            let m2_inverted = m2.invert().unwrap();
+
+            assert_eq!(m2_inverted, matrix::<4>([
+                [-0.15385, -0.15385, -0.28205, -0.53846],
+                [-0.07692, 0.12308, 0.02564, 0.03077],
+                [0.35897, 0.35897, 0.43590, 0.92308],
+                [-0.69231, -0.69231, -0.76923, -1.92308],
+            ]));
+
+            let m3 = matrix::<4>([
+                [9.0, 3.0, 0.0, 9.0],
+                [-5.0, -2.0, -6.0, -3.0],
+                [-4.0, 9.0, 6.0, 4.0],
+                [-7.0, 6.0, 6.0, 2.0],
+            ]);
+            let m3_inverted = m3.invert().unwrap();
+
+            assert_eq!(m3_inverted, matrix::<4>([
+                [-0.04074, -0.07778, 0.14444, -0.22222],
+                [-0.07778, 0.03333, 0.36667, -0.33333],
+                [-0.02901, -0.14630, -0.10926, 0.12963],
+                [0.17778, 0.06667, -0.26667, 0.33333],
+            ]));
+        }
+
+        #[test]
+        fn invertibility_of_noninvertible_matrix() {
+            let m = matrix::<4>([
+                [-4.0, 2.0, -2.0, -3.0],
+                [9.0, 6.0, 2.0, 6.0],
+                [0.0, -5.0, 1.0, -5.0],
+                [0.0, 0.0, 0.0, 0.0],
+            ]);
+            assert_eq!(m.determinant(), 0.0);
+            assert_eq!(m.invert(), None)
+        }
+
+        #[test]
+        fn invertibility_of_invertible_matrix() {
+            let m = matrix::<4>([
+                [6.0, 4.0, 4.0, 4.0],
+                [5.0, 5.0, 7.0, 6.0],
+                [4.0, -9.0, 3.0, -7.0],
+                [9.0, 1.0, 7.0, -6.0],
+            ]);
+
+            assert_eq!(m.determinant(), -2120.0);
+            assert_ne!(m.invert(), None)
+        }
+
+        #[test]
+        fn calculating_determinant_of_4_by_4() {
+            let m = matrix::<4>([
+                [-2.0, -8.0, 3.0, 5.0],
+                [-3.0, 1.0, 7.0, 3.0],
+                [1.0, 2.0, -9.0, 6.0],
+                [-6.0, 7.0, 7.0, -9.0],
+            ]);
+            assert_eq!(m.cofactor(0, 0), 690.0);
+            assert_eq!(m.cofactor(0, 1), 447.0);
+            assert_eq!(m.cofactor(0, 2), 210.0);
+            assert_eq!(m.cofactor(0, 3), 51.0);
+            assert_eq!(m.determinant(), -4071.0);
+        }
+
+        #[test]
+        fn calculating_determinant_of_3_by_3() {
+            let m = matrix::<3>([
+                [1.0, 2.0, 6.0],
+                [-5.0, 8.0, -4.0],
+                [2.0, 6.0, 4.0],
+            ]);
+            assert_eq!(m.cofactor(0, 0), 56.0);
+            assert_eq!(m.cofactor(0, 1), 12.0);
+            assert_eq!(m.cofactor(0, 2), -46.0);
+            assert_eq!(m.determinant(), -196.0);
+        }
+
+        #[test]
+        fn calculating_cofactor() {
+            let m = matrix::<3>([
+                [3.0, 5.0, 0.0],
+                [2.0, -1.0, -7.0],
+                [6.0, -1.0, 5.0],
+            ]);
+
+            assert_eq!(m.minor(0, 0), -12.0);
+            assert_eq!(m.cofactor(0, 0), -12.0);
+            assert_eq!(m.minor(1, 0), 25.0);
+            assert_eq!(m.cofactor(1, 0), -25.0);
+        }
+
+        #[test]
+        fn calculating_minor() {
+            let m1 = matrix::<3>([
+                [3.0, 5.0, 0.0],
+                [2.0, -1.0, -7.0],
+                [6.0, -1.0, 5.0],
+            ]);
+            let m2 = m1.submatrix(1, 0);
+
+            assert_eq!(m2.determinant(), 25.0);
+            assert_eq!(m1.minor(1, 0), 25.0);
+        }
+
+        #[test]
+        fn small_submatrix() {
+            let m = matrix::<3>([
+                [1.0, 5.0, 0.0],
+                [-3.0, 2.0, 7.0],
+                [0.0, 6.0, -3.0],
+            ]);
+
+            let expected_result = matrix::<2>([
+                [-3.0, 2.0],
+                [0.0, 6.0],
+            ]);
+            assert_eq!(m.submatrix(0, 2), expected_result);
+        }
+
+        #[test]
+        fn larger_submatrix() {
+            let m = matrix::<4>([
+                [-6.0, 1.0, 1.0, 6.0],
+                [-8.0, 5.0, 8.0, 6.0],
+                [-1.0, 0.0, 8.0, 2.0],
+                [-7.0, 1.0, -1.0, 1.0],
+            ]);
+
+            let expected_result = matrix::<3>([
+                [-6.0, 1.0, 6.0],
+                [-8.0, 8.0, 6.0],
+                [-7.0, -1.0, 1.0],
+            ]);
+            assert_eq!(m.submatrix(2, 1), expected_result);
+        }
+
+        #[test]
+        fn determinant_of_matrix() {
+            let m = matrix::<2>([
+                [1.0, 5.0],
+                [-3.0, 2.0]
+            ]);
+            assert_eq!(m.determinant(), 17.0)
+        }
+
+        #[test]
+        fn transposing_matrix() {
+            let m = matrix([
+                [0.0, 9.0, 3.0, 0.0],
+                [9.0, 8.0, 0.0, 8.0],
+                [1.0, 8.0, 5.0, 3.0],
+                [0.0, 0.0, 5.0, 8.0]
+            ]);
+
+            let expected_result = matrix([
+                [0.0, 9.0, 1.0, 0.0],
+                [9.0, 8.0, 8.0, 0.0],
+                [3.0, 0.0, 5.0, 5.0],
+                [0.0, 8.0, 3.0, 8.0]
+            ]);
+            assert_eq!(m.transpose(), expected_result);
+        }
+
+        #[test]
+        fn transposing_identity_matrix() {
+            let transposed = IDENTITY_MATRIX.transpose();
+            assert_eq!(transposed, IDENTITY_MATRIX);
+        }
+
+        #[test]
+        fn matrix_multiply_by_identity_matrix() {
+            let m = matrix::<4>(
+                [[0.0, 1.0, 2.0, 3.0],
+                    [1.0, 2.0, 4.0, 8.0],
+                    [2.0, 4.0, 8.0, 16.0],
+                    [4.0, 8.0, 16.0, 32.0]]
+            );
+
+            assert_eq!(m * IDENTITY_MATRIX, m);
+        }
+
+        #[test]
+        fn matrix_multiply_with_tuple() {
+            let m = matrix::<4>(
+                [[1.0, 2.0, 3.0, 4.0],
+                    [2.0, 4.0, 4.0, 2.0],
+                    [8.0, 6.0, 4.0, 1.0],
+                    [0.0, 0.0, 0.0, 1.0]]
+            );
+            let t = Tuple {
+                x: 1.0,
+                y: 2.0,
+                z: 3.0,
+                w: 1.0,
+            };
+
+            let expected_result = Tuple {
+                x: 18.0,
+                y: 24.0,
+                z: 33.0,
+                w: 1.0,
+            };
+            assert_eq!(m * t, expected_result);
+        }
+
+        #[test]
+        fn matrix_multiplication() {
+            let m1 = matrix::<4>(
+                [[1.0, 2.0, 3.0, 4.0],
+                    [5.0, 6.0, 7.0, 8.0],
+                    [9.0, 8.0, 7.0, 6.0],
+                    [5.0, 4.0, 3.0, 2.0]]
+            );
+            let m2 = matrix::<4>(
+                [[-2.0, 1.0, 2.0, 3.0],
+                    [3.0, 2.0, 1.0, -1.0],
+                    [4.0, 3.0, 6.0, 5.0],
+                    [1.0, 2.0, 7.0, 8.0]]
+            );
+
+            let expected_result = matrix::<4>(
+                [[20.0, 22.0, 50.0, 48.0],
+                    [44.0, 54.0, 114.0, 108.0],
+                    [40.0, 58.0, 110.0, 102.0],
+                    [16.0, 26.0, 46.0, 42.0]]
+            );
+
+            assert!(m1 * m2 == expected_result);
+        }
+
+        #[test]
+        fn matrix_inequality() {
+            let m1 = matrix::<4>(
+                [[1.0, 2.0, 3.0, 4.0],
+                    [5.0, 6.0, 7.0, 8.0],
+                    [9.0, 8.0, 7.0, 6.0],
+                    [5.0, 4.0, 3.0, 2.0]]
+            );
+            let m2 = matrix::<4>(
+                [[2.0, 3.0, 4.0, 5.0],
+                    [6.0, 7.0, 8.0, 9.0],
+                    [8.0, 7.0, 6.0, 5.0],
+                    [4.0, 3.0, 2.0, 1.0]]
+            );
+
+            assert!(m1 != m2)
+        }
+
+        #[test]
+        fn matrix_equality() {
+            let m1 = matrix::<4>(
+                [[1.0, 2.0, 3.0, 4.0],
+                    [5.0, 6.0, 7.0, 8.0],
+                    [9.0, 8.0, 7.0, 6.0],
+                    [5.0, 4.0, 3.0, 2.0]]
+            );
+            let m2 = matrix::<4>(
+                [[1.0, 2.0, 3.0, 4.0],
+                    [5.0, 6.0, 7.0, 8.0],
+                    [9.0, 8.0, 7.0, 6.0],
+                    [5.0, 4.0, 3.0, 2.0]]
+            );
+
+            assert!(m1 == m2)
+        }
+
+        #[test]
+        fn three_by_three_matrix_works() {
+            let matrix = matrix::<3>(
+                [[-3.0, 5.0, 0.0],
+                    [1.0, -2.0, -7.0],
+                    [1.0, -2.0, 1.0]]
+            );
+            assert_eq!(matrix.data[0][0], -3.0);
+            assert_eq!(matrix.data[1][1], -2.0);
+            assert_eq!(matrix.data[2][2], 1.0);
+        }
+
+        #[test]
+        fn two_by_two_matrix_works() {
+            let matrix = matrix::<2>(
+                [[-3.0, 5.0],
+                    [1.0, -2.0]]
+            );
+
+            assert_eq!(matrix.data[0][0], -3.0);
+            assert_eq!(matrix.data[0][1], 5.0);
+            assert_eq!(matrix.data[1][0], 1.0);
+            assert_eq!(matrix.data[1][1], -2.0);
+        }
+
+        #[test]
+        fn constructing_and_inspecting_a_matrix() {
+            let matrix = matrix::<4>(
+                [
+                    [1.0, 2.0, 3.0, 4.0],
+                    [5.5, 6.5, 7.5, 8.5],
+                    [9.0, 10.0, 11.0, 12.0],
+                    [13.5, 14.5, 15.5, 16.5],
+                ]
+            );
+
+            assert_eq!(matrix.data[0][0], 1.0);
+            assert_eq!(matrix.data[0][3], 4.0);
+            assert_eq!(matrix.data[1][0], 5.5);
+            assert_eq!(matrix.data[1][2], 7.5);
+            assert_eq!(matrix.data[2][2], 11.0);
+            assert_eq!(matrix.data[3][0], 13.5);
+            assert_eq!(matrix.data[3][2], 15.5);
+        }
+    }
+}
@@ -0,0 +1,269 @@
+use crate::matrix::{matrix, Matrix};
+
+// a transformation split into translation, rotation and scale, which can be interpolated independently
+#[derive(Debug, PartialEq, Copy, Clone)]
+struct Decomposition {
+    translation: [f64; 3],
+    rotation: Quaternion,
+    scale: [f64; 3],
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+struct Quaternion {
+    w: f64,
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+// interpolates between two transformations, time 0 gives `start` and time 1 gives `end`
+pub fn interpolate_transform(start: Matrix<4>, end: Matrix<4>, time: f64) -> Matrix<4> {
+    if time <= 0.0 {
+        return start;
+    }
+    if time >= 1.0 {
+        return end;
+    }
+    match (decompose(start), decompose(end)) {
+        (Some(from), Some(to)) => {
+            let mut translation = [0.0; 3];
+            let mut scale = [0.0; 3];
+            for i in 0..3 {
+                translation[i] = lerp(from.translation[i], to.translation[i], time);
+                scale[i] = lerp(from.scale[i], to.scale[i], time);
+            }
+            compose(Decomposition {
+                translation,
+                rotation: slerp(from.rotation, to.rotation, time),
+                scale,
+            })
+        }
+        // degenerate transformations can not be decomposed, fall back to blending the matrices
+        _ => {
+            let mut blended = start;
+            for row in 0..4 {
+                for column in 0..4 {
+                    blended.data[row][column] = lerp(start.data[row][column], end.data[row][column], time);
+                }
+            }
+            blended
+        }
+    }
+}
+
+fn lerp(from: f64, to: f64, time: f64) -> f64 {
+    from + (to - from) * time
+}
+
+// assumes the transformation has no shearing, so the upper 3x3 part is a rotation times a scale
+fn decompose(transform: Matrix<4>) -> Option<Decomposition> {
+    let data = transform.data;
+    let mut scale = [0.0; 3];
+    let mut rotation = [[0.0; 3]; 3];
+    for column in 0..3 {
+        scale[column] = f64::sqrt(data[0][column].powi(2) + data[1][column].powi(2) + data[2][column].powi(2));
+        if scale[column] < 1e-12 {
+            return None;
+        }
+        for row in 0..3 {
+            rotation[row][column] = data[row][column] / scale[column];
+        }
+    }
+
+    // a mirrored transformation is represented by a negative scale on the x axis
+    let determinant = rotation[0][0] * (rotation[1][1] * rotation[2][2] - rotation[1][2] * rotation[2][1])
+        - rotation[0][1] * (rotation[1][0] * rotation[2][2] - rotation[1][2] * rotation[2][0])
+        + rotation[0][2] * (rotation[1][0] * rotation[2][1] - rotation[1][1] * rotation[2][0]);
+    if determinant < 0.0 {
+        scale[0] = -scale[0];
+        for row in rotation.iter_mut() {
+            row[0] = -row[0];
+        }
+    }
+
+    Some(Decomposition {
+        translation: [data[0][3], data[1][3], data[2][3]],
+        rotation: quaternion_from_rotation(rotation),
+        scale,
+    })
+}
+
+fn compose(decomposition: Decomposition) -> Matrix<4> {
+    let rotation = rotation_from_quaternion(decomposition.rotation);
+    let mut rows = [[0.0; 4]; 4];
+    for row in 0..3 {
+        for column in 0..3 {
+            rows[row][column] = rotation[row][column] * decomposition.scale[column];
+        }
+        rows[row][3] = decomposition.translation[row];
+    }
+    rows[3][3] = 1.0;
+    matrix::<4>(rows)
+}
+
+fn quaternion_from_rotation(m: [[f64; 3]; 3]) -> Quaternion {
+    let trace = m[0][0] + m[1][1] + m[2][2];
+    let q = if trace > 0.0 {
+        let s = f64::sqrt(trace + 1.0) * 2.0;
+        Quaternion { w: 0.25 * s, x: (m[2][1] - m[1][2]) / s, y: (m[0][2] - m[2][0]) / s, z: (m[1][0] - m[0][1]) / s }
+    } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+        let s = f64::sqrt(1.0 + m[0][0] - m[1][1] - m[2][2]) * 2.0;
+        Quaternion { w: (m[2][1] - m[1][2]) / s, x: 0.25 * s, y: (m[0][1] + m[1][0]) / s, z: (m[0][2] + m[2][0]) / s }
+    } else if m[1][1] > m[2][2] {
+        let s = f64::sqrt(1.0 + m[1][1] - m[0][0] - m[2][2]) * 2.0;
+        Quaternion { w: (m[0][2] - m[2][0]) / s, x: (m[0][1] + m[1][0]) / s, y: 0.25 * s, z: (m[1][2] + m[2][1]) / s }
+    } else {
+        let s = f64::sqrt(1.0 + m[2][2] - m[0][0] - m[1][1]) * 2.0;
+        Quaternion { w: (m[1][0] - m[0][1]) / s, x: (m[0][2] + m[2][0]) / s, y: (m[1][2] + m[2][1]) / s, z: 0.25 * s }
+    };
+    normalize(q)
+}
+
+fn rotation_from_quaternion(q: Quaternion) -> [[f64; 3]; 3] {
+    let Quaternion { w, x, y, z } = q;
+    [
+        [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - z * w), 2.0 * (x * z + y * w)],
+        [2.0 * (x * y + z * w), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - x * w)],
+        [2.0 * (x * z - y * w), 2.0 * (y * z + x * w), 1.0 - 2.0 * (x * x + y * y)],
+    ]
+}
+
+fn slerp(from: Quaternion, to: Quaternion, time: f64) -> Quaternion {
+    let mut to = to;
+    let mut cos = from.w * to.w + from.x * to.x + from.y * to.y + from.z * to.z;
+    // take the shorter way around
+    if cos < 0.0 {
+        to = Quaternion { w: -to.w, x: -to.x, y: -to.y, z: -to.z };
+        cos = -cos;
+    }
+    let (a, b) = if cos > 0.9995 {
+        (1.0 - time, time)
+    } else {
+        let angle = cos.acos();
+        let sin = angle.sin();
+        (((1.0 - time) * angle).sin() / sin, (time * angle).sin() / sin)
+    };
+    normalize(Quaternion {
+        w: a * from.w + b * to.w,
+        x: a * from.x + b * to.x,
+        y: a * from.y + b * to.y,
+        z: a * from.z + b * to.z,
+    })
+}
+
+fn normalize(q: Quaternion) -> Quaternion {
+    let length = f64::sqrt(q.w * q.w + q.x * q.x + q.y * q.y + q.z * q.z);
+    Quaternion { w: q.w / length, x: q.x / length, y: q.y / length, z: q.z / length }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::camera::camera;
+    use crate::colour::{BLACK, WHITE};
+    use crate::matrix::{identity, Matrix, rotation_z, scaling, translation, translation_i};
+    use crate::motion::interpolate_transform;
+    use crate::objects::sphere;
+    use crate::ray::{Intersections, ray, Ray, timed_ray};
+    use crate::sampling::{Filter, SamplePattern, supersampling};
+    use crate::tuple::{point, point_i, vector_i};
+
+    // entry by entry with signs, unlike the matrix equality
+    fn assert_matrix_close(actual: Matrix<4>, expected: Matrix<4>) {
+        let close = actual.data.iter().flatten().zip(expected.data.iter().flatten()).all(|(a, b)| (a - b).abs() < 1e-9);
+        assert!(close, "{:?} is not {:?}", actual.data, expected.data);
+    }
+
+    #[test]
+    fn rays_start_at_time_zero() {
+        let r = ray(point_i(1, 2, 3), vector_i(0, 1, 0));
+        assert_eq!(r.time, 0.0);
+    }
+
+    #[test]
+    fn transforming_a_ray_keeps_its_time() {
+        let r = timed_ray(point_i(1, 2, 3), vector_i(0, 1, 0), 0.75);
+        let r2 = r.transform(translation_i(3, 4, 5));
+        assert_eq!(r2.time, 0.75);
+    }
+
+    #[test]
+    fn static_objects_ignore_time() {
+        let s = sphere().set_transform(translation_i(1, 2, 3)).unwrap();
+        assert_eq!(s.transformation_at(0.7).data, translation_i(1, 2, 3).data);
+    }
+
+    #[test]
+    fn interpolating_translations() {
+        let m = interpolate_transform(translation_i(0, 0, 0), translation_i(10, -4, 2), 0.25);
+        assert_matrix_close(m, translation(2.5, -1.0, 0.5));
+    }
+
+    #[test]
+    fn interpolating_rotations_follows_the_arc() {
+        let m = interpolate_transform(rotation_z(0.0), rotation_z(PI / 2.0), 0.5);
+        assert_matrix_close(m, rotation_z(PI / 4.0));
+        let p = m * point_i(1, 0, 0);
+        assert!((p.x * p.x + p.y * p.y - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn interpolating_combined_transformations() {
+        let start = identity().scale(1.0, 1.0, 1.0).rotate_z(0.0).translate(0.0, 0.0, 0.0);
+        let end = identity().scale(3.0, 3.0, 3.0).rotate_z(PI / 2.0).translate(4.0, 0.0, 0.0);
+        let m = interpolate_transform(start, end, 0.5);
+        let expected = identity().scale(2.0, 2.0, 2.0).rotate_z(PI / 4.0).translate(2.0, 0.0, 0.0);
+        assert_matrix_close(m, expected);
+    }
+
+    #[test]
+    fn interpolation_endpoints_are_exact() {
+        let start = scaling(1.0, 2.0, 3.0);
+        let end = translation_i(1, 1, 1);
+        assert_eq!(interpolate_transform(start, end, 0.0).data, start.data);
+        assert_eq!(interpolate_transform(start, end, 1.0).data, end.data);
+    }
+
+    #[test]
+    fn intersecting_a_moving_sphere_depends_on_ray_time() {
+        let s = sphere().set_motion(translation_i(10, 0, 0)).unwrap();
+        let early = timed_ray(point_i(5, 0, -5), vector_i(0, 0, 1), 0.0);
+        let middle = timed_ray(point_i(5, 0, -5), vector_i(0, 0, 1), 0.5);
+
+        assert_eq!(early.intersect(&s), Intersections::None);
+        match middle.intersect(&s) {
+            Intersections::Some(intersections) => {
+                assert!((intersections[0].time - 4.0).abs() < 1e-9);
+                assert!((intersections[1].time - 6.0).abs() < 1e-9);
+            }
+            _ => panic!("moving sphere should be hit halfway through the shutter interval"),
+        }
+    }
+
+    #[test]
+    fn normal_on_a_moving_sphere() {
+        let s = sphere().set_motion(translation_i(0, 4, 0)).unwrap();
+        let n = s.normal_at_time(point(0.0, 3.0, 0.0), 0.5);
+        assert!((n.y - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn open_shutter_blurs_moving_objects() {
+        let s = sphere().set_transform(scaling(0.5, 0.5, 0.5)).unwrap().set_motion(identity().scale(0.5, 0.5, 0.5).translate(1.0, 0.0, 0.0)).unwrap();
+        let trace = |r: &Ray| match r.intersect(&s).hit() {
+            Some(..) => WHITE,
+            _ => BLACK,
+        };
+        let sampling = supersampling(SamplePattern::Jittered, 64, Filter::Box);
+        let c = camera(21, 11, PI / 3.0).set_transform(translation_i(0, 0, -5)).unwrap();
+
+        let still = c.render(&sampling, 2, trace);
+        let blurred = c.set_shutter(0.0, 1.0).render(&sampling, 2, trace);
+        // a pixel on the path of the sphere is only covered part of the time
+        let still_value = still.pixel_at(6, 5).red;
+        let blurred_value = blurred.pixel_at(6, 5).red;
+        assert_eq!(still_value, 0.0);
+        assert!(blurred_value > 0.1 && blurred_value < 0.9);
+    }
+}
@@ -0,0 +1,102 @@
+use crate::error::Error;
+use crate::objects::{Object, triangle};
+use crate::tuple::{point, Tuple};
+
+// reads the vertices and faces of a wavefront obj file, faces with more than three corners are split into a fan
+// of triangles, everything else (normals, texture coordinates, groups, materials) is ignored
+pub fn parse_obj(text: &str) -> Result<Vec<Object>, Error> {
+    let mut vertices: Vec<Tuple> = Vec::new();
+    let mut triangles = Vec::new();
+
+    for (number, line) in text.lines().enumerate() {
+        let number = number + 1;
+        let mut parts = line.split_whitespace();
+        match parts.next() {
+            Some("v") => {
+                let coordinates: Vec<f64> = parts.take(3)
+                    .map(|value| value.parse().map_err(|_| Error::Parse(format!("Line {}: invalid coordinate '{}'.", number, value))))
+                    .collect::<Result<_, _>>()?;
+                if coordinates.len() != 3 {
+                    return Err(Error::Parse(format!("Line {}: a vertex needs three coordinates.", number)));
+                }
+                vertices.push(point(coordinates[0], coordinates[1], coordinates[2]));
+            }
+            Some("f") => {
+                let corners: Vec<Tuple> = parts
+                    .map(|corner| vertex_of(corner, &vertices, number))
+                    .collect::<Result<_, _>>()?;
+                if corners.len() < 3 {
+                    return Err(Error::Parse(format!("Line {}: a face needs at least three vertices.", number)));
+                }
+                for i in 1..corners.len() - 1 {
+                    triangles.push(triangle(corners[0], corners[i], corners[i + 1]));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(triangles)
+}
+
+// corners look like `v`, `v/vt`, `v//vn` or `v/vt/vn`, negative indices count back from the last vertex
+fn vertex_of(corner: &str, vertices: &[Tuple], number: usize) -> Result<Tuple, Error> {
+    let index = corner.split('/').next().unwrap_or("");
+    let index: i64 = index.parse().map_err(|_| Error::Parse(format!("Line {}: invalid vertex index '{}'.", number, corner)))?;
+    let resolved = if index < 0 { vertices.len() as i64 + index } else { index - 1 };
+    if resolved < 0 || resolved >= vertices.len() as i64 {
+        return Err(Error::Parse(format!("Line {}: vertex {} does not exist.", number, index)));
+    }
+    Ok(vertices[resolved as usize])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+    use crate::obj::parse_obj;
+    use crate::objects::Object;
+    use crate::tuple::point_i;
+
+    #[test]
+    fn unknown_lines_are_ignored() {
+        let triangles = parse_obj("There was a young lady named Bright\nwho traveled much faster than light.\n").unwrap();
+        assert!(triangles.is_empty());
+    }
+
+    #[test]
+    fn faces_of_vertices() {
+        let text = "v -1 1 0\nv -1.0000 0.5000 0.0000\nv 1 0 0\nv 1 1 0\n\nf 1 2 3\nf 1 3 4\n";
+        let triangles = parse_obj(text).unwrap();
+        assert_eq!(triangles.len(), 2);
+        match triangles[1] {
+            Object::Triangle { p1, p2, p3, .. } => {
+                assert_eq!(p1, point_i(-1, 1, 0));
+                assert_eq!(p2, point_i(1, 0, 0));
+                assert_eq!(p3, point_i(1, 1, 0));
+            }
+            _ => panic!("expected a triangle"),
+        }
+    }
+
+    #[test]
+    fn polygons_become_triangle_fans() {
+        let text = "v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\nv 0 2 0\nf 1/1/1 2//1 3/4 4 -1\n";
+        let triangles = parse_obj(text).unwrap();
+        assert_eq!(triangles.len(), 3);
+        match triangles[2] {
+            Object::Triangle { p1, p2, p3, .. } => {
+                assert_eq!(p1, point_i(-1, 1, 0));
+                assert_eq!(p2, point_i(1, 1, 0));
+                assert_eq!(p3, point_i(0, 2, 0));
+            }
+            _ => panic!("expected a triangle"),
+        }
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        assert_eq!(parse_obj("v 1 2\n"), Err(Error::Parse(String::from("Line 1: a vertex needs three coordinates."))));
+        assert_eq!(parse_obj("v 1 2 3\nv 1 2 x\n"), Err(Error::Parse(String::from("Line 2: invalid coordinate 'x'."))));
+        assert_eq!(parse_obj("v 1 2 3\n\nf 1 2 3\n"), Err(Error::Parse(String::from("Line 3: vertex 2 does not exist."))));
+        assert_eq!(parse_obj("v 1 2 3\nf 1 1\n"), Err(Error::Parse(String::from("Line 2: a face needs at least three vertices."))));
+    }
+}
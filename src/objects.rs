@@ -1,6 +1,11 @@
+use std::f64::consts::PI;
+
+use crate::error::Error;
+use crate::materials::{material, Material};
 use crate::matrix::{IDENTITY_MATRIX, Matrix};
-use crate::objects::Object::Sphere;
-use crate::tuple::{ORIGIN, Tuple};
+use crate::motion::interpolate_transform;
+use crate::objects::Object::{Sphere, Triangle};
+use crate::tuple::{cross_product, ORIGIN, point, Tuple, vector};
 
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum Object {
@@ -8,44 +13,324 @@ pub enum Object {
         position: Tuple,
         radius: f64,
         transformation: Matrix<4>,
+        // transformation at the end of the shutter interval, the object is static if there is none
+        end_transformation: Option<Matrix<4>>,
+        material: Material,
+    },
+    // the edges and normal are precomputed from the corners, in object space
+    Triangle {
+        p1: Tuple,
+        p2: Tuple,
+        p3: Tuple,
+        e1: Tuple,
+        e2: Tuple,
+        normal: Tuple,
+        transformation: Matrix<4>,
+        end_transformation: Option<Matrix<4>>,
+        material: Material,
     },
 }
 
+// a point on the surface of an object, `pdf` is the density of picking it per unit of world space area
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct SurfaceSample {
+    pub point: Tuple,
+    pub normal: Tuple,
+    pub pdf: f64,
+}
+
 pub fn sphere() -> Object {
     Sphere {
         position: ORIGIN,
         radius: 1.0,
         transformation: IDENTITY_MATRIX,
+        end_transformation: None,
+        material: material(),
+    }
+}
+
+pub fn triangle(p1: Tuple, p2: Tuple, p3: Tuple) -> Object {
+    let e1 = p2 - p1;
+    let e2 = p3 - p1;
+    Triangle {
+        p1,
+        p2,
+        p3,
+        e1,
+        e2,
+        normal: cross_product(e2, e1).normalize(),
+        transformation: IDENTITY_MATRIX,
+        end_transformation: None,
+        material: material(),
     }
 }
 
 impl Object {
-    pub fn set_transform(self, transform: Matrix<4>) -> Object {
+    // fails for transformations without an inverse, the object could never be hit
+    pub fn set_transform(self, transform: Matrix<4>) -> Result<Object, Error> {
+        transform.invert().ok_or(Error::SingularTransform)?;
+        let mut object = self;
+        match &mut object {
+            Sphere { transformation, .. } |
+            Triangle { transformation, .. } => *transformation = transform,
+        }
+        Ok(object)
+    }
+
+    // the object moves from its transformation at time 0 to `end_transform` at time 1
+    pub fn set_motion(self, end_transform: Matrix<4>) -> Result<Object, Error> {
+        end_transform.invert().ok_or(Error::SingularTransform)?;
+        let mut object = self;
+        match &mut object {
+            Sphere { end_transformation, .. } |
+            Triangle { end_transformation, .. } => *end_transformation = Some(end_transform),
+        }
+        Ok(object)
+    }
+
+    pub fn set_material(self, new_material: Material) -> Object {
+        let mut object = self;
+        match &mut object {
+            Sphere { material, .. } |
+            Triangle { material, .. } => *material = new_material,
+        }
+        object
+    }
+
+    pub fn material(&self) -> Material {
         match self {
-            Sphere { position, radius, transformation: _ } => {
-                Sphere {
-                    position,
-                    radius,
-                    transformation: transform,
-                }
-            }
+            Sphere { material, .. } |
+            Triangle { material, .. } => *material,
         }
     }
 
-    pub fn normal_at(&self, point: Tuple) -> Tuple {
+    pub fn transformation_at(&self, time: f64) -> Matrix<4> {
         match self {
-            Sphere { position, radius: _, transformation } => {
-                let object_point = transformation.invert().unwrap() * point;
-                let object_normal = object_point - *position;
-                let world_normal = transformation.invert().unwrap().transpose() * object_normal;
-                let world_normal = Tuple {
-                    x: world_normal.x,
-                    y: world_normal.y,
-                    z: world_normal.z,
-                    w: 0.0,
-                };
-                world_normal.normalize()
+            Sphere { transformation, end_transformation, .. } |
+            Triangle { transformation, end_transformation, .. } => match end_transformation {
+                None => *transformation,
+                Some(end) => interpolate_transform(*transformation, *end, time),
+            },
+        }
+    }
+
+    pub fn normal_at(&self, point: Tuple) -> Tuple {
+        self.normal_at_time(point, 0.0)
+    }
+
+    // both ends of a motion can be inverted, but the blend between them can still flatten the object for an instant
+    pub fn inverse_transformation_at(&self, time: f64) -> Option<Matrix<4>> {
+        self.transformation_at(time).invert()
+    }
+
+    pub fn normal_at_time(&self, point: Tuple, time: f64) -> Tuple {
+        // rays never hit a flattened object, so there is no normal to get wrong
+        let inverse = self.inverse_transformation_at(time).unwrap_or(IDENTITY_MATRIX);
+        let object_normal = match self {
+            Sphere { position, .. } => inverse * point - *position,
+            Triangle { normal, .. } => *normal,
+        };
+        to_world_normal(inverse, object_normal)
+    }
+
+    // a uniformly distributed point on the surface, `u` and `v` are in [0, 1)
+    pub fn sample_surface(&self, u: f64, v: f64, time: f64) -> SurfaceSample {
+        let (object_point, object_normal, object_area) = match self {
+            Sphere { position, .. } => {
+                let z = 1.0 - 2.0 * u;
+                let radius = (1.0 - z * z).max(0.0).sqrt();
+                let angle = 2.0 * PI * v;
+                let normal = vector(radius * angle.cos(), radius * angle.sin(), z);
+                (*position + normal, normal, 4.0 * PI)
+            }
+            Triangle { p1, e1, e2, normal, .. } => {
+                // folding the unit square onto the triangle keeps the distribution uniform
+                let (u, v) = if u + v > 1.0 { (1.0 - u, 1.0 - v) } else { (u, v) };
+                (*p1 + *e1 * u + *e2 * v, *normal, cross_product(*e1, *e2).magnitude() / 2.0)
             }
+        };
+        let transform = self.transformation_at(time);
+        let inverse = match transform.invert() {
+            Some(inverse) => inverse,
+            // a flattened object has no area to emit from
+            None => return SurfaceSample { point: transform * object_point, normal: object_normal, pdf: 0.0 },
+        };
+        // nanson's formula, how much the transformation stretches the area around the point
+        let stretched = inverse.transpose() * object_normal;
+        let stretch = transform.determinant().abs() * vector(stretched.x, stretched.y, stretched.z).magnitude();
+        let world_point = transform * object_point;
+        SurfaceSample {
+            point: point(world_point.x, world_point.y, world_point.z),
+            normal: to_world_normal(inverse, object_normal),
+            pdf: 1.0 / (object_area * stretch),
         }
     }
 }
+
+fn to_world_normal(inverse: Matrix<4>, object_normal: Tuple) -> Tuple {
+    let world_normal = inverse.transpose() * object_normal;
+    let world_normal = Tuple {
+        x: world_normal.x,
+        y: world_normal.y,
+        z: world_normal.z,
+        w: 0.0,
+    };
+    world_normal.normalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::error::Error;
+    use crate::matrix::{rotation_z, scaling, translation, translation_i};
+    use crate::objects::{Object, sphere, triangle};
+    use crate::ray::{Intersections, ray, timed_ray};
+    use crate::tuple::{point, point_i, vector, vector_i};
+
+    fn default_triangle() -> Object {
+        triangle(point_i(0, 1, 0), point_i(-1, 0, 0), point_i(1, 0, 0))
+    }
+
+    #[test]
+    fn constructing_a_triangle() {
+        match default_triangle() {
+            Object::Triangle { e1, e2, normal, .. } => {
+                assert_eq!(e1, vector_i(-1, -1, 0));
+                assert_eq!(e2, vector_i(1, -1, 0));
+                assert_eq!(normal, vector_i(0, 0, -1));
+            }
+            _ => panic!("expected a triangle"),
+        }
+    }
+
+    #[test]
+    fn normal_of_a_triangle_is_the_same_everywhere() {
+        let t = default_triangle();
+        assert_eq!(t.normal_at(point(0.0, 0.5, 0.0)), vector_i(0, 0, -1));
+        assert_eq!(t.normal_at(point(-0.5, 0.75, 0.0)), vector_i(0, 0, -1));
+    }
+
+    #[test]
+    fn ray_parallel_to_triangle() {
+        let r = ray(point_i(0, -1, -2), vector_i(0, 1, 0));
+        assert_eq!(r.intersect(&default_triangle()), Intersections::None);
+    }
+
+    #[test]
+    fn ray_misses_the_edges() {
+        let t = default_triangle();
+        assert_eq!(ray(point_i(1, 1, -2), vector_i(0, 0, 1)).intersect(&t), Intersections::None);
+        assert_eq!(ray(point_i(-1, 1, -2), vector_i(0, 0, 1)).intersect(&t), Intersections::None);
+        assert_eq!(ray(point_i(0, -1, -2), vector_i(0, 0, 1)).intersect(&t), Intersections::None);
+    }
+
+    #[test]
+    fn ray_strikes_a_triangle() {
+        let r = ray(point(0.0, 0.5, -2.0), vector_i(0, 0, 1));
+        let t = default_triangle();
+        let xs = r.intersect(&t);
+        assert_eq!(xs.as_slice().len(), 1);
+        assert_eq!(xs.as_slice()[0].time, 2.0);
+        assert_eq!(xs.hit().unwrap().time, 2.0);
+    }
+
+    #[test]
+    fn triangle_behind_the_ray_is_not_a_hit() {
+        let r = ray(point(0.0, 0.5, 2.0), vector_i(0, 0, 1));
+        assert!(r.intersect(&default_triangle()).hit().is_none());
+    }
+
+    #[test]
+    fn surface_samples_of_a_scaled_sphere() {
+        let s = sphere().set_transform(translation(1.0, 0.0, 0.0) * scaling(2.0, 2.0, 2.0)).unwrap();
+        for (u, v) in [(0.1, 0.2), (0.5, 0.5), (0.9, 0.7)] {
+            let sample = s.sample_surface(u, v, 0.0);
+            assert!(((sample.point - point(1.0, 0.0, 0.0)).magnitude() - 2.0).abs() < 1e-9);
+            assert!((sample.pdf - 1.0 / (16.0 * PI)).abs() < 1e-12);
+            assert_eq!(sample.normal, (sample.point - point(1.0, 0.0, 0.0)).normalize());
+        }
+    }
+
+    #[test]
+    fn surface_samples_of_a_transformed_triangle() {
+        let t = default_triangle().set_transform(scaling(3.0, 2.0, 1.0)).unwrap();
+        for (u, v) in [(0.1, 0.2), (0.8, 0.9), (0.5, 0.4)] {
+            let sample = t.sample_surface(u, v, 0.0);
+            // the triangle covers an area of 1 before and 6 after scaling
+            assert!((sample.pdf - 1.0 / 6.0).abs() < 1e-12);
+            assert!(sample.point.y >= 0.0 && sample.point.y <= 2.0);
+            assert!(sample.point.x.abs() <= 3.0 * (1.0 - sample.point.y / 2.0) + 1e-9);
+            assert_eq!(sample.point.z, 0.0);
+            assert_eq!(sample.normal, vector(0.0, 0.0, -1.0));
+        }
+    }
+
+    #[test]
+    fn transforms_without_an_inverse_are_rejected() {
+        assert_eq!(sphere().set_transform(scaling(0.0, 1.0, 1.0)), Err(Error::SingularTransform));
+        assert_eq!(default_triangle().set_motion(scaling(1.0, 0.0, 1.0)), Err(Error::SingularTransform));
+    }
+
+    #[test]
+    fn objects_flattened_during_their_motion_are_missed() {
+        // mirroring the sphere passes through a scale of zero half way through the shutter interval
+        let s = sphere().set_motion(scaling(-1.0, 1.0, 1.0)).unwrap();
+        assert!(s.inverse_transformation_at(0.5).is_none());
+        assert_eq!(timed_ray(point_i(0, 0, -5), vector_i(0, 0, 1), 0.5).intersect(&s), Intersections::None);
+        assert_eq!(s.sample_surface(0.3, 0.6, 0.5).pdf, 0.0);
+        assert!(matches!(timed_ray(point_i(0, 0, -5), vector_i(0, 0, 1), 0.0).intersect(&s), Intersections::Some(..)));
+    }
+
+    #[test]
+    fn computing_normal_on_a_transformed_sphere() {
+        let s = sphere();
+        let m = scaling(1.0, 0.5, 1.0) * rotation_z(PI / 5.0);
+        let s = s.set_transform(m).unwrap();
+        let n = s.normal_at(point(0.0, f64::sqrt(2.0)/2.0, -f64::sqrt(2.0)/2.0));
+        assert_eq!(n, vector(0.0, 0.97014, -0.24254))
+    }
+
+    #[test]
+    fn computing_normal_on_a_translated_sphere() {
+        let s = sphere();
+        let s = s.set_transform(translation_i(0, 1, 0)).unwrap();
+        let n = s.normal_at(point(0.0, 1.70711, -0.70711));
+        assert_eq!(n, vector(0.0, 0.70711, -0.70711))
+    }
+
+    #[test]
+    fn normal_is_normalized() {
+        let s = sphere();
+        let n = s.normal_at(point(f64::sqrt(3.0) / 3.0, f64::sqrt(3.0) / 3.0, f64::sqrt(3.0) / 3.0));
+        assert_eq!(n, n.normalize())
+    }
+
+    #[test]
+    fn normal_on_a_sphere4() {
+        let s = sphere();
+        let n = s.normal_at(point(f64::sqrt(3.0) / 3.0, f64::sqrt(3.0) / 3.0, f64::sqrt(3.0) / 3.0));
+        assert_eq!(n, vector(f64::sqrt(3.0) / 3.0, f64::sqrt(3.0) / 3.0, f64::sqrt(3.0) / 3.0));
+    }
+
+    #[test]
+    fn normal_on_a_sphere3() {
+        let s = sphere();
+        let n = s.normal_at(point_i(0, 0, 1));
+        assert_eq!(n, vector_i(0, 0, 1));
+    }
+
+    #[test]
+    fn normal_on_a_sphere2() {
+        let s = sphere();
+        let n = s.normal_at(point_i(0, 1, 0));
+        assert_eq!(n, vector_i(0, 1, 0));
+    }
+
+    #[test]
+    fn normal_on_a_sphere1() {
+        let s = sphere();
+        let n = s.normal_at(point_i(1, 0, 0));
+        assert_eq!(n, vector_i(1, 0, 0));
+    }
+}
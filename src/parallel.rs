@@ -0,0 +1,104 @@
+use std::sync::Mutex;
+use std::thread;
+
+use crate::canvas::{canvas, Canvas, Tile};
+use crate::colour::Colour;
+
+pub const DEFAULT_TILE_SIZE: u32 = 16;
+
+// renders every pixel of a new canvas by calling `shade` with the pixel coordinates
+pub fn render_parallel<F>(width: u32, height: u32, threads: u32, tile_size: u32, shade: F) -> Canvas
+    where F: Fn(u32, u32) -> Colour + Sync {
+    let mut canvas = canvas(width, height);
+    render_into(&mut canvas, threads, tile_size, shade);
+    canvas
+}
+
+// small tiles are handed out from a shared queue, so threads that finish cheap tiles early simply
+// pick up more work instead of waiting for the slowest part of the image
+pub fn render_into<F>(canvas: &mut Canvas, threads: u32, tile_size: u32, shade: F)
+    where F: Fn(u32, u32) -> Colour + Sync {
+    let threads = if threads == 0 { available_threads() } else { threads };
+    let mut tiles = canvas.tiles_mut(tile_size, tile_size);
+    // the queue is popped from the back, reversing keeps the tiles in top to bottom order
+    tiles.reverse();
+    let queue = Mutex::new(tiles);
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                while let Some(mut tile) = next_tile(&queue) {
+                    render_tile(&mut tile, &shade);
+                }
+            });
+        }
+    });
+}
+
+pub fn available_threads() -> u32 {
+    thread::available_parallelism().map(|threads| threads.get() as u32).unwrap_or(1)
+}
+
+fn next_tile<'a>(queue: &Mutex<Vec<Tile<'a>>>) -> Option<Tile<'a>> {
+    // the queue is only poisoned if another worker panicked, the tiles left in it are still fine to render
+    queue.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).pop()
+}
+
+fn render_tile<F>(tile: &mut Tile, shade: &F)
+    where F: Fn(u32, u32) -> Colour {
+    for y in 0..tile.height {
+        let canvas_y = tile.y + y;
+        let tile_x = tile.x;
+        for (x, pixel) in tile.row_mut(y).iter_mut().enumerate() {
+            *pixel = shade(tile_x + x as u32, canvas_y);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::colour::{colour, Colour};
+    use crate::parallel::{render_parallel, DEFAULT_TILE_SIZE};
+
+    fn gradient(x: u32, y: u32) -> Colour {
+        colour(x as f64, y as f64, (x * y) as f64)
+    }
+
+    #[test]
+    fn every_pixel_is_rendered_for_sizes_not_divisible_by_tile_size() {
+        let c = render_parallel(37, 23, 4, 8, gradient);
+        assert_eq!(c.width, 37);
+        assert_eq!(c.height, 23);
+        for y in 0..23 {
+            for x in 0..37 {
+                let pixel = c.pixel_at(x, y);
+                assert_eq!(pixel.red, x as f64);
+                assert_eq!(pixel.green, y as f64);
+                assert_eq!(pixel.blue, (x * y) as f64);
+            }
+        }
+    }
+
+    #[test]
+    fn result_does_not_depend_on_thread_count() {
+        let single = render_parallel(50, 30, 1, DEFAULT_TILE_SIZE, gradient);
+        let multi = render_parallel(50, 30, 7, DEFAULT_TILE_SIZE, gradient);
+        for (a, b) in single.pixels.iter().zip(multi.pixels.iter()) {
+            assert_eq!(a.red, b.red);
+            assert_eq!(a.green, b.green);
+            assert_eq!(a.blue, b.blue);
+        }
+    }
+
+    #[test]
+    fn more_threads_than_tiles() {
+        let c = render_parallel(3, 2, 16, 64, gradient);
+        assert_eq!(c.pixel_at(2, 1).red, 2.0);
+    }
+
+    #[test]
+    fn zero_threads_uses_available_parallelism() {
+        let c = render_parallel(10, 10, 0, 3, gradient);
+        assert_eq!(c.pixel_at(9, 9).blue, 81.0);
+    }
+}
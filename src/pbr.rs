@@ -0,0 +1,274 @@
+use std::f64::consts::PI;
+
+use crate::colour::{BLACK, colour, Colour, WHITE};
+use crate::materials::reflect;
+use crate::sampling::cosine_hemisphere;
+use crate::tuple::{cross_product, dot_product, Tuple, vector};
+
+// roughness is clamped to this, perfectly smooth surfaces would make the distribution a dirac delta
+const MIN_ROUGHNESS: f64 = 0.03;
+
+// metallic-roughness parameters as used by gltf and most PBR authoring tools
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Pbr {
+    pub base_colour: Colour,
+    // 0 is a dielectric, 1 a metal that tints its reflections with the base colour
+    pub metallic: f64,
+    pub roughness: f64,
+    // reflectance of dielectrics at normal incidence, 0.5 gives the common 4%
+    pub specular: f64,
+}
+
+pub fn pbr(base_colour: Colour, metallic: f64, roughness: f64) -> Pbr {
+    Pbr {
+        base_colour,
+        metallic: metallic.clamp(0.0, 1.0),
+        roughness: roughness.clamp(0.0, 1.0),
+        specular: 0.5,
+    }
+}
+
+impl Pbr {
+    fn alpha(&self) -> f64 {
+        let roughness = self.roughness.max(MIN_ROUGHNESS);
+        roughness * roughness
+    }
+
+    fn f0(&self) -> Colour {
+        let dielectric = 0.08 * self.specular;
+        colour(dielectric, dielectric, dielectric) * (1.0 - self.metallic) + self.base_colour * self.metallic
+    }
+
+    // the brdf for light arriving along `lightv` and leaving along `eyev`, both pointing away from the surface
+    pub fn brdf(&self, normalv: Tuple, eyev: Tuple, lightv: Tuple) -> Colour {
+        let n_dot_l = dot_product(normalv, lightv);
+        let n_dot_v = dot_product(normalv, eyev);
+        if n_dot_l <= 0.0 || n_dot_v <= 0.0 {
+            return BLACK;
+        }
+        let halfv = (eyev + lightv).normalize();
+        let alpha = self.alpha();
+        let fresnel = schlick_fresnel(self.f0(), dot_product(eyev, halfv));
+        let distribution = ggx_distribution(dot_product(normalv, halfv), alpha);
+        let geometry = smith_g1(n_dot_v, alpha) * smith_g1(n_dot_l, alpha);
+        let specular = fresnel * (distribution * geometry / (4.0 * n_dot_l * n_dot_v));
+        let diffuse = self.base_colour * (1.0 - self.metallic) * (1.0 / PI);
+        diffuse * (WHITE - fresnel) + specular
+    }
+
+    // probability of sampling the specular lobe instead of the diffuse one
+    fn specular_probability(&self) -> f64 {
+        let f0 = self.f0();
+        let specular = (f0.red + f0.green + f0.blue) / 3.0;
+        let diffuse = (1.0 - self.metallic) * (self.base_colour.red + self.base_colour.green + self.base_colour.blue) / 3.0;
+        if specular + diffuse <= 0.0 {
+            return 0.5;
+        }
+        (specular / (specular + diffuse)).clamp(0.1, 0.9)
+    }
+
+    // density of `sample` picking `lightv`, with respect to solid angle
+    pub fn pdf(&self, normalv: Tuple, eyev: Tuple, lightv: Tuple) -> f64 {
+        let n_dot_l = dot_product(normalv, lightv);
+        if n_dot_l <= 0.0 {
+            return 0.0;
+        }
+        let halfv = (eyev + lightv).normalize();
+        let v_dot_h = dot_product(eyev, halfv);
+        let n_dot_h = dot_product(normalv, halfv);
+        let specular = if v_dot_h > 0.0 {
+            ggx_distribution(n_dot_h, self.alpha()) * n_dot_h / (4.0 * v_dot_h)
+        } else {
+            0.0
+        };
+        let probability = self.specular_probability();
+        probability * specular + (1.0 - probability) * n_dot_l / PI
+    }
+
+    // importance samples a direction for light arriving at the surface, `u` and `v` pick the direction and
+    // `lobe` the diffuse or specular part, returns the direction and the brdf times cosine divided by the pdf
+    pub fn sample(&self, normalv: Tuple, eyev: Tuple, lobe: f64, u: f64, v: f64) -> Option<(Tuple, Colour)> {
+        let lightv = if lobe < self.specular_probability() {
+            let halfv = ggx_half_vector(normalv, self.alpha(), u, v);
+            reflect(-eyev, halfv)
+        } else {
+            cosine_hemisphere(normalv, u, v)
+        };
+        let n_dot_l = dot_product(normalv, lightv);
+        let pdf = self.pdf(normalv, eyev, lightv);
+        if n_dot_l <= 0.0 || pdf <= 0.0 {
+            return None;
+        }
+        Some((lightv, self.brdf(normalv, eyev, lightv) * (n_dot_l / pdf)))
+    }
+}
+
+// trowbridge-reitz distribution of microfacet normals
+pub fn ggx_distribution(n_dot_h: f64, alpha: f64) -> f64 {
+    if n_dot_h <= 0.0 {
+        return 0.0;
+    }
+    let alpha2 = alpha * alpha;
+    let denominator = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+    alpha2 / (PI * denominator * denominator)
+}
+
+// smith masking for one direction, the geometry term is the product for the light and eye directions
+pub fn smith_g1(n_dot_x: f64, alpha: f64) -> f64 {
+    let alpha2 = alpha * alpha;
+    2.0 * n_dot_x / (n_dot_x + (alpha2 + (1.0 - alpha2) * n_dot_x * n_dot_x).sqrt())
+}
+
+pub fn schlick_fresnel(f0: Colour, cos: f64) -> Colour {
+    let weight = (1.0 - cos.clamp(0.0, 1.0)).powi(5);
+    f0 + (WHITE - f0) * weight
+}
+
+// a microfacet normal distributed by D(h) * cos(theta_h)
+fn ggx_half_vector(normalv: Tuple, alpha: f64, u: f64, v: f64) -> Tuple {
+    let helper = if normalv.x.abs() > 0.9 { vector(0.0, 1.0, 0.0) } else { vector(1.0, 0.0, 0.0) };
+    let tangent = cross_product(normalv, helper).normalize();
+    let bitangent = cross_product(normalv, tangent);
+
+    let cos_theta = ((1.0 - u) / (1.0 + (alpha * alpha - 1.0) * u)).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * v;
+    (tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + normalv * cos_theta).normalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::colour::{colour, WHITE};
+    use crate::integrator::path_tracing;
+    use crate::lights::{directional_light, point_light};
+    use crate::materials::{lighting, material, pbr_material};
+    use crate::objects::sphere;
+    use crate::pbr::{ggx_distribution, pbr, schlick_fresnel, smith_g1};
+    use crate::ray::ray;
+    use crate::tuple::{point_i, vector, vector_i};
+    use crate::world::world;
+
+    // integrates `f` over the hemisphere around +z with the midpoint rule
+    fn integrate_hemisphere<F>(f: F) -> f64 where F: Fn(f64, f64) -> f64 {
+        let steps = 400;
+        let mut total = 0.0;
+        for i in 0..steps {
+            let theta = (i as f64 + 0.5) / steps as f64 * PI / 2.0;
+            for j in 0..steps {
+                let phi = (j as f64 + 0.5) / steps as f64 * 2.0 * PI;
+                total += f(theta, phi) * theta.sin();
+            }
+        }
+        total * (PI / 2.0 / steps as f64) * (2.0 * PI / steps as f64)
+    }
+
+    #[test]
+    fn ggx_distribution_is_normalized() {
+        for alpha in [0.2, 0.5, 1.0] {
+            let projected_area = integrate_hemisphere(|theta, _| ggx_distribution(theta.cos(), alpha) * theta.cos());
+            assert!((projected_area - 1.0).abs() < 1e-2);
+        }
+    }
+
+    #[test]
+    fn smith_masking_is_one_at_normal_incidence() {
+        assert!((smith_g1(1.0, 0.3) - 1.0).abs() < 1e-12);
+        assert!(smith_g1(0.1, 0.3) < smith_g1(0.5, 0.3));
+    }
+
+    #[test]
+    fn fresnel_goes_from_f0_to_white() {
+        let f0 = colour(0.04, 0.5, 1.0);
+        let normal = schlick_fresnel(f0, 1.0);
+        assert_eq!((normal.red, normal.green, normal.blue), (0.04, 0.5, 1.0));
+        let grazing = schlick_fresnel(f0, 0.0);
+        assert_eq!((grazing.red, grazing.green, grazing.blue), (1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn sampling_pdf_integrates_to_one() {
+        let surface = pbr(colour(0.8, 0.6, 0.4), 0.5, 0.4);
+        let normalv = vector_i(0, 0, 1);
+        let eyev = vector(0.6, 0.0, 0.8);
+        let total = integrate_hemisphere(|theta, phi| {
+            let lightv = vector(theta.sin() * phi.cos(), theta.sin() * phi.sin(), theta.cos());
+            surface.pdf(normalv, eyev, lightv)
+        });
+        assert!((total - 1.0).abs() < 2e-2);
+    }
+
+    #[test]
+    fn importance_sampling_matches_integration() {
+        let surface = pbr(WHITE, 1.0, 0.5);
+        let normalv = vector_i(0, 0, 1);
+        let eyev = vector(0.6, 0.0, 0.8);
+        let expected = integrate_hemisphere(|theta, phi| {
+            let lightv = vector(theta.sin() * phi.cos(), theta.sin() * phi.sin(), theta.cos());
+            surface.brdf(normalv, eyev, lightv).red * theta.cos()
+        });
+
+        let steps = 64;
+        let mut total = 0.0;
+        for lobe in [0.05, 0.95] {
+            for i in 0..steps {
+                for j in 0..steps {
+                    let u = (i as f64 + 0.5) / steps as f64;
+                    let v = (j as f64 + 0.5) / steps as f64;
+                    if let Some((_, weight)) = surface.sample(normalv, eyev, lobe, u, v) {
+                        total += weight.red;
+                    }
+                }
+            }
+        }
+        let estimate = total / (2 * steps * steps) as f64;
+        // a white metal reflects nearly everything, but never more than arrives
+        assert!(expected > 0.8 && expected <= 1.0);
+        assert!((estimate - expected).abs() < 2e-2);
+    }
+
+    #[test]
+    fn pbr_materials_keep_the_phong_fields() {
+        let m = pbr_material(colour(0.5, 0.5, 0.5), 1.0, 0.2);
+        assert_eq!(m.ambient, material().ambient);
+        assert_eq!(m.pbr.unwrap().metallic, 1.0);
+        assert_eq!(material().pbr, None);
+    }
+
+    #[test]
+    fn rough_dielectric_is_lit_like_lambert() {
+        let m = pbr_material(WHITE, 0.0, 1.0);
+        let light = point_light(point_i(0, 0, -10), WHITE);
+        let c = lighting(&m, &light, point_i(0, 0, 0), vector_i(0, 0, -1), vector_i(0, 0, -1), 1.0);
+        // ambient plus the diffuse part, minus the little that fresnel sends into the specular lobe
+        assert!(c.red > 1.0 && c.red < 1.15);
+    }
+
+    #[test]
+    fn smooth_metal_reflects_only_around_the_mirror_direction() {
+        let m = pbr_material(WHITE, 1.0, 0.1);
+        let light = point_light(point_i(0, 10, -10), WHITE);
+        let mirror = vector(0.0, -f64::sqrt(2.0) / 2.0, -f64::sqrt(2.0) / 2.0);
+        let highlight = lighting(&m, &light, point_i(0, 0, 0), mirror, vector_i(0, 0, -1), 1.0);
+        let off = lighting(&m, &light, point_i(0, 0, 0), vector_i(0, 0, -1), vector_i(0, 0, -1), 1.0);
+        assert!(highlight.red > 1.0);
+        assert!((off.red - 0.1).abs() < 1e-2);
+    }
+
+    #[test]
+    fn path_tracer_uses_the_brdf_for_direct_light() {
+        let mut w = world();
+        let surface = pbr(colour(0.5, 0.25, 1.0), 0.3, 0.6);
+        let mut m = pbr_material(surface.base_colour, surface.metallic, surface.roughness);
+        m.ambient = 0.0;
+        w.objects.push(sphere().set_material(m));
+        w.lights.push(directional_light(vector(0.0, -1.0, 1.0), WHITE));
+        let r = ray(point_i(0, 0, -5), vector_i(0, 0, 1));
+        let c = path_tracing(1, 0).colour_at(&w, &r);
+        let lightv = vector(0.0, 1.0, -1.0).normalize();
+        let expected = surface.brdf(vector_i(0, 0, -1), vector_i(0, 0, -1), lightv) * (PI * f64::sqrt(2.0) / 2.0);
+        assert!((c.red - expected.red).abs() < 1e-9);
+        assert!((c.blue - expected.blue).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,264 @@
+use std::time::{Duration, Instant};
+
+use crate::camera::Camera;
+use crate::canvas::{canvas, Canvas};
+use crate::colour::{BLACK, colour, Colour};
+use crate::error::Error;
+use crate::parallel::{DEFAULT_TILE_SIZE, render_parallel};
+use crate::random::pixel_random;
+use crate::ray::Ray;
+
+const ACCUMULATION_SIGNATURE: &str = "ACCUM";
+
+// running sum of all passes, dividing by the number of passes gives the current estimate of the image
+pub struct Accumulation {
+    pub width: u32,
+    pub height: u32,
+    pub sums: Vec<Colour>,
+    // every pass adds exactly one sample to every pixel
+    pub samples: u32,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct ProgressiveRender {
+    // total number of samples per pixel to stop at, including the ones of a resumed accumulation
+    pub samples: Option<u32>,
+    // stop starting new passes after this much time, the running pass is always finished
+    pub time_limit: Option<Duration>,
+    pub seed: u64,
+}
+
+pub fn accumulation(width: u32, height: u32) -> Accumulation {
+    Accumulation {
+        width,
+        height,
+        sums: vec![BLACK; width as usize * height as usize],
+        samples: 0,
+    }
+}
+
+// with neither a sample nor a time limit a single pass is rendered
+pub fn progressive_render(samples: Option<u32>, time_limit: Option<Duration>) -> ProgressiveRender {
+    ProgressiveRender {
+        samples,
+        time_limit,
+        seed: 0,
+    }
+}
+
+impl ProgressiveRender {
+    pub fn set_seed(self, seed: u64) -> ProgressiveRender {
+        ProgressiveRender {
+            seed,
+            ..self
+        }
+    }
+
+    fn finished(&self, accumulation: &Accumulation, passes: u32, start: Instant) -> bool {
+        if self.samples.is_none() && self.time_limit.is_none() {
+            return passes > 0;
+        }
+        let enough_samples = self.samples.is_some_and(|samples| accumulation.samples >= samples);
+        let out_of_time = self.time_limit.is_some_and(|limit| start.elapsed() >= limit);
+        enough_samples || out_of_time
+    }
+}
+
+impl Accumulation {
+    pub fn add_pass(&mut self, pass: &Canvas) -> Result<(), Error> {
+        if pass.width != self.width || pass.height != self.height {
+            return Err(Error::SizeMismatch(format!("Pass of size {}x{} does not match the accumulation of size {}x{}.",
+                                                   pass.width, pass.height, self.width, self.height)));
+        }
+        for (sum, pixel) in self.sums.iter_mut().zip(&pass.pixels) {
+            *sum = *sum + *pixel;
+        }
+        self.samples += 1;
+        Ok(())
+    }
+
+    // the average of all passes so far, black before the first pass
+    pub fn snapshot(&self) -> Canvas {
+        let mut image = canvas(self.width, self.height);
+        if self.samples == 0 {
+            return image;
+        }
+        let scale = 1.0 / self.samples as f64;
+        for (pixel, sum) in image.pixels.iter_mut().zip(&self.sums) {
+            *pixel = *sum * scale;
+        }
+        image
+    }
+
+    // the sums are stored as little endian f64, so a resumed render continues exactly where it stopped
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(format!("{}\n{} {} {}\n", ACCUMULATION_SIGNATURE, self.width, self.height, self.samples).as_bytes());
+        for sum in &self.sums {
+            bytes.extend_from_slice(&sum.red.to_le_bytes());
+            bytes.extend_from_slice(&sum.green.to_le_bytes());
+            bytes.extend_from_slice(&sum.blue.to_le_bytes());
+        }
+        bytes
+    }
+}
+
+pub fn accumulation_from_bytes(data: &[u8]) -> Result<Accumulation, Error> {
+    let mut lines = data.splitn(3, |byte| *byte == b'\n');
+    if lines.next() != Some(ACCUMULATION_SIGNATURE.as_bytes()) {
+        return Err(Error::Parse(String::from("Missing accumulation signature.")));
+    }
+    let header = lines.next().ok_or_else(|| Error::Parse(String::from("Missing accumulation header.")))?;
+    let header = String::from_utf8_lossy(header);
+    let values: Vec<u32> = header.split_whitespace()
+        .map(|value| value.parse().map_err(|_| Error::Parse(format!("Invalid accumulation header '{}'.", header))))
+        .collect::<Result<_, _>>()?;
+    if values.len() != 3 {
+        return Err(Error::Parse(format!("Invalid accumulation header '{}'.", header)));
+    }
+
+    // the size is checked against the data before the sums are allocated
+    let raster = lines.next().unwrap_or(&[]);
+    let expected = (values[0] as usize).checked_mul(values[1] as usize).and_then(|pixels| pixels.checked_mul(24));
+    if expected != Some(raster.len()) {
+        return Err(Error::Parse(String::from("Accumulation data does not match its size.")));
+    }
+    let mut result = accumulation(values[0], values[1]);
+    result.samples = values[2];
+    for (sum, bytes) in result.sums.iter_mut().zip(raster.chunks_exact(24)) {
+        let value = |offset: usize| {
+            let mut buffer = [0; 8];
+            buffer.copy_from_slice(&bytes[offset..offset + 8]);
+            f64::from_le_bytes(buffer)
+        };
+        *sum = colour(value(0), value(8), value(16));
+    }
+    Ok(result)
+}
+
+impl Camera {
+    // one sample per pixel at a random position within the pixel, `pass` picks the random numbers so every pass
+    // samples different positions
+    pub fn render_pass<F>(&self, seed: u64, pass: u32, threads: u32, trace: F) -> Canvas
+        where F: Fn(&Ray) -> Colour + Sync {
+        render_parallel(self.hsize, self.vsize, threads, DEFAULT_TILE_SIZE, |x, y| {
+            let mut rng = pixel_random(seed, x, y, pass);
+            let sample_x = x as f64 + rng.next_f64();
+            let sample_y = y as f64 + rng.next_f64();
+            let lens_u = rng.next_f64();
+            let lens_v = rng.next_f64();
+            let mut ray = self.ray_through_lens(sample_x, sample_y, lens_u, lens_v);
+            ray.time = self.shutter_time(rng.next_f64());
+            trace(&ray)
+        })
+    }
+
+    // adds passes to `accumulation` until the limits of `settings` are reached, `after_pass` is called after every
+    // pass and can be used to write snapshots, returns the number of passes rendered
+    pub fn render_progressive<F, A>(&self, settings: &ProgressiveRender, accumulation: &mut Accumulation, threads: u32,
+                                    trace: F, mut after_pass: A) -> Result<u32, Error>
+        where F: Fn(&Ray) -> Colour + Sync, A: FnMut(&Accumulation) {
+        if accumulation.width != self.hsize || accumulation.height != self.vsize {
+            return Err(Error::SizeMismatch(format!("Accumulation of size {}x{} does not match the camera of size {}x{}.",
+                                                   accumulation.width, accumulation.height, self.hsize, self.vsize)));
+        }
+        let start = Instant::now();
+        let mut passes = 0;
+        while !settings.finished(accumulation, passes, start) {
+            let pass = self.render_pass(settings.seed, accumulation.samples, threads, &trace);
+            accumulation.add_pass(&pass)?;
+            passes += 1;
+            after_pass(accumulation);
+        }
+        Ok(passes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::camera::camera;
+    use crate::canvas::canvas;
+    use crate::colour::{colour, Colour};
+    use crate::error::Error;
+    use crate::progressive::{accumulation, accumulation_from_bytes, progressive_render};
+    use crate::ray::Ray;
+
+    fn direction_shade(ray: &Ray) -> Colour {
+        colour(ray.direction.x.abs(), ray.direction.y.abs(), 1.0)
+    }
+
+    #[test]
+    fn snapshot_is_the_running_average() {
+        let mut acc = accumulation(2, 1);
+        assert_eq!(acc.snapshot().pixel_at(0, 0).red, 0.0);
+        let mut pass = canvas(2, 1);
+        pass.write_pixel(0, 0, colour(1.0, 2.0, 3.0)).unwrap();
+        acc.add_pass(&pass).unwrap();
+        pass.write_pixel(0, 0, colour(3.0, 2.0, 1.0)).unwrap();
+        acc.add_pass(&pass).unwrap();
+        assert_eq!(acc.samples, 2);
+        let pixel = acc.snapshot().pixel_at(0, 0);
+        assert_eq!((pixel.red, pixel.green, pixel.blue), (2.0, 2.0, 2.0));
+    }
+
+    #[test]
+    fn passes_of_the_wrong_size_are_rejected() {
+        let mut acc = accumulation(2, 2);
+        assert!(matches!(acc.add_pass(&canvas(2, 1)), Err(Error::SizeMismatch(_))));
+        assert_eq!(acc.samples, 0);
+    }
+
+    #[test]
+    fn stops_at_the_target_sample_count() {
+        let c = camera(6, 4, PI / 2.0);
+        let mut acc = accumulation(6, 4);
+        let mut snapshots = 0;
+        let passes = c.render_progressive(&progressive_render(Some(5), None), &mut acc, 2, direction_shade, |_| snapshots += 1);
+        assert_eq!(passes, Ok(5));
+        assert_eq!(acc.samples, 5);
+        assert_eq!(snapshots, 5);
+    }
+
+    #[test]
+    fn without_limits_a_single_pass_is_rendered() {
+        let c = camera(6, 4, PI / 2.0);
+        let mut acc = accumulation(6, 4);
+        assert_eq!(c.render_progressive(&progressive_render(None, None), &mut acc, 1, direction_shade, |_| {}), Ok(1));
+    }
+
+    #[test]
+    fn accumulation_must_match_the_camera() {
+        let c = camera(6, 4, PI / 2.0);
+        let mut acc = accumulation(4, 6);
+        assert!(c.render_progressive(&progressive_render(Some(1), None), &mut acc, 1, direction_shade, |_| {}).is_err());
+    }
+
+    #[test]
+    fn resuming_gives_the_same_image_as_rendering_at_once() {
+        let c = camera(7, 5, PI / 2.0);
+        let mut at_once = accumulation(7, 5);
+        c.render_progressive(&progressive_render(Some(6), None), &mut at_once, 3, direction_shade, |_| {}).unwrap();
+
+        let mut first = accumulation(7, 5);
+        c.render_progressive(&progressive_render(Some(2), None), &mut first, 1, direction_shade, |_| {}).unwrap();
+        let mut resumed = accumulation_from_bytes(&first.to_bytes()).unwrap();
+        assert_eq!(resumed.samples, 2);
+        c.render_progressive(&progressive_render(Some(6), None), &mut resumed, 2, direction_shade, |_| {}).unwrap();
+
+        assert_eq!(resumed.samples, 6);
+        for (a, b) in at_once.sums.iter().zip(&resumed.sums) {
+            assert_eq!((a.red, a.green, a.blue), (b.red, b.green, b.blue));
+        }
+    }
+
+    #[test]
+    fn invalid_accumulation_files_are_rejected() {
+        assert!(matches!(accumulation_from_bytes(b"P3\n1 1 1\n"), Err(Error::Parse(_))));
+        assert!(accumulation_from_bytes(b"ACCUM\n1 1\n").is_err());
+        assert!(accumulation_from_bytes(b"ACCUM\n1 1 1\nshort").is_err());
+        // the size is checked before the sums are allocated
+        assert!(accumulation_from_bytes(b"ACCUM\n4294967295 4294967295 1\nshort").is_err());
+    }
+}
@@ -0,0 +1,250 @@
+// the largest number of dimensions `sobol` supports
+pub const SOBOL_DIMENSIONS: u32 = 8;
+
+// degree, coefficients and initial direction numbers of the primitive polynomials for sobol dimensions 2 to 8,
+// taken from the Joe-Kuo tables, the first dimension is the van der Corput sequence
+const SOBOL_POLYNOMIALS: [(u32, u32, [u32; 5]); 7] = [
+    (1, 0, [1, 0, 0, 0, 0]),
+    (2, 1, [1, 3, 0, 0, 0]),
+    (3, 1, [1, 3, 1, 0, 0]),
+    (3, 2, [1, 1, 1, 0, 0]),
+    (4, 1, [1, 1, 3, 3, 0]),
+    (4, 4, [1, 3, 5, 13, 0]),
+    (5, 2, [1, 1, 5, 5, 17]),
+];
+
+// the number of prime bases `halton` supports
+pub const HALTON_DIMENSIONS: u32 = 16;
+
+const PRIMES: [u32; HALTON_DIMENSIONS as usize] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53];
+
+const PCG_MULTIPLIER: u64 = 6364136223846793005;
+
+// PCG32 (XSH RR), small and fast with independent streams, so every pixel can get its own
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Random {
+    state: u64,
+    increment: u64,
+}
+
+pub fn random(seed: u64, stream: u64) -> Random {
+    let mut rng = Random {
+        state: 0,
+        // the increment has to be odd
+        increment: (stream << 1) | 1,
+    };
+    rng.next_u32();
+    rng.state = rng.state.wrapping_add(seed);
+    rng.next_u32();
+    rng
+}
+
+// the generator for one sample of one pixel, it only depends on the seed and the arguments, never on which
+// thread renders the pixel or in which order
+pub fn pixel_random(seed: u64, x: u32, y: u32, sample: u32) -> Random {
+    random(mix(seed ^ mix((x as u64) << 32 | y as u64)), sample as u64)
+}
+
+// a generator seeded from arbitrary values, for example the bits of a point
+pub fn random_from(values: &[u64]) -> Random {
+    let seed = values.iter().fold(0, |hash, value| mix(hash ^ *value));
+    random(seed, mix(seed))
+}
+
+impl Random {
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(PCG_MULTIPLIER).wrapping_add(self.increment);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rotation = (old >> 59) as u32;
+        xorshifted.rotate_right(rotation)
+    }
+
+    // uniform in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        let high = (self.next_u32() >> 5) as u64;
+        let low = (self.next_u32() >> 6) as u64;
+        (high * 67108864 + low) as f64 / (1u64 << 53) as f64
+    }
+}
+
+// radical inverse of `index` in the `dimension`-th prime base, None past `HALTON_DIMENSIONS` since reusing a base
+// would correlate two dimensions
+pub fn halton(index: u32, dimension: u32) -> Option<f64> {
+    PRIMES.get(dimension as usize).map(|base| radical_inverse(index, *base as u64))
+}
+
+// the first two halton dimensions shifted by `offset_u` and `offset_v` and wrapped back into [0, 1), so neighbouring
+// pixels do not share patterns
+pub fn halton_shifted(index: u32, offset_u: f64, offset_v: f64) -> (f64, f64) {
+    ((radical_inverse(index, 2) + offset_u).fract(), (radical_inverse(index, 3) + offset_v).fract())
+}
+
+fn radical_inverse(index: u32, base: u64) -> f64 {
+    let inverse_base = 1.0 / base as f64;
+    let mut index = index as u64;
+    let mut factor = inverse_base;
+    let mut result = 0.0;
+    while index > 0 {
+        result += (index % base) as f64 * factor;
+        index /= base;
+        factor *= inverse_base;
+    }
+    result
+}
+
+// point `index` of the sobol sequence in the given dimension, None past `SOBOL_DIMENSIONS` since reusing the
+// directions of a dimension would correlate two dimensions
+pub fn sobol(index: u32, dimension: u32) -> Option<f64> {
+    sobol_scrambled(index, dimension, 0)
+}
+
+// xor scrambling with a random `scramble` keeps the stratification of the sequence
+pub fn sobol_scrambled(index: u32, dimension: u32, scramble: u32) -> Option<f64> {
+    (dimension < SOBOL_DIMENSIONS).then(|| scrambled_point(index, sobol_directions(dimension), scramble))
+}
+
+// the first two sobol dimensions, each with its own scramble
+pub fn sobol_scrambled_pair(index: u32, scramble_u: u32, scramble_v: u32) -> (f64, f64) {
+    (scrambled_point(index, sobol_directions(0), scramble_u), scrambled_point(index, sobol_directions(1), scramble_v))
+}
+
+fn scrambled_point(index: u32, directions: [u32; 32], scramble: u32) -> f64 {
+    let mut result = scramble;
+    let mut index = index;
+    let mut bit = 0;
+    while index > 0 {
+        if index & 1 == 1 {
+            result ^= directions[bit];
+        }
+        index >>= 1;
+        bit += 1;
+    }
+    result as f64 / 4294967296.0
+}
+
+fn sobol_directions(dimension: u32) -> [u32; 32] {
+    let mut directions = [0u32; 32];
+    if dimension == 0 {
+        for (bit, direction) in directions.iter_mut().enumerate() {
+            *direction = 1 << (31 - bit);
+        }
+        return directions;
+    }
+
+    let (degree, coefficients, initial) = SOBOL_POLYNOMIALS[(dimension - 1) as usize];
+    let degree = degree as usize;
+    for bit in 0..32 {
+        directions[bit] = if bit < degree {
+            initial[bit] << (31 - bit)
+        } else {
+            let mut value = directions[bit - degree] ^ (directions[bit - degree] >> degree);
+            for k in 1..degree {
+                if (coefficients >> (degree - 1 - k)) & 1 == 1 {
+                    value ^= directions[bit - k];
+                }
+            }
+            value
+        };
+    }
+    directions
+}
+
+// splitmix64 finalizer
+pub fn mix(value: u64) -> u64 {
+    let mut hash = value.wrapping_add(0x9e3779b97f4a7c15);
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d049bb133111eb);
+    hash ^ (hash >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::random::{halton, HALTON_DIMENSIONS, halton_shifted, mix, pixel_random, random, random_from, sobol, sobol_scrambled,
+                        sobol_scrambled_pair, SOBOL_DIMENSIONS};
+
+    #[test]
+    fn same_seed_gives_the_same_numbers() {
+        let mut a = random(42, 7);
+        let mut b = random(42, 7);
+        for _ in 0..100 {
+            assert_eq!(a.next_u32(), b.next_u32());
+        }
+    }
+
+    #[test]
+    fn seeds_and_streams_are_independent() {
+        let first: Vec<u32> = (0..8).scan(random(42, 7), |rng, _| Some(rng.next_u32())).collect();
+        let other_seed: Vec<u32> = (0..8).scan(random(43, 7), |rng, _| Some(rng.next_u32())).collect();
+        let other_stream: Vec<u32> = (0..8).scan(random(42, 8), |rng, _| Some(rng.next_u32())).collect();
+        assert_ne!(first, other_seed);
+        assert_ne!(first, other_stream);
+    }
+
+    #[test]
+    fn floats_are_in_the_unit_interval_and_roughly_uniform() {
+        let mut rng = random(1, 1);
+        let mut buckets = [0; 10];
+        for _ in 0..10000 {
+            let value = rng.next_f64();
+            assert!((0.0..1.0).contains(&value));
+            buckets[(value * 10.0) as usize] += 1;
+        }
+        for count in buckets {
+            assert!(count > 900 && count < 1100);
+        }
+    }
+
+    #[test]
+    fn pixel_generators_only_depend_on_their_arguments() {
+        assert_eq!(pixel_random(5, 1, 2, 3), pixel_random(5, 1, 2, 3));
+        assert_ne!(pixel_random(5, 1, 2, 3), pixel_random(5, 2, 1, 3));
+        assert_ne!(pixel_random(5, 1, 2, 3), pixel_random(5, 1, 2, 4));
+        assert_ne!(pixel_random(5, 1, 2, 3), pixel_random(6, 1, 2, 3));
+        assert_eq!(random_from(&[1, 2]), random_from(&[1, 2]));
+        assert_ne!(random_from(&[1, 2]), random_from(&[2, 1]));
+        assert_ne!(mix(0), mix(1));
+    }
+
+    #[test]
+    fn halton_sequence() {
+        let base_two: Vec<f64> = (0..5).map(|i| halton(i, 0).unwrap()).collect();
+        assert_eq!(base_two, vec![0.0, 0.5, 0.25, 0.75, 0.125]);
+        let base_three: Vec<f64> = (1..4).map(|i| halton(i, 1).unwrap()).collect();
+        assert!((base_three[0] - 1.0 / 3.0).abs() < 1e-12);
+        assert!((base_three[1] - 2.0 / 3.0).abs() < 1e-12);
+        assert!((base_three[2] - 1.0 / 9.0).abs() < 1e-12);
+        // the last prime base is 53, there is none after it
+        assert!((halton(1, HALTON_DIMENSIONS - 1).unwrap() - 1.0 / 53.0).abs() < 1e-12);
+        assert_eq!(halton(1, HALTON_DIMENSIONS), None);
+        let (u, v) = halton_shifted(1, 0.75, 0.5);
+        assert!((u - 0.25).abs() < 1e-12 && (v - 5.0 / 6.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn sobol_sequence() {
+        let first: Vec<f64> = (0..8).map(|i| sobol(i, 0).unwrap()).collect();
+        assert_eq!(first, vec![0.0, 0.5, 0.25, 0.75, 0.125, 0.625, 0.375, 0.875]);
+        let second: Vec<f64> = (0..8).map(|i| sobol(i, 1).unwrap()).collect();
+        assert_eq!(second, vec![0.0, 0.5, 0.75, 0.25, 0.625, 0.125, 0.375, 0.875]);
+        // there are only direction numbers for the first dimensions
+        assert!(sobol(1, SOBOL_DIMENSIONS - 1).is_some());
+        assert_eq!(sobol(1, SOBOL_DIMENSIONS), None);
+        assert_eq!(sobol_scrambled_pair(5, 7, 9), (sobol_scrambled(5, 0, 7).unwrap(), sobol_scrambled(5, 1, 9).unwrap()));
+    }
+
+    #[test]
+    fn every_sobol_dimension_is_stratified() {
+        for dimension in 0..SOBOL_DIMENSIONS {
+            for scramble in [0, 0x9e3779b9] {
+                let mut strata = [0; 16];
+                for i in 0..16 {
+                    let value = sobol_scrambled(i, dimension, scramble).unwrap();
+                    assert!((0.0..1.0).contains(&value));
+                    strata[(value * 16.0) as usize] += 1;
+                }
+                assert_eq!(strata, [1; 16]);
+            }
+        }
+    }
+}
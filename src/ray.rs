@@ -1,24 +1,32 @@
 use crate::matrix::Matrix;
 use crate::objects::Object;
-use crate::tuple::{dot_product, ORIGIN, Tuple};
+use crate::tuple::{cross_product, dot_product, Tuple};
+use crate::util::EPSILON;
 
 pub struct Ray {
     pub origin: Tuple,
     pub direction: Tuple,
+    // point in the shutter interval the ray was sent at, moving objects are placed accordingly
+    pub time: f64,
 }
 
 impl Ray {
     pub fn transform(&self, translation: Matrix<4>) -> Ray {
         let origin = translation * self.origin;
         let direction = translation * self.direction;
-        ray(origin, direction)
+        timed_ray(origin, direction, self.time)
     }
 }
 
 pub fn ray(origin: Tuple, direction: Tuple) -> Ray {
+    timed_ray(origin, direction, 0.0)
+}
+
+pub fn timed_ray(origin: Tuple, direction: Tuple, time: f64) -> Ray {
     Ray {
         origin,
         direction,
+        time,
     }
 }
 
@@ -27,38 +35,61 @@ impl Ray {
         self.origin + (self.direction * time)
     }
 
-    pub fn intersect(&self, object: &Object) -> Intersections {
-        let ray = match object {
-            Object::Sphere { transformation, .. } => self.transform(transformation.invert().unwrap()),
+    pub fn intersect<'a>(&self, object: &'a Object) -> Intersections<'a> {
+        let ray = match object.inverse_transformation_at(self.time) {
+            Some(inverse) => self.transform(inverse),
+            None => return Intersections::None,
         };
 
-        let object_position = match object {
-            Object::Sphere { position, .. } => *position,
-        };
-
-        let sphere_to_ray = ray.origin - object_position;
-        let a = dot_product(ray.direction, ray.direction);
-        let b = 2.0 * dot_product(ray.direction, sphere_to_ray);
-        let c = dot_product(sphere_to_ray, sphere_to_ray) - 1.0;
-        let discriminant = b.powi(2) - 4.0 * a * c;
+        match object {
+            Object::Sphere { position, .. } => {
+                let sphere_to_ray = ray.origin - *position;
+                let a = dot_product(ray.direction, ray.direction);
+                let b = 2.0 * dot_product(ray.direction, sphere_to_ray);
+                let c = dot_product(sphere_to_ray, sphere_to_ray) - 1.0;
+                let discriminant = b.powi(2) - 4.0 * a * c;
 
-        if discriminant < 0.0 {
-            return Intersections::None;
+                if discriminant < 0.0 {
+                    return Intersections::None;
+                }
+                let intersect1 = (-b - f64::sqrt(discriminant)) / (2.0 * a);
+                let intersect2 = (-b + f64::sqrt(discriminant)) / (2.0 * a);
+                Intersections::Some([intersection(intersect1, object), intersection(intersect2, object)])
+            }
+            Object::Triangle { p1, e1, e2, .. } => {
+                // moeller-trumbore
+                let dir_cross_e2 = cross_product(ray.direction, *e2);
+                let determinant = dot_product(*e1, dir_cross_e2);
+                if determinant.abs() < EPSILON * EPSILON {
+                    return Intersections::None;
+                }
+                let f = 1.0 / determinant;
+                let p1_to_origin = ray.origin - *p1;
+                let u = f * dot_product(p1_to_origin, dir_cross_e2);
+                if !(0.0..=1.0).contains(&u) {
+                    return Intersections::None;
+                }
+                let origin_cross_e1 = cross_product(p1_to_origin, *e1);
+                let v = f * dot_product(ray.direction, origin_cross_e1);
+                if v < 0.0 || u + v > 1.0 {
+                    return Intersections::None;
+                }
+                Intersections::One(intersection(f * dot_product(*e2, origin_cross_e1), object))
+            }
         }
-        let intersect1 = (-b - f64::sqrt(discriminant)) / (2.0 * a);
-        let intersect2 = (-b + f64::sqrt(discriminant)) / (2.0 * a);
-        Intersections::Some([intersection(intersect1, *object), intersection(intersect2, *object)])
     }
 }
 
 #[derive(Debug, PartialEq)]
-pub enum Intersections {
+pub enum Intersections<'a> {
     None,
-    Some([Intersection; 2]),
+    // flat objects like triangles are only crossed once
+    One(Intersection<'a>),
+    Some([Intersection<'a>; 2]),
 }
 
-impl Intersections {
-    pub fn hit(&self) -> Option<Intersection> {
+impl<'a> Intersections<'a> {
+    pub fn hit(&self) -> Option<Intersection<'a>> {
         match self {
             Intersections::Some(intersects) => {
                 let mut smallest_i = usize::MAX;
@@ -75,20 +106,281 @@ impl Intersections {
                     Some(intersects[smallest_i])
                 }
             }
+            Intersections::One(intersection) if intersection.time > 0.0 => Some(*intersection),
             _ => None,
         }
     }
+
+    pub fn as_slice(&self) -> &[Intersection<'a>] {
+        match self {
+            Intersections::None => &[],
+            Intersections::One(intersection) => std::slice::from_ref(intersection),
+            Intersections::Some(intersections) => intersections,
+        }
+    }
 }
 
+// refers to the object rather than copying it, objects are large
 #[derive(Debug, PartialEq, Copy, Clone)]
-pub struct Intersection {
+pub struct Intersection<'a> {
     pub time: f64,
-    pub object: Object,
+    pub object: &'a Object,
 }
 
-pub fn intersection(t: f64, object: Object) -> Intersection {
+pub fn intersection(t: f64, object: &Object) -> Intersection<'_> {
     Intersection {
         time: t,
         object,
     }
 }
+
+// the visible intersection is the one closest to the ray origin that is in front of it
+pub fn hit<'a>(intersections: &[Intersection<'a>]) -> Option<Intersection<'a>> {
+    intersections.iter()
+        .filter(|intersection| intersection.time > 0.0)
+        .min_by(|a, b| a.time.total_cmp(&b.time))
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::matrix::{IDENTITY_MATRIX, scaling_i, translation_i};
+    use crate::objects::{Object, sphere};
+    use crate::objects::Object::Sphere;
+    use crate::ray::{intersection, Intersections, ray};
+    use crate::tuple::{point, point_i, vector_i};
+
+    #[test]
+    fn intersecting_a_translated_sphere_with_a_ray() {
+        let r = ray(point_i(0, 0, -5), vector_i(0, 0, 1));
+        let s = sphere().set_transform(translation_i(5, 0, 0)).unwrap();
+        let intersects = r.intersect(&s);
+
+        assert_eq!(intersects, Intersections::None);
+    }
+
+    #[test]
+    fn intersecting_a_scaled_sphere_with_a_ray() {
+        let r = ray(point_i(0, 0, -5), vector_i(0, 0, 1));
+        let s = sphere().set_transform(scaling_i(2, 2, 2)).unwrap();
+        let intersects = r.intersect(&s);
+
+        match intersects {
+            Intersections::Some(intersections) => {
+                assert_eq!(intersections.len(), 2);
+                assert_eq!(intersections[0].time, 3.0);
+                assert_eq!(intersections[1].time, 7.0);
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn changing_a_spheres_transformation() {
+        let s = sphere();
+        let t = translation_i(2, 3, 4);
+        let s = s.set_transform(t).unwrap();
+
+        match s {
+            Sphere { position: _, radius: _, transformation, .. } => {
+                assert_eq!(transformation, t);
+            }
+            _ => panic!("expected a sphere"),
+        }
+    }
+
+    #[test]
+    fn sphere_default_transformation() {
+        let s = sphere();
+        match s {
+            Sphere { position: _, radius: _, transformation, .. } => {
+                assert_eq!(transformation, IDENTITY_MATRIX);
+            }
+            _ => panic!("expected a sphere"),
+        }
+    }
+
+    #[test]
+    fn scaling_a_ray() {
+        let r = ray(point_i(1, 2, 3), vector_i(0, 1, 0));
+        let translation = scaling_i(2, 3, 4);
+        let r2 = r.transform(translation);
+
+        assert_eq!(r2.origin, point_i(2, 6, 12));
+        assert_eq!(r2.direction, vector_i(0, 3, 0));
+    }
+
+    #[test]
+    fn translating_a_ray() {
+        let r = ray(point_i(1, 2, 3), vector_i(0, 1, 0));
+        let translation = translation_i(3, 4, 5);
+        let r2 = r.transform(translation);
+
+        assert_eq!(r2.origin, point_i(4, 6, 8));
+        assert_eq!(r2.direction, vector_i(0, 1, 0));
+    }
+
+    #[test]
+    fn hit_when_all_intersects_have_negative_t() {
+        let s = sphere();
+        let i1 = intersection(-2.0, &s);
+        let i2 = intersection(-1.0, &s);
+        let intersects = Intersections::Some([i2, i1]);
+
+        let hit = intersects.hit();
+        assert_eq!(hit, None);
+    }
+
+    #[test]
+    fn hit_when_some_intersects_have_negative_t() {
+        let s = sphere();
+        let i1 = intersection(1.0, &s);
+        let i2 = intersection(-1.0, &s);
+        let intersects = Intersections::Some([i2, i1]);
+
+        let hit = intersects.hit().unwrap();
+        assert_eq!(hit, i1);
+    }
+
+    #[test]
+    fn hit_when_all_intersects_have_positive_t() {
+        let s = sphere();
+        let i1 = intersection(1.0, &s);
+        let i2 = intersection(2.0, &s);
+        let intersects = Intersections::Some([i2, i1]);
+
+        let hit = intersects.hit().unwrap();
+        assert_eq!(hit, i1);
+    }
+
+    #[test]
+    fn intersect_sets_object_on_intersections() {
+        let r = ray(point_i(0, 0, -5), vector_i(0, 0, 1));
+        let s = sphere();
+        let intersects = r.intersect(&s);
+
+        match intersects {
+            Intersections::Some(intersections) => {
+                assert_eq!(intersections.len(), 2);
+                assert_eq!(*intersections[0].object, s);
+                assert_eq!(*intersections[1].object, s);
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn aggregating_intersections() {
+        let s = sphere();
+        let i1 = intersection(1.0, &s);
+        let i2 = intersection(2.0, &s);
+        let intersections = Intersections::Some([i1, i2]);
+
+        match intersections {
+            Intersections::Some(intersects) => {
+                assert_eq!(intersects.len(), 2);
+                assert_eq!(intersects[0].time, 1.0);
+                assert_eq!(intersects[1].time, 2.0);
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn intersection_encapsulates_t_and_object() {
+        let s = sphere();
+        let i = intersection(3.5, &s);
+
+        assert_eq!(i.time, 3.5);
+        assert_eq!(*i.object, s);
+    }
+
+    #[test]
+    fn sphere_is_behind_ray() {
+        let r = ray(point_i(0, 0, 5), vector_i(0, 0, 1));
+        let s = sphere();
+        let intersects = r.intersect(&s);
+
+        match intersects {
+            Intersections::Some(intersections) => {
+                assert_eq!(intersections[0].time, -6.0);
+                assert_eq!(intersections[1].time, -4.0);
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn ray_originates_inside_sphere() {
+        let r = ray(point_i(0, 0, 0), vector_i(0, 0, 1));
+        let s = sphere();
+        let intersects = r.intersect(&s);
+
+        match intersects {
+            Intersections::Some(intersections) => {
+                assert_eq!(intersections[0].time, -1.0);
+                assert_eq!(intersections[1].time, 1.0);
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn ray_misses_sphere() {
+        let r = ray(point_i(0, 2, -5), vector_i(0, 0, 1));
+        let s = sphere();
+        let intersects = r.intersect(&s);
+
+        assert_eq!(intersects, Intersections::None);
+    }
+
+    #[test]
+    fn ray_intersects_sphere_at_a_tangent() {
+        let r = ray(point_i(0, 1, -5), vector_i(0, 0, 1));
+        let s = sphere();
+        let intersects = r.intersect(&s);
+
+        match intersects {
+            Intersections::Some(intersections) => {
+                assert_eq!(intersections[0].time, 5.0);
+                assert_eq!(intersections[1].time, 5.0);
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn ray_intersects_sphere_at_two_points() {
+        let r = ray(point_i(0, 0, -5), vector_i(0, 0, 1));
+        let s = sphere();
+        let intersects = r.intersect(&s);
+
+        match intersects {
+            Intersections::Some(intersections) => {
+                assert_eq!(intersections[0].time, 4.0);
+                assert_eq!(intersections[1].time, 6.0);
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn computing_a_point_from_a_distance() {
+        let r = ray(point_i(2, 3, 4), vector_i(1, 0, 0));
+
+        assert_eq!(r.position(0.0), point_i(2, 3, 4));
+        assert_eq!(r.position(1.0), point_i(3, 3, 4));
+        assert_eq!(r.position(-1.0), point_i(1, 3, 4));
+        assert_eq!(r.position(2.5), point(4.5, 3.0, 4.0));
+    }
+
+    #[test]
+    fn creating_a_ray() {
+        let origin = point_i(1, 2, 3);
+        let direction = vector_i(4, 5, 6);
+        let r = ray(origin, direction);
+
+        assert_eq!(r.origin, origin);
+        assert_eq!(r.direction, direction);
+    }
+}
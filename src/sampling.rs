@@ -0,0 +1,332 @@
+use std::f64::consts::PI;
+
+use crate::colour::{BLACK, Colour};
+use crate::random::{halton_shifted, pixel_random, Random, sobol_scrambled_pair};
+use crate::tuple::{cross_product, Tuple, vector};
+
+pub const NO_SUPERSAMPLING: Supersampling = Supersampling {
+    pattern: SamplePattern::Grid,
+    samples: 1,
+    filter: Filter::Box,
+    seed: 0,
+};
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum SamplePattern {
+    Grid,
+    Jittered,
+    RotatedGrid,
+    // low discrepancy sequences, randomised per pixel
+    Halton,
+    Sobol,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Filter {
+    Box,
+    Tent,
+    Mitchell,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Supersampling {
+    pub pattern: SamplePattern,
+    // rounded up to the next square number, so the samples can be arranged in a grid
+    pub samples: u32,
+    pub filter: Filter,
+    // every random number of a render is derived from this, the same seed always gives the same image
+    pub seed: u64,
+}
+
+pub fn supersampling(pattern: SamplePattern, samples: u32, filter: Filter) -> Supersampling {
+    Supersampling {
+        pattern,
+        samples,
+        filter,
+        seed: 0,
+    }
+}
+
+impl Supersampling {
+    pub fn set_seed(self, seed: u64) -> Supersampling {
+        Supersampling {
+            seed,
+            ..self
+        }
+    }
+
+    pub fn grid_size(&self) -> u32 {
+        (self.samples.max(1) as f64).sqrt().ceil() as u32
+    }
+
+    // the generator for everything else a sample needs (lens position, time, ...), it continues after the
+    // numbers used for the position of the sample in the pixel
+    pub fn sample_random(&self, x: u32, y: u32, sample: u32) -> Random {
+        let mut rng = pixel_random(self.seed, x, y, sample);
+        rng.next_f64();
+        rng.next_f64();
+        rng
+    }
+
+    // sample positions relative to the pixel centre, spread over the support of the filter
+    pub fn sample_offsets(&self, x: u32, y: u32) -> Vec<(f64, f64)> {
+        let grid_size = self.grid_size();
+        let extent = 2.0 * self.filter.radius();
+        let cell = 1.0 / grid_size as f64;
+        // rotating an n by n grid by atan(1/n) puts every sample in its own row and column of the n^2 by n^2 grid
+        let (sin, cos) = (1.0 / grid_size as f64).atan().sin_cos();
+        // sample u32::MAX is never used for the samples themselves, so it is free for per pixel randomisation
+        let mut rng = pixel_random(self.seed, x, y, u32::MAX);
+        let shift_u = rng.next_f64();
+        let shift_v = rng.next_f64();
+        let scramble_u = rng.next_u32();
+        let scramble_v = rng.next_u32();
+
+        let mut offsets = Vec::with_capacity((grid_size * grid_size) as usize);
+        for row in 0..grid_size {
+            for column in 0..grid_size {
+                let (u, v) = match self.pattern {
+                    SamplePattern::Grid => ((column as f64 + 0.5) * cell, (row as f64 + 0.5) * cell),
+                    SamplePattern::Jittered => {
+                        let mut rng = pixel_random(self.seed, x, y, row * grid_size + column);
+                        ((column as f64 + rng.next_f64()) * cell, (row as f64 + rng.next_f64()) * cell)
+                    }
+                    SamplePattern::RotatedGrid => {
+                        let u = (column as f64 + 0.5) * cell - 0.5;
+                        let v = (row as f64 + 0.5) * cell - 0.5;
+                        // rotate around the centre and wrap the points back into the unit square
+                        ((u * cos - v * sin + 0.5).rem_euclid(1.0), (u * sin + v * cos + 0.5).rem_euclid(1.0))
+                    }
+                    SamplePattern::Halton => {
+                        let index = row * grid_size + column;
+                        halton_shifted(index, shift_u, shift_v)
+                    }
+                    SamplePattern::Sobol => {
+                        let index = row * grid_size + column;
+                        sobol_scrambled_pair(index, scramble_u, scramble_v)
+                    }
+                };
+                offsets.push(((u - 0.5) * extent, (v - 0.5) * extent));
+            }
+        }
+        offsets
+    }
+
+    // `trace` receives continuous image coordinates and returns the colour seen through that point
+    pub fn render_pixel<F>(&self, x: u32, y: u32, trace: F) -> Colour
+        where F: Fn(f64, f64) -> Colour {
+        self.render_pixel_indexed(x, y, |sample_x, sample_y, _| trace(sample_x, sample_y))
+    }
+
+    // like `render_pixel`, but also passes the index of the sample within the pixel to `trace`
+    pub fn render_pixel_indexed<F>(&self, x: u32, y: u32, trace: F) -> Colour
+        where F: Fn(f64, f64, u32) -> Colour {
+        let centre_x = x as f64 + 0.5;
+        let centre_y = y as f64 + 0.5;
+
+        let mut total = BLACK;
+        let mut total_weight = 0.0;
+        for (index, (offset_x, offset_y)) in self.sample_offsets(x, y).into_iter().enumerate() {
+            let weight = self.filter.weight(offset_x, offset_y);
+            if weight == 0.0 {
+                continue;
+            }
+            total = total + trace(centre_x + offset_x, centre_y + offset_y, index as u32) * weight;
+            total_weight += weight;
+        }
+        if total_weight == 0.0 {
+            return trace(centre_x, centre_y, 0);
+        }
+        total * (1.0 / total_weight)
+    }
+}
+
+impl Filter {
+    pub fn radius(&self) -> f64 {
+        match self {
+            Filter::Box => 0.5,
+            Filter::Tent => 1.0,
+            Filter::Mitchell => 2.0,
+        }
+    }
+
+    pub fn weight(&self, x: f64, y: f64) -> f64 {
+        self.weight_1d(x) * self.weight_1d(y)
+    }
+
+    fn weight_1d(&self, x: f64) -> f64 {
+        let x = x.abs();
+        match self {
+            Filter::Box => if x <= 0.5 { 1.0 } else { 0.0 },
+            Filter::Tent => (1.0 - x).max(0.0),
+            Filter::Mitchell => {
+                // Mitchell-Netravali with B = C = 1/3
+                let b = 1.0 / 3.0;
+                let c = 1.0 / 3.0;
+                if x < 1.0 {
+                    ((12.0 - 9.0 * b - 6.0 * c) * x.powi(3) + (-18.0 + 12.0 * b + 6.0 * c) * x.powi(2) + (6.0 - 2.0 * b)) / 6.0
+                } else if x < 2.0 {
+                    ((-b - 6.0 * c) * x.powi(3) + (6.0 * b + 30.0 * c) * x.powi(2) + (-12.0 * b - 48.0 * c) * x + (8.0 * b + 24.0 * c)) / 6.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+// a direction around `normal` with density proportional to the cosine of the angle to it
+pub fn cosine_hemisphere(normal: Tuple, u: f64, v: f64) -> Tuple {
+    let helper = if normal.x.abs() > 0.9 { vector(0.0, 1.0, 0.0) } else { vector(1.0, 0.0, 0.0) };
+    let tangent = cross_product(normal, helper).normalize();
+    let bitangent = cross_product(normal, tangent);
+
+    let radius = u.sqrt();
+    let angle = 2.0 * PI * v;
+    let height = (1.0 - u).max(0.0).sqrt();
+    (tangent * (radius * angle.cos()) + bitangent * (radius * angle.sin()) + normal * height).normalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::colour::{colour, WHITE};
+    use crate::sampling::{cosine_hemisphere, Filter, NO_SUPERSAMPLING, SamplePattern, supersampling};
+    use crate::tuple::{dot_product, vector};
+
+    #[test]
+    fn single_sample_goes_through_pixel_centre() {
+        let offsets = NO_SUPERSAMPLING.sample_offsets(3, 4);
+        assert_eq!(offsets, vec![(0.0, 0.0)]);
+    }
+
+    #[test]
+    fn sample_count_is_rounded_up_to_a_square() {
+        assert_eq!(supersampling(SamplePattern::Grid, 5, Filter::Box).sample_offsets(0, 0).len(), 9);
+        assert_eq!(supersampling(SamplePattern::Grid, 16, Filter::Box).sample_offsets(0, 0).len(), 16);
+    }
+
+    #[test]
+    fn regular_grid_samples_are_evenly_spaced() {
+        let offsets = supersampling(SamplePattern::Grid, 4, Filter::Box).sample_offsets(0, 0);
+        assert_eq!(offsets, vec![(-0.25, -0.25), (0.25, -0.25), (-0.25, 0.25), (0.25, 0.25)]);
+    }
+
+    #[test]
+    fn jittered_samples_stay_in_their_stratum() {
+        let sampling = supersampling(SamplePattern::Jittered, 16, Filter::Box);
+        let offsets = sampling.sample_offsets(7, 9);
+        for (i, (x, y)) in offsets.iter().enumerate() {
+            let column = (i % 4) as f64;
+            let row = (i / 4) as f64;
+            assert!(*x >= -0.5 + column * 0.25 && *x < -0.5 + (column + 1.0) * 0.25);
+            assert!(*y >= -0.5 + row * 0.25 && *y < -0.5 + (row + 1.0) * 0.25);
+        }
+        assert_eq!(offsets, sampling.sample_offsets(7, 9));
+        assert_ne!(offsets, sampling.sample_offsets(8, 9));
+    }
+
+    #[test]
+    fn rotated_grid_samples_have_distinct_rows_and_columns() {
+        for samples in [4, 9, 16, 25, 64] {
+            let offsets = supersampling(SamplePattern::RotatedGrid, samples, Filter::Box).sample_offsets(0, 0);
+            // one sample in each of the `samples` columns and rows the pixel is divided into
+            let mut columns = vec![0; samples as usize];
+            let mut rows = vec![0; samples as usize];
+            for (u, v) in offsets {
+                assert!(u.abs() <= 0.5 && v.abs() <= 0.5);
+                columns[((u + 0.5) * samples as f64) as usize] += 1;
+                rows[((v + 0.5) * samples as f64) as usize] += 1;
+            }
+            assert_eq!(columns, vec![1; samples as usize], "columns of {} samples", samples);
+            assert_eq!(rows, vec![1; samples as usize], "rows of {} samples", samples);
+        }
+    }
+
+    #[test]
+    fn samples_cover_the_filter_support() {
+        let offsets = supersampling(SamplePattern::Grid, 4, Filter::Mitchell).sample_offsets(0, 0);
+        assert_eq!(offsets[0], (-1.0, -1.0));
+        assert_eq!(offsets[3], (1.0, 1.0));
+    }
+
+    #[test]
+    fn filter_weights() {
+        assert_eq!(Filter::Box.weight(0.4, -0.4), 1.0);
+        assert_eq!(Filter::Box.weight(0.6, 0.0), 0.0);
+        assert_eq!(Filter::Tent.weight(0.5, 0.0), 0.5);
+        assert_eq!(Filter::Tent.weight(0.5, 0.5), 0.25);
+        assert!((Filter::Mitchell.weight(0.0, 0.0) - (16.0 / 18.0) * (16.0 / 18.0)).abs() < 1e-9);
+        assert!(Filter::Mitchell.weight(1.5, 0.0) < 0.0);
+        assert_eq!(Filter::Mitchell.weight(2.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn constant_image_is_unchanged_by_every_filter() {
+        for filter in [Filter::Box, Filter::Tent, Filter::Mitchell] {
+            let sampling = supersampling(SamplePattern::Jittered, 9, filter);
+            let c = sampling.render_pixel(2, 2, |_, _| colour(0.3, 0.6, 0.9));
+            assert!((c.red - 0.3).abs() < 1e-9);
+            assert!((c.blue - 0.9).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn supersampling_an_edge_gives_partial_coverage() {
+        // everything left of x = 5 is white, pixel 4 is cut in half by the edge
+        let sampling = supersampling(SamplePattern::Grid, 16, Filter::Box);
+        let c = sampling.render_pixel(4, 0, |x, _| if x < 4.5 { WHITE } else { colour(0.0, 0.0, 0.0) });
+        assert!((c.red - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn jitter_depends_on_the_seed() {
+        let sampling = supersampling(SamplePattern::Jittered, 4, Filter::Box);
+        assert_eq!(sampling.sample_offsets(3, 4), sampling.set_seed(0).sample_offsets(3, 4));
+        assert_ne!(sampling.sample_offsets(3, 4), sampling.set_seed(7).sample_offsets(3, 4));
+    }
+
+    #[test]
+    fn low_discrepancy_samples_stay_in_the_pixel() {
+        for pattern in [SamplePattern::Halton, SamplePattern::Sobol] {
+            let sampling = supersampling(pattern, 16, Filter::Box);
+            let offsets = sampling.sample_offsets(5, 9);
+            assert_eq!(offsets.len(), 16);
+            // four samples in each quarter of the pixel, across and down
+            let mut columns = [0; 4];
+            let mut rows = [0; 4];
+            for (x, y) in &offsets {
+                assert!(x.abs() <= 0.5 && y.abs() <= 0.5);
+                columns[((x + 0.5) * 4.0) as usize] += 1;
+                rows[((y + 0.5) * 4.0) as usize] += 1;
+            }
+            assert_eq!(columns, [4, 4, 4, 4]);
+            if pattern == SamplePattern::Sobol {
+                assert_eq!(rows, [4, 4, 4, 4]);
+            } else {
+                // 16 is not a power of 3, so the base 3 rows are only close to even
+                assert!(rows.iter().all(|count| (3..=5).contains(count)), "{:?}", rows);
+            }
+            assert_ne!(offsets, sampling.sample_offsets(6, 9));
+        }
+    }
+
+    #[test]
+    fn cosine_samples_lie_in_the_hemisphere() {
+        let normal = vector(1.0, 2.0, -0.5).normalize();
+        let mut total = 0.0;
+        let steps = 32;
+        for i in 0..steps {
+            for j in 0..steps {
+                let u = (i as f64 + 0.5) / steps as f64;
+                let v = (j as f64 + 0.5) / steps as f64;
+                let direction = cosine_hemisphere(normal, u, v);
+                assert!((direction.magnitude() - 1.0).abs() < 1e-9);
+                let cos = dot_product(direction, normal);
+                assert!(cos > 0.0);
+                total += cos;
+            }
+        }
+        // the mean cosine of a cosine weighted hemisphere is 2/3
+        assert!((total / (steps * steps) as f64 - 2.0 / 3.0).abs() < 1e-2);
+    }
+}
@@ -0,0 +1,817 @@
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::path::Path;
+
+use crate::camera::{Bokeh, camera, Camera, Projection, view_transform};
+use crate::colour::{colour, Colour};
+use crate::environment::{constant_environment, Environment, gradient_environment, load_environment, sky_environment};
+use crate::error::Error;
+use crate::json::{parse_json, to_json};
+use crate::lights::{area_light, attenuation, Attenuation, directional_light, Light, point_light, spherical_light, spot_light};
+use crate::materials::{material, Material};
+use crate::matrix::{IDENTITY_MATRIX, matrix, Matrix, rotation_x, rotation_y, rotation_z, scaling, shearing, translation};
+use crate::obj::parse_obj;
+use crate::objects::{Object, sphere, triangle};
+use crate::pbr::pbr;
+use crate::sky::sky;
+use crate::tuple::{point, Tuple, vector};
+use crate::util::{read_file, write_file};
+use crate::world::{world, World};
+use crate::yaml::{Node, parse_yaml, Value};
+
+// definitions can refer to other definitions, this stops one that refers to itself
+const MAX_DEFINITION_DEPTH: u32 = 32;
+
+pub struct Scene {
+    pub camera: Camera,
+    pub world: World,
+}
+
+// a list of `add` and `define` entries, see `scene_from_node`
+pub fn parse_scene(text: &str) -> Result<Scene, Error> {
+    scene_from_node(&parse_yaml(text)?, Path::new("."))
+}
+
+// the same entries as `parse_scene`, written as a json array of objects
+pub fn parse_scene_json(text: &str) -> Result<Scene, Error> {
+    scene_from_node(&parse_json(text)?, Path::new("."))
+}
+
+// .json files are read as json and everything else as yaml, files the scene refers to are looked up relative to it
+pub fn load_scene(filepath: &str) -> Result<Scene, Error> {
+    let data = read_file(filepath)?;
+    let text = String::from_utf8(data).map_err(|_| Error::Parse(format!("'{}' is not valid UTF-8.", filepath)))?;
+    let directory = Path::new(filepath).parent().unwrap_or(Path::new("."));
+    let root = if filepath.to_lowercase().ends_with(".json") { parse_json(&text) } else { parse_yaml(&text) }?;
+    scene_from_node(&root, directory)
+}
+
+// writes the scene as json, loading it back gives an equal camera, lights and objects
+pub fn save_scene(scene: &Scene, filepath: &str) -> Result<(), Error> {
+    write_file(filepath, scene_to_json(scene)?)
+}
+
+pub fn scene_to_json(scene: &Scene) -> Result<String, Error> {
+    scene_to_node(scene).map(|root| to_json(&root))
+}
+
+// every entry either adds a camera, light, environment or shape to the scene, or defines a named value that later
+// entries can use in place of a material or a transform. `extend` starts a definition from an earlier one, mappings
+// are merged and lists appended
+pub fn scene_from_node(root: &Node, directory: &Path) -> Result<Scene, Error> {
+    let mut builder = Builder {
+        definitions: HashMap::new(),
+        camera: None,
+        world: world(),
+        directory,
+    };
+    for entry in list(root)? {
+        let map = map(entry)?;
+        if let Some(definition) = find(map, "define") {
+            builder.define(entry, scalar(definition)?)?;
+        } else if let Some(kind) = find(map, "add") {
+            builder.add(entry, kind)?;
+        } else {
+            return Err(entry.error("Expected an 'add' or 'define' entry."));
+        }
+    }
+    let camera = builder.camera.ok_or_else(|| Error::Parse(String::from("The scene has no camera.")))?;
+    Ok(Scene { camera, world: builder.world })
+}
+
+struct Builder<'a> {
+    definitions: HashMap<String, Node>,
+    camera: Option<Camera>,
+    world: World,
+    directory: &'a Path,
+}
+
+impl Builder<'_> {
+    fn define(&mut self, entry: &Node, name: &str) -> Result<(), Error> {
+        let map = map(entry)?;
+        check_keys(entry, &["define", "extend", "value"])?;
+        let value = find(map, "value").ok_or_else(|| entry.error("A definition needs a 'value'."))?;
+        let value = match find(map, "extend") {
+            None => value.clone(),
+            Some(base_name) => {
+                let base = self.lookup(base_name)?;
+                match (&base.value, &value.value) {
+                    (Value::Map(base_entries), Value::Map(entries)) => {
+                        let mut merged = base_entries.clone();
+                        for (key, node) in entries {
+                            match merged.iter_mut().find(|(existing, _)| existing == key) {
+                                Some((_, existing)) => *existing = node.clone(),
+                                None => merged.push((key.clone(), node.clone())),
+                            }
+                        }
+                        Node { value: Value::Map(merged), ..value.clone() }
+                    }
+                    (Value::List(base_items), Value::List(items)) => {
+                        let items = base_items.iter().chain(items).cloned().collect();
+                        Node { value: Value::List(items), ..value.clone() }
+                    }
+                    _ => return Err(value.error("Only mappings and lists can be extended, and only by the same kind.")),
+                }
+            }
+        };
+        self.definitions.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    fn lookup(&self, name: &Node) -> Result<&Node, Error> {
+        let key = scalar(name)?;
+        self.definitions.get(key).ok_or_else(|| name.error(&format!("Unknown definition '{}'.", key)))
+    }
+
+    fn add(&mut self, entry: &Node, kind: &Node) -> Result<(), Error> {
+        match scalar(kind)? {
+            "camera" => self.camera = Some(self.camera(entry)?),
+            "light" | "directional-light" | "spot-light" | "area-light" | "spherical-light" => {
+                let light = self.light(entry, scalar(kind)?)?;
+                self.world.lights.push(light);
+            }
+            "environment" => self.environment(entry)?,
+            "sphere" | "triangle" | "obj" => {
+                let objects = self.shapes(entry, scalar(kind)?)?;
+                self.world.objects.extend(objects);
+            }
+            other => return Err(kind.error(&format!("Unknown kind '{}'.", other))),
+        }
+        Ok(())
+    }
+
+    fn camera(&self, entry: &Node) -> Result<Camera, Error> {
+        check_keys(entry, &["add", "width", "height", "field-of-view", "from", "to", "up", "transform", "aperture",
+            "focal-distance", "bokeh", "shutter", "projection", "view-width"])?;
+        let width = number(required(entry, "width")?)?;
+        let height = number(required(entry, "height")?)?;
+        if width < 1.0 || height < 1.0 || width.fract() != 0.0 || height.fract() != 0.0 {
+            return Err(entry.error("The camera needs a whole number width and height of at least 1."));
+        }
+        // either a position and target to look at or the view transform itself
+        let transform = match optional(entry, "transform") {
+            Some(node) => self.invertible_transform(node)?,
+            None => {
+                let from = point_value(required(entry, "from")?)?;
+                let to = point_value(required(entry, "to")?)?;
+                let up = vector_value(required(entry, "up")?)?;
+                let transform = view_transform(from, to, up);
+                if transform.invert().is_none() {
+                    return Err(entry.error("The camera cannot look from 'from' to 'to' with this 'up'."));
+                }
+                transform
+            }
+        };
+        let mut result = camera(width as u32, height as u32, number(required(entry, "field-of-view")?)?)
+            .set_transform(transform).map_err(|error| entry.error(&error.to_string()))?;
+        let aperture = optional(entry, "aperture").map(number).transpose()?.unwrap_or(result.aperture);
+        let focal_distance = optional(entry, "focal-distance").map(number).transpose()?.unwrap_or(result.focal_distance);
+        let bokeh = match optional(entry, "bokeh") {
+            None => result.bokeh,
+            Some(node) => match &node.value {
+                Value::Scalar(name) if name == "disk" => Bokeh::Disk,
+                Value::List(items) if items.len() == 3 && scalar(&items[0]) == Ok("polygon") => {
+                    Bokeh::Polygon { blades: count(&items[1])?, rotation: number(&items[2])? }
+                }
+                _ => return Err(node.error("Expected 'disk' or [polygon, blades, rotation].")),
+            },
+        };
+        result = result.set_lens(aperture, focal_distance, bokeh);
+        if let Some(shutter) = optional(entry, "shutter") {
+            let [open, close] = numbers::<2>(shutter)?;
+            result = result.set_shutter(open, close);
+        }
+        if let Some(projection) = optional(entry, "projection") {
+            let projection = match scalar(projection)? {
+                "perspective" => Projection::Perspective,
+                "orthographic" => Projection::Orthographic { width: number(required(entry, "view-width")?)? },
+                "fisheye" => Projection::Fisheye,
+                "equirectangular" => Projection::Equirectangular,
+                other => return Err(projection.error(&format!("Unknown projection '{}'.", other))),
+            };
+            result = result.set_projection(projection);
+        }
+        Ok(result)
+    }
+
+    fn light(&self, entry: &Node, kind: &str) -> Result<Light, Error> {
+        let intensity = colour_value(required(entry, "intensity")?)?;
+        let light = match kind {
+            "light" => {
+                check_keys(entry, &["add", "at", "intensity", "attenuation"])?;
+                point_light(point_value(required(entry, "at")?)?, intensity)
+            }
+            "directional-light" => {
+                check_keys(entry, &["add", "direction", "intensity"])?;
+                directional_light(vector_value(required(entry, "direction")?)?, intensity)
+            }
+            "spot-light" => {
+                check_keys(entry, &["add", "at", "direction", "inner-angle", "outer-angle", "intensity", "attenuation"])?;
+                spot_light(point_value(required(entry, "at")?)?, vector_value(required(entry, "direction")?)?,
+                           number(required(entry, "inner-angle")?)?, number(required(entry, "outer-angle")?)?, intensity)
+            }
+            "area-light" => {
+                check_keys(entry, &["add", "corner", "uvec", "usteps", "vvec", "vsteps", "intensity"])?;
+                area_light(point_value(required(entry, "corner")?)?,
+                           vector_value(required(entry, "uvec")?)?, count(required(entry, "usteps")?)?,
+                           vector_value(required(entry, "vvec")?)?, count(required(entry, "vsteps")?)?, intensity)
+            }
+            _ => {
+                check_keys(entry, &["add", "at", "radius", "steps", "intensity"])?;
+                spherical_light(point_value(required(entry, "at")?)?, number(required(entry, "radius")?)?,
+                                count(required(entry, "steps")?)?, intensity)
+            }
+        };
+        match optional(entry, "attenuation") {
+            Some(node) => {
+                let [constant, linear, quadratic] = numbers::<3>(node)?;
+                Ok(light.set_attenuation(attenuation(constant, linear, quadratic)))
+            }
+            None => Ok(light),
+        }
+    }
+
+    // a constant colour, a gradient, a panorama file or a daylight sky
+    fn environment(&mut self, entry: &Node) -> Result<(), Error> {
+        check_keys(entry, &["add", "colour", "color", "bottom", "top", "file", "intensity", "rotation", "sun",
+            "turbidity"])?;
+        let environment = if let Some(file) = optional(entry, "file") {
+            let intensity = optional(entry, "intensity").map(number).transpose()?.unwrap_or(1.0);
+            let rotation = optional(entry, "rotation").map(number).transpose()?.unwrap_or(0.0);
+            let path = self.directory.join(scalar(file)?);
+            load_environment(&path.to_string_lossy(), intensity, rotation).map_err(|error| match error {
+                Error::Parse(message) => file.error(&message),
+                error => error,
+            })?
+        } else if let Some(sun) = optional(entry, "sun") {
+            let turbidity = optional(entry, "turbidity").map(number).transpose()?.unwrap_or(3.0);
+            let intensity = optional(entry, "intensity").map(number).transpose()?.unwrap_or(1.0);
+            sky_environment(sky(vector_value(sun)?, turbidity).set_intensity(intensity))
+        } else if let Some(top) = optional(entry, "top") {
+            gradient_environment(colour_value(required(entry, "bottom")?)?, colour_value(top)?)
+        } else {
+            let colour = optional(entry, "colour").or(optional(entry, "color"))
+                .ok_or_else(|| entry.error("An environment needs a 'colour', 'top' and 'bottom', a 'file' or a 'sun'."))?;
+            constant_environment(colour_value(colour)?)
+        };
+        self.world.environment = environment;
+        Ok(())
+    }
+
+    fn shapes(&self, entry: &Node, kind: &str) -> Result<Vec<Object>, Error> {
+        // spheres are always unit spheres, their centre and radius become part of the transform
+        let mut placement = IDENTITY_MATRIX;
+        let shapes = match kind {
+            "sphere" => {
+                check_keys(entry, &["add", "centre", "radius", "material", "transform", "motion"])?;
+                if let Some(node) = optional(entry, "radius") {
+                    let radius = number(node)?;
+                    if radius <= 0.0 {
+                        return Err(node.error("The radius has to be positive."));
+                    }
+                    placement = scaling(radius, radius, radius);
+                }
+                if let Some(node) = optional(entry, "centre") {
+                    let centre = point_value(node)?;
+                    placement = translation(centre.x, centre.y, centre.z) * placement;
+                }
+                vec![sphere()]
+            }
+            "triangle" => {
+                check_keys(entry, &["add", "p1", "p2", "p3", "material", "transform", "motion"])?;
+                vec![triangle(point_value(required(entry, "p1")?)?, point_value(required(entry, "p2")?)?,
+                              point_value(required(entry, "p3")?)?)]
+            }
+            _ => {
+                check_keys(entry, &["add", "file", "material", "transform", "motion"])?;
+                let file = required(entry, "file")?;
+                let path = self.directory.join(scalar(file)?);
+                let data = read_file(&path.to_string_lossy())?;
+                parse_obj(&String::from_utf8_lossy(&data)).map_err(|error| file.error(&error.to_string()))?
+            }
+        };
+        let material = optional(entry, "material").map(|node| self.material(node, 0)).transpose()?;
+        let transform = optional(entry, "transform").map(|node| self.invertible_transform(node)).transpose()?;
+        let motion = optional(entry, "motion").map(|node| self.invertible_transform(node)).transpose()?;
+        shapes.into_iter().map(|mut shape| {
+            if let Some(material) = material {
+                shape = shape.set_material(material);
+            }
+            let transform = transform.unwrap_or(IDENTITY_MATRIX) * placement;
+            shape = shape.set_transform(transform).map_err(|error| entry.error(&error.to_string()))?;
+            if let Some(motion) = motion {
+                shape = shape.set_motion(motion * placement).map_err(|error| entry.error(&error.to_string()))?;
+            }
+            Ok(shape)
+        }).collect()
+    }
+
+    // a mapping of material attributes or the name of a definition
+    fn material(&self, node: &Node, depth: u32) -> Result<Material, Error> {
+        if depth > MAX_DEFINITION_DEPTH {
+            return Err(node.error("Definitions are nested too deeply."));
+        }
+        if let Value::Scalar(_) = node.value {
+            return self.material(self.lookup(node)?, depth + 1);
+        }
+        let mut result = material();
+        let mut metallic = None;
+        let mut roughness = None;
+        let mut base_colour = None;
+        let mut reflectance = None;
+        for (key, value) in map(node)? {
+            match key.as_str() {
+                "colour" | "color" => result.colour = colour_value(value)?,
+                "ambient" => result.ambient = number(value)?,
+                "diffuse" => result.diffuse = number(value)?,
+                "specular" => result.specular = number(value)?,
+                "shininess" => result.shininess = number(value)?,
+                "emission" => result.emission = colour_value(value)?,
+                "metallic" => metallic = Some(number(value)?),
+                "roughness" => roughness = Some(number(value)?),
+                "base-colour" | "base-color" => base_colour = Some(colour_value(value)?),
+                "reflectance" => reflectance = Some(number(value)?),
+                other => return Err(value.error(&format!("Unknown material attribute '{}'.", other))),
+            }
+        }
+        // any of the microfacet attributes switches the material from phong to the microfacet brdf
+        if metallic.is_some() || roughness.is_some() || base_colour.is_some() || reflectance.is_some() {
+            let mut parameters = pbr(base_colour.unwrap_or(result.colour), metallic.unwrap_or(0.0), roughness.unwrap_or(0.5));
+            parameters.specular = reflectance.unwrap_or(parameters.specular);
+            result.pbr = Some(parameters);
+        }
+        Ok(result)
+    }
+
+    // a list of operations, applied in the order they are listed, or names of definitions holding such lists
+    fn transform(&self, node: &Node, depth: u32) -> Result<Matrix<4>, Error> {
+        if depth > MAX_DEFINITION_DEPTH {
+            return Err(node.error("Definitions are nested too deeply."));
+        }
+        if let Value::Scalar(_) = node.value {
+            return self.transform(self.lookup(node)?, depth + 1);
+        }
+        let mut result = IDENTITY_MATRIX;
+        for operation in list(node)? {
+            let step = match &operation.value {
+                Value::Scalar(_) => self.transform(self.lookup(operation)?, depth + 1)?,
+                Value::List(items) if !items.is_empty() => {
+                    let arguments: Vec<f64> = items[1..].iter().map(number).collect::<Result<_, _>>()?;
+                    let expect = |count: usize| {
+                        if arguments.len() == count {
+                            Ok(())
+                        } else {
+                            Err(operation.error(&format!("'{}' takes {} numbers.", scalar(&items[0]).unwrap_or(""), count)))
+                        }
+                    };
+                    match scalar(&items[0])? {
+                        "translate" => expect(3).map(|_| translation(arguments[0], arguments[1], arguments[2]))?,
+                        "scale" => expect(3).map(|_| scaling(arguments[0], arguments[1], arguments[2]))?,
+                        "rotate-x" => expect(1).map(|_| rotation_x(arguments[0]))?,
+                        "rotate-y" => expect(1).map(|_| rotation_y(arguments[0]))?,
+                        "rotate-z" => expect(1).map(|_| rotation_z(arguments[0]))?,
+                        "shear" => expect(6).map(|_| shearing(arguments[0], arguments[1], arguments[2], arguments[3],
+                                                              arguments[4], arguments[5]))?,
+                        // the 16 entries row by row
+                        "matrix" => expect(16).map(|_| {
+                            let mut rows = [[0.0; 4]; 4];
+                            for (index, value) in arguments.iter().enumerate() {
+                                rows[index / 4][index % 4] = *value;
+                            }
+                            matrix::<4>(rows)
+                        })?,
+                        other => return Err(items[0].error(&format!("Unknown transform '{}'.", other))),
+                    }
+                }
+                _ => return Err(operation.error("Expected a transform like [translate, 1, 2, 3] or a definition.")),
+            };
+            result = step * result;
+        }
+        Ok(result)
+    }
+
+    fn invertible_transform(&self, node: &Node) -> Result<Matrix<4>, Error> {
+        let transform = self.transform(node, 0)?;
+        if transform.invert().is_none() {
+            return Err(node.error("The transform cannot be inverted."));
+        }
+        Ok(transform)
+    }
+}
+
+fn map(node: &Node) -> Result<&[(String, Node)], Error> {
+    match &node.value {
+        Value::Map(entries) => Ok(entries),
+        _ => Err(node.error("Expected a mapping of 'key: value' entries.")),
+    }
+}
+
+fn list(node: &Node) -> Result<&[Node], Error> {
+    match &node.value {
+        Value::List(items) => Ok(items),
+        _ => Err(node.error("Expected a list.")),
+    }
+}
+
+fn scalar(node: &Node) -> Result<&str, Error> {
+    match &node.value {
+        Value::Scalar(text) => Ok(text),
+        _ => Err(node.error("Expected a single value.")),
+    }
+}
+
+fn find<'a>(entries: &'a [(String, Node)], key: &str) -> Option<&'a Node> {
+    entries.iter().find(|(existing, _)| existing == key).map(|(_, node)| node)
+}
+
+fn optional<'a>(entry: &'a Node, key: &str) -> Option<&'a Node> {
+    match &entry.value {
+        Value::Map(entries) => find(entries, key),
+        _ => None,
+    }
+}
+
+fn required<'a>(entry: &'a Node, key: &str) -> Result<&'a Node, Error> {
+    optional(entry, key).ok_or_else(|| entry.error(&format!("Missing '{}'.", key)))
+}
+
+fn check_keys(entry: &Node, allowed: &[&str]) -> Result<(), Error> {
+    for (key, node) in map(entry)? {
+        if !allowed.contains(&key.as_str()) {
+            return Err(node.error(&format!("Unknown attribute '{}'.", key)));
+        }
+    }
+    Ok(())
+}
+
+// a plain number or a multiple of pi, like 'pi', '-pi/2' or '0.5pi'
+fn number(node: &Node) -> Result<f64, Error> {
+    let text = scalar(node)?;
+    let invalid = || node.error(&format!("Expected a number, found '{}'.", text));
+    if let Ok(value) = text.parse::<f64>() {
+        return Ok(value);
+    }
+    let (numerator, denominator) = match text.split_once('/') {
+        Some((numerator, denominator)) => (numerator.trim(), denominator.trim().parse::<f64>().map_err(|_| invalid())?),
+        None => (text, 1.0),
+    };
+    let factor = numerator.strip_suffix("pi").ok_or_else(invalid)?.trim();
+    let factor = match factor {
+        "" => 1.0,
+        "-" => -1.0,
+        _ => factor.parse::<f64>().map_err(|_| invalid())?,
+    };
+    Ok(factor * PI / denominator)
+}
+
+fn count(node: &Node) -> Result<u32, Error> {
+    let value = number(node)?;
+    if value < 1.0 || value.fract() != 0.0 || value > u32::MAX as f64 {
+        return Err(node.error("Expected a whole number of at least 1."));
+    }
+    Ok(value as u32)
+}
+
+fn numbers<const N: usize>(node: &Node) -> Result<[f64; N], Error> {
+    let items = list(node)?;
+    if items.len() != N {
+        return Err(node.error(&format!("Expected a list of {} numbers.", N)));
+    }
+    let mut result = [0.0; N];
+    for (value, item) in result.iter_mut().zip(items) {
+        *value = number(item)?;
+    }
+    Ok(result)
+}
+
+fn point_value(node: &Node) -> Result<Tuple, Error> {
+    let [x, y, z] = numbers::<3>(node)?;
+    Ok(point(x, y, z))
+}
+
+fn vector_value(node: &Node) -> Result<Tuple, Error> {
+    let [x, y, z] = numbers::<3>(node)?;
+    Ok(vector(x, y, z))
+}
+
+fn colour_value(node: &Node) -> Result<Colour, Error> {
+    let [red, green, blue] = numbers::<3>(node)?;
+    Ok(colour(red, green, blue))
+}
+
+// the entries `scene_from_node` reads, every transform is written as a single matrix so nothing is lost to rounding
+pub fn scene_to_node(scene: &Scene) -> Result<Node, Error> {
+    let camera = &scene.camera;
+    let mut camera_entries = vec![
+        entry("add", text("camera")),
+        entry("width", real(camera.hsize as f64)?),
+        entry("height", real(camera.vsize as f64)?),
+        entry("field-of-view", real(camera.field_of_view)?),
+        entry("transform", transform_node(camera.transform())?),
+        entry("aperture", real(camera.aperture)?),
+        entry("focal-distance", real(camera.focal_distance)?),
+        entry("bokeh", match camera.bokeh {
+            Bokeh::Disk => text("disk"),
+            Bokeh::Polygon { blades, rotation } => list_node(vec![text("polygon"), real(blades as f64)?, real(rotation)?]),
+        }),
+        entry("shutter", reals(&[camera.shutter_open, camera.shutter_close])?),
+    ];
+    camera_entries.push(entry("projection", text(match camera.projection {
+        Projection::Perspective => "perspective",
+        Projection::Orthographic { .. } => "orthographic",
+        Projection::Fisheye => "fisheye",
+        Projection::Equirectangular => "equirectangular",
+    })));
+    if let Projection::Orthographic { width } = camera.projection {
+        camera_entries.push(entry("view-width", real(width)?));
+    }
+    let mut entries = vec![map_node(camera_entries)];
+
+    entries.push(map_node(match &scene.world.environment {
+        Environment::Constant(colour) => vec![entry("add", text("environment")), entry("colour", colour_node(*colour)?)],
+        Environment::Gradient { bottom, top } => vec![
+            entry("add", text("environment")),
+            entry("bottom", colour_node(*bottom)?),
+            entry("top", colour_node(*top)?),
+        ],
+        Environment::Sky { sky, .. } => vec![
+            entry("add", text("environment")),
+            entry("sun", tuple_node(sky.sun_direction)?),
+            entry("turbidity", real(sky.turbidity)?),
+            entry("intensity", real(sky.intensity)?),
+        ],
+        Environment::Image(_) => return Err(Error::Unsupported(String::from("Panorama environments cannot be saved, they do not remember their file."))),
+    }));
+
+    for light in &scene.world.lights {
+        entries.push(map_node(light_entries(light)?));
+    }
+
+    for object in &scene.world.objects {
+        let mut object_entries = match object {
+            Object::Sphere { .. } => vec![entry("add", text("sphere"))],
+            Object::Triangle { p1, p2, p3, .. } => vec![
+                entry("add", text("triangle")),
+                entry("p1", tuple_node(*p1)?),
+                entry("p2", tuple_node(*p2)?),
+                entry("p3", tuple_node(*p3)?),
+            ],
+        };
+        object_entries.push(entry("material", material_node(&object.material())?));
+        // the offset of a sphere from its object space origin is written as part of its transform
+        let (transformation, end_transformation) = match object {
+            Object::Sphere { position, transformation, end_transformation, .. } => {
+                let offset = translation(position.x, position.y, position.z);
+                (*transformation * offset, end_transformation.map(|end| end * offset))
+            }
+            Object::Triangle { transformation, end_transformation, .. } => (*transformation, *end_transformation),
+        };
+        object_entries.push(entry("transform", transform_node(transformation)?));
+        if let Some(end) = end_transformation {
+            object_entries.push(entry("motion", transform_node(end)?));
+        }
+        entries.push(map_node(object_entries));
+    }
+    Ok(list_node(entries))
+}
+
+fn light_entries(light: &Light) -> Result<Vec<(String, Node)>, Error> {
+    let attenuation_node = |attenuation: &Attenuation| reals(&[attenuation.constant, attenuation.linear, attenuation.quadratic]);
+    Ok(match light {
+        Light::Point { position, intensity, attenuation } => vec![
+            entry("add", text("light")),
+            entry("at", tuple_node(*position)?),
+            entry("intensity", colour_node(*intensity)?),
+            entry("attenuation", attenuation_node(attenuation)?),
+        ],
+        Light::Directional { direction, intensity } => vec![
+            entry("add", text("directional-light")),
+            entry("direction", tuple_node(*direction)?),
+            entry("intensity", colour_node(*intensity)?),
+        ],
+        Light::Spot { position, direction, inner_angle, outer_angle, intensity, attenuation } => vec![
+            entry("add", text("spot-light")),
+            entry("at", tuple_node(*position)?),
+            entry("direction", tuple_node(*direction)?),
+            entry("inner-angle", real(*inner_angle)?),
+            entry("outer-angle", real(*outer_angle)?),
+            entry("intensity", colour_node(*intensity)?),
+            entry("attenuation", attenuation_node(attenuation)?),
+        ],
+        Light::Rectangle { corner, uvec, usteps, vvec, vsteps, intensity } => vec![
+            entry("add", text("area-light")),
+            entry("corner", tuple_node(*corner)?),
+            entry("uvec", tuple_node(*uvec * *usteps as f64)?),
+            entry("usteps", real(*usteps as f64)?),
+            entry("vvec", tuple_node(*vvec * *vsteps as f64)?),
+            entry("vsteps", real(*vsteps as f64)?),
+            entry("intensity", colour_node(*intensity)?),
+        ],
+        Light::Spherical { centre, radius, steps, intensity } => vec![
+            entry("add", text("spherical-light")),
+            entry("at", tuple_node(*centre)?),
+            entry("radius", real(*radius)?),
+            entry("steps", real(*steps as f64)?),
+            entry("intensity", colour_node(*intensity)?),
+        ],
+    })
+}
+
+fn material_node(material: &Material) -> Result<Node, Error> {
+    let mut entries = vec![
+        entry("colour", colour_node(material.colour)?),
+        entry("ambient", real(material.ambient)?),
+        entry("diffuse", real(material.diffuse)?),
+        entry("specular", real(material.specular)?),
+        entry("shininess", real(material.shininess)?),
+        entry("emission", colour_node(material.emission)?),
+    ];
+    if let Some(parameters) = material.pbr {
+        entries.push(entry("base-colour", colour_node(parameters.base_colour)?));
+        entries.push(entry("metallic", real(parameters.metallic)?));
+        entries.push(entry("roughness", real(parameters.roughness)?));
+        entries.push(entry("reflectance", real(parameters.specular)?));
+    }
+    Ok(map_node(entries))
+}
+
+fn transform_node(transform: Matrix<4>) -> Result<Node, Error> {
+    let mut items = vec![text("matrix")];
+    for row in transform.data {
+        for value in row {
+            items.push(real(value)?);
+        }
+    }
+    Ok(list_node(vec![list_node(items)]))
+}
+
+fn node(value: Value) -> Node {
+    Node { value, line: 0, column: 0 }
+}
+
+fn text(value: &str) -> Node {
+    node(Value::Scalar(value.to_string()))
+}
+
+// rust prints the shortest text that parses back to the same number
+fn real(value: f64) -> Result<Node, Error> {
+    if !value.is_finite() {
+        return Err(Error::Unsupported(format!("Cannot save the number {}.", value)));
+    }
+    Ok(node(Value::Scalar(format!("{}", value))))
+}
+
+fn reals(values: &[f64]) -> Result<Node, Error> {
+    Ok(list_node(values.iter().map(|value| real(*value)).collect::<Result<_, _>>()?))
+}
+
+fn tuple_node(tuple: Tuple) -> Result<Node, Error> {
+    reals(&[tuple.x, tuple.y, tuple.z])
+}
+
+fn colour_node(colour: Colour) -> Result<Node, Error> {
+    reals(&[colour.red, colour.green, colour.blue])
+}
+
+fn list_node(items: Vec<Node>) -> Node {
+    node(Value::List(items))
+}
+
+fn map_node(entries: Vec<(String, Node)>) -> Node {
+    node(Value::Map(entries))
+}
+
+fn entry(key: &str, value: Node) -> (String, Node) {
+    (key.to_string(), value)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::colour::colour;
+    use crate::error::Error;
+    use crate::lights::Light;
+    use crate::ray::{Intersections, ray};
+    use crate::scene::{load_scene, parse_scene};
+    use crate::tuple::{point, point_i, vector_i};
+    use crate::util::write_file;
+
+    const SCENE: &str = "
+# a sphere on a floor
+- add: camera
+  width: 40
+  height: 20
+  field-of-view: pi/3
+  from: [0, 1.5, -5]
+  to: [0, 1, 0]
+  up: [0, 1, 0]
+
+- add: light
+  at: [-10, 10, -10]
+  intensity: [1, 1, 1]
+
+- define: white-material
+  value:
+    color: [1, 1, 1]
+    diffuse: 0.7
+    ambient: 0.1
+
+- define: blue-material
+  extend: white-material
+  value:
+    color: [0.5, 0.8, 0.9]
+
+- define: standard-transform
+  value:
+    - [translate, 1, -1, 1]
+    - [scale, 0.5, 0.5, 0.5]
+
+- add: sphere
+  material: blue-material
+  transform:
+    - standard-transform
+    - [rotate-y, pi/2]
+
+- add: triangle
+  p1: [0, 0, 0]
+  p2: [1, 0, 0]
+  p3: [0, 1, 0]
+  material:
+    color: [1, 0, 0]   # red
+    metallic: 1
+    roughness: 0.2
+";
+
+    #[test]
+    fn building_a_scene() {
+        let scene = parse_scene(SCENE).unwrap();
+        assert_eq!((scene.camera.hsize, scene.camera.vsize), (40, 20));
+        assert!((scene.camera.field_of_view - PI / 3.0).abs() < 1e-12);
+        assert_eq!(scene.world.lights.len(), 1);
+        assert!(matches!(scene.world.lights[0], Light::Point { .. }));
+        assert_eq!(scene.world.objects.len(), 2);
+
+        // the extended material keeps the attributes it did not override
+        let sphere = scene.world.objects[0];
+        let m = sphere.material();
+        assert_eq!((m.colour.red, m.colour.green, m.diffuse), (0.5, 0.8, 0.7));
+        // the listed transforms apply in order, a quarter turn around y takes +x to -z
+        let moved = sphere.transformation_at(0.0) * point_i(0, 0, 0);
+        assert!((moved.x - 0.5).abs() < 1e-9 && (moved.y + 0.5).abs() < 1e-9 && (moved.z + 0.5).abs() < 1e-9);
+
+        let red = scene.world.objects[1].material();
+        assert_eq!(red.colour, colour(1.0, 0.0, 0.0));
+        assert_eq!(red.pbr.map(|pbr| pbr.metallic), Some(1.0));
+    }
+
+    #[test]
+    fn spheres_take_a_centre_and_radius() {
+        let scene = parse_scene(&format!("{}\n- add: sphere\n  centre: [1, 2, 3]\n  radius: 2\n  transform: [[translate, 0, 0, 1]]\n",
+                                         SCENE)).unwrap();
+        let s = scene.world.objects[2];
+        let Intersections::Some(xs) = ray(point_i(1, 2, -5), vector_i(0, 0, 1)).intersect(&s) else { panic!("expected two hits") };
+        assert!((xs[0].time - 7.0).abs() < 1e-9 && (xs[1].time - 11.0).abs() < 1e-9);
+        let normal = s.normal_at(point_i(3, 2, 4));
+        assert!((normal.x - 1.0).abs() < 1e-9 && normal.y.abs() < 1e-9 && normal.z.abs() < 1e-9);
+    }
+
+    #[test]
+    fn scene_errors_have_positions() {
+        let error = |text: &str| parse_scene(text).err().unwrap().to_string();
+        assert_eq!(error("- add: sphere\n  colour: [1, 0, 0]\n"), "Line 2, column 11: Unknown attribute 'colour'.");
+        assert_eq!(error("- add: sphere\n  material: shiny\n"), "Line 2, column 13: Unknown definition 'shiny'.");
+        assert_eq!(error("- add: sphere\n  transform:\n    - [scale, 1, x, 1]\n"),
+                   "Line 3, column 18: Expected a number, found 'x'.");
+        assert_eq!(error("- add: sphere\n  transform:\n    - [rotate-x, 1, 2]\n"),
+                   "Line 3, column 7: 'rotate-x' takes 1 numbers.");
+        assert_eq!(error("- add: cone\n"), "Line 1, column 8: Unknown kind 'cone'.");
+        assert_eq!(error("- add: sphere\n  radius: 0\n"), "Line 2, column 11: The radius has to be positive.");
+        assert_eq!(error("- add: light\n  at: [0, 0, 0]\n"), "Line 1, column 3: Missing 'intensity'.");
+        assert_eq!(error("- define: a\n  value: [[scale, 0, 1, 1]]\n- add: sphere\n  transform: a\n"),
+                   "Line 4, column 14: The transform cannot be inverted.");
+        assert_eq!(error("- define: a\n  value: [a]\n- define: a\n  extend: a\n  value: [a]\n- add: sphere\n  transform: a\n"),
+                   "Line 5, column 10: Definitions are nested too deeply.");
+        assert_eq!(error("- add: sphere\n"), "The scene has no camera.");
+    }
+
+    #[test]
+    fn loading_a_scene_with_a_model() {
+        let directory = std::env::temp_dir().join(format!("scene-with-model-{}", std::process::id()));
+        let path = |name: &str| directory.join(name).to_string_lossy().into_owned();
+        let written = std::fs::create_dir_all(&directory).map_err(|error| Error::Io(error.to_string()))
+            .and(write_file(&path("model.obj"), "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\n"))
+            .and(write_file(&path("scene.yml"), format!("{}\n- add: obj\n  file: model.obj\n  transform: [[translate, 0, 0, 2]]\n", SCENE)))
+            .and(write_file(&path("missing-model.yml"), format!("{}\n- add: obj\n  file: missing.obj\n", SCENE)));
+        let scene = load_scene(&path("scene.yml"));
+        let missing_model = load_scene(&path("missing-model.yml"));
+        let _ = std::fs::remove_dir_all(&directory);
+        written.unwrap();
+        let scene = scene.unwrap();
+        assert_eq!(scene.world.objects.len(), 4);
+        let corner = scene.world.objects[3].transformation_at(0.0) * point(1.0, 0.0, 0.0);
+        assert_eq!((corner.x, corner.z), (1.0, 2.0));
+        assert!(matches!(load_scene(&path("missing.yml")), Err(Error::Io(_))));
+        assert!(matches!(missing_model, Err(Error::Io(_))));
+    }
+}
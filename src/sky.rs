@@ -0,0 +1,262 @@
+use std::f64::consts::PI;
+
+use crate::colour::{BLACK, colour, Colour};
+use crate::lights::{directional_light, Light};
+use crate::tuple::{Tuple, vector};
+use crate::util::radians;
+
+// illuminance of the sun outside the atmosphere in kilolux, the sky is in kcd/m^2 so both match
+const SOLAR_ILLUMINANCE: f64 = 128.0;
+// wavelengths in micrometres the red, green and blue channels of the sun colour are evaluated at
+const WAVELENGTHS: [f64; 3] = [0.68, 0.55, 0.44];
+
+// Preetham, Shirley and Smits' analytic daylight model. directions use +y as up, -z as north and +x as east
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Sky {
+    pub sun_direction: Tuple,
+    // haziness of the atmosphere, 2 is a very clear sky and 10 a hazy one
+    pub turbidity: f64,
+    // scales the luminance, which is in kcd/m^2
+    pub intensity: f64,
+    zenith: [f64; 3],
+    coefficients: [[f64; 5]; 3],
+}
+
+pub fn sky(sun_direction: Tuple, turbidity: f64) -> Sky {
+    let sun_direction = sun_direction.normalize();
+    let t = turbidity.clamp(1.7, 10.0);
+    // the model breaks down for the sun below the horizon
+    let theta = sun_direction.y.clamp(0.0, 1.0).acos();
+
+    let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta);
+    let zenith_luminance = ((4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192).max(0.0);
+    let polynomial = |c: [[f64; 4]; 3]| {
+        let thetas = [theta.powi(3), theta.powi(2), theta, 1.0];
+        let row = |r: [f64; 4]| r.iter().zip(thetas).map(|(a, b)| a * b).sum::<f64>();
+        t * t * row(c[0]) + t * row(c[1]) + row(c[2])
+    };
+    let zenith_x = polynomial([
+        [0.00166, -0.00375, 0.00209, 0.0],
+        [-0.02903, 0.06377, -0.03202, 0.00394],
+        [0.11693, -0.21196, 0.06052, 0.25886],
+    ]);
+    let zenith_y = polynomial([
+        [0.00275, -0.00610, 0.00317, 0.0],
+        [-0.04214, 0.08970, -0.04153, 0.00516],
+        [0.15346, -0.26756, 0.06670, 0.26688],
+    ]);
+
+    Sky {
+        sun_direction,
+        turbidity: t,
+        intensity: 1.0,
+        zenith: [zenith_luminance, zenith_x, zenith_y],
+        coefficients: [
+            [0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251, 0.1206 * t - 2.5771, -0.0670 * t + 0.3703],
+            [-0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125, -0.0641 * t - 0.8989, -0.0033 * t + 0.0452],
+            [-0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102, -0.0441 * t - 1.6537, -0.0109 * t + 0.0529],
+        ],
+    }
+}
+
+impl Sky {
+    pub fn set_intensity(self, intensity: f64) -> Sky {
+        Sky {
+            intensity,
+            ..self
+        }
+    }
+
+    // radiance of the sky in `direction`, without the sun itself, black below the horizon
+    pub fn radiance(&self, direction: Tuple) -> Colour {
+        let direction = direction.normalize();
+        if direction.y <= 0.0 {
+            return BLACK;
+        }
+        let theta = direction.y.acos();
+        let theta_sun = self.sun_direction.y.clamp(0.0, 1.0).acos();
+        let cos_gamma = (direction.x * self.sun_direction.x + direction.y * self.sun_direction.y
+            + direction.z * self.sun_direction.z).clamp(-1.0, 1.0);
+        let gamma = cos_gamma.acos();
+
+        let mut values = [0.0; 3];
+        for (channel, value) in values.iter_mut().enumerate() {
+            let [a, b, c, d, e] = self.coefficients[channel];
+            let perez = |theta: f64, gamma: f64| {
+                (1.0 + a * (b / theta.cos().max(0.01)).exp()) * (1.0 + c * (d * gamma).exp() + e * gamma.cos().powi(2))
+            };
+            *value = self.zenith[channel] * perez(theta, gamma) / perez(0.0, theta_sun);
+        }
+        let [luminance, x, y] = values;
+        xyy_to_rgb(luminance * self.intensity, x, y)
+    }
+
+    // colour of direct sunlight after passing through the atmosphere, as irradiance at normal incidence
+    pub fn sun_colour(&self) -> Colour {
+        let elevation = self.sun_direction.y.clamp(-1.0, 1.0).asin().to_degrees();
+        if elevation <= -0.5 {
+            return BLACK;
+        }
+        // kasten and young's relative air mass
+        let zenith_degrees = 90.0 - elevation;
+        let air_mass = 1.0 / (radians(zenith_degrees).cos() + 0.50572 * (96.07995 - zenith_degrees).powf(-1.6364));
+        // rayleigh scattering by the air and aerosol extinction after angstroem, both as optical depth at the zenith
+        let beta = 0.04608 * self.turbidity - 0.04586;
+        let transmittance = WAVELENGTHS.map(|wavelength| {
+            let rayleigh = 0.008735 * wavelength.powf(-4.08);
+            let aerosol = beta * wavelength.powf(-1.3);
+            (-air_mass * (rayleigh + aerosol)).exp()
+        });
+        colour(transmittance[0], transmittance[1], transmittance[2]) * (SOLAR_ILLUMINANCE / PI * self.intensity)
+    }
+
+    // a directional light standing in for the sun of this sky
+    pub fn sun_light(&self) -> Light {
+        directional_light(-self.sun_direction, self.sun_colour())
+    }
+}
+
+fn xyy_to_rgb(luminance: f64, x: f64, y: f64) -> Colour {
+    if y <= 0.0 {
+        return BLACK;
+    }
+    let big_x = x / y * luminance;
+    let big_z = (1.0 - x - y) / y * luminance;
+    // XYZ to linear sRGB
+    colour(
+        (3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z).max(0.0),
+        (-0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z).max(0.0),
+        (0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z).max(0.0),
+    )
+}
+
+// direction towards the sun, `latitude` and `longitude` in degrees with north and east positive, the time is UTC
+// in fractional hours. uses the NOAA approximations, which are good to a fraction of a degree
+pub fn sun_direction(latitude: f64, longitude: f64, year: i32, month: u32, day: u32, hours: f64) -> Tuple {
+    let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let days_before_month = [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334];
+    let month_index = (month.clamp(1, 12) - 1) as usize;
+    let day_of_year = days_before_month[month_index] + day + if leap && month > 2 { 1 } else { 0 };
+    let days_in_year = if leap { 366.0 } else { 365.0 };
+
+    let year_angle = 2.0 * PI / days_in_year * (day_of_year as f64 - 1.0 + (hours - 12.0) / 24.0);
+    let equation_of_time = 229.18 * (0.000075 + 0.001868 * year_angle.cos() - 0.032077 * year_angle.sin()
+        - 0.014615 * (2.0 * year_angle).cos() - 0.040849 * (2.0 * year_angle).sin());
+    let declination = 0.006918 - 0.399912 * year_angle.cos() + 0.070257 * year_angle.sin()
+        - 0.006758 * (2.0 * year_angle).cos() + 0.000907 * (2.0 * year_angle).sin()
+        - 0.002697 * (3.0 * year_angle).cos() + 0.00148 * (3.0 * year_angle).sin();
+
+    let true_solar_minutes = hours * 60.0 + equation_of_time + 4.0 * longitude;
+    let hour_angle = radians(true_solar_minutes / 4.0 - 180.0);
+    let latitude = radians(latitude);
+
+    let cos_zenith = (latitude.sin() * declination.sin() + latitude.cos() * declination.cos() * hour_angle.cos()).clamp(-1.0, 1.0);
+    let sin_zenith = (1.0 - cos_zenith * cos_zenith).sqrt();
+    // clockwise from north
+    let azimuth = hour_angle.sin().atan2(hour_angle.cos() * latitude.sin() - declination.tan() * latitude.cos()) + PI;
+    vector(azimuth.sin() * sin_zenith, cos_zenith, -azimuth.cos() * sin_zenith)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::environment::sky_environment;
+    use crate::integrator::path_tracing;
+    use crate::lights::Light;
+    use crate::materials::material;
+    use crate::objects::sphere;
+    use crate::ray::ray;
+    use crate::sky::{sky, sun_direction};
+    use crate::tuple::{point_i, vector, vector_i};
+    use crate::world::world;
+
+    #[test]
+    fn sun_position() {
+        // midsummer noon in greenwich, the sun stands about 62 degrees above the southern horizon
+        let noon = sun_direction(51.48, 0.0, 2024, 6, 21, 12.0);
+        assert!((noon.y.asin().to_degrees() - 62.0).abs() < 0.5);
+        assert!(noon.z > 0.0 && noon.x.abs() < 0.02);
+        // it rises in the north east and is below the horizon at midnight
+        let morning = sun_direction(51.48, 0.0, 2024, 6, 21, 5.0);
+        assert!(morning.x > 0.0 && morning.z < 0.0 && morning.y > 0.0);
+        assert!(sun_direction(51.48, 0.0, 2024, 6, 21, 0.0).y < 0.0);
+        // overhead at the equator at the equinox, once the equation of time is accounted for
+        assert!(sun_direction(0.0, 0.0, 2024, 3, 20, 12.12).y > 0.999);
+        // the southern hemisphere sees the noon sun in the north
+        assert!(sun_direction(-33.9, 151.2, 2024, 6, 21, 2.0).z < 0.0);
+    }
+
+    #[test]
+    fn sky_radiance() {
+        let s = sky(vector(0.0, 0.5, 1.0), 3.0);
+        let zenith = s.radiance(vector_i(0, 1, 0));
+        // a clear sky is blue, brightest around the sun and black below the horizon
+        assert!(zenith.blue > zenith.red);
+        assert!(s.radiance(vector(0.0, 0.5, 1.1)).green > s.radiance(vector(0.0, 0.5, -1.0)).green);
+        assert_eq!(s.radiance(vector(0.0, -0.1, 1.0)).green, 0.0);
+        assert!((s.set_intensity(2.0).radiance(vector_i(0, 1, 0)).green - 2.0 * zenith.green).abs() < 1e-9);
+        // haze makes the horizon whiter
+        let horizon = |turbidity: f64| {
+            let c = sky(vector(0.0, 0.5, 1.0), turbidity).radiance(vector(1.0, 0.05, 0.0));
+            c.red / c.blue
+        };
+        assert!(horizon(8.0) > horizon(2.0));
+    }
+
+    #[test]
+    fn sunlight_reddens_towards_the_horizon() {
+        let high = sky(vector(0.0, 1.0, 0.3), 3.0);
+        let low = sky(vector(0.0, 0.05, 1.0), 3.0);
+        let ratio = |c: crate::colour::Colour| c.red / c.blue;
+        assert!(ratio(low.sun_colour()) > ratio(high.sun_colour()));
+        assert!(low.sun_colour().green < high.sun_colour().green);
+        assert_eq!(sky(vector(0.0, -0.5, 1.0), 3.0).sun_colour().green, 0.0);
+        match high.sun_light() {
+            Light::Directional { direction, .. } => assert!((direction.y + high.sun_direction.y).abs() < 1e-9),
+            _ => panic!("expected a directional light"),
+        }
+    }
+
+    #[test]
+    fn sky_as_miss_colour_and_light() {
+        let s = sky(vector(1.0, 0.3, 0.0), 3.0);
+        let environment = sky_environment(s);
+        assert!(!environment.is_black());
+        let direction = vector(0.3, 0.6, -0.2);
+        assert_eq!(environment.colour(direction), s.radiance(direction));
+
+        // importance sampling gives the same irradiance on an upward facing surface as a uniform quadrature
+        let steps = 64;
+        let mut sampled = 0.0;
+        let mut reference = 0.0;
+        for i in 0..steps {
+            for j in 0..steps {
+                let (u, v) = ((i as f64 + 0.5) / steps as f64, (j as f64 + 0.5) / steps as f64);
+                let sample = environment.sample(u, v);
+                assert!((sample.pdf - environment.pdf(sample.direction)).abs() < 1e-9 * sample.pdf.max(1.0));
+                if sample.pdf > 0.0 {
+                    sampled += sample.radiance.green * sample.direction.y.max(0.0) / sample.pdf;
+                }
+                let cos_theta = u;
+                let phi = 2.0 * PI * v;
+                let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+                let uniform = vector(sin_theta * phi.cos(), cos_theta, sin_theta * phi.sin());
+                reference += s.radiance(uniform).green * cos_theta * 2.0 * PI;
+            }
+        }
+        let count = (steps * steps) as f64;
+        assert!((sampled / count - reference / count).abs() < 0.05 * reference / count);
+
+        // a diffuse sphere is lit from above by the sky alone
+        let mut w = world();
+        w.environment = environment;
+        let mut grey = material();
+        grey.ambient = 0.0;
+        w.objects.push(sphere().set_material(grey));
+        let integrator = path_tracing(200, 2);
+        let top = integrator.colour_at(&w, &ray(point_i(0, 5, 0), vector_i(0, -1, 0)));
+        let bottom = integrator.colour_at(&w, &ray(point_i(0, -5, 0), vector_i(0, 1, 0)));
+        assert!(top.green > 0.1 && bottom.green < 0.1 * top.green);
+    }
+}
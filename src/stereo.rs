@@ -0,0 +1,159 @@
+use crate::camera::{Camera, view_transform};
+use crate::canvas::{canvas, Canvas};
+use crate::colour::colour;
+use crate::error::Error;
+use crate::tuple::{point, vector};
+
+// two cameras side by side that both look at the convergence point straight ahead of `camera`
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct StereoRig {
+    pub camera: Camera,
+    // distance between the eyes, in world units
+    pub interocular: f64,
+    // distance in front of the rig at which both eyes see the same point, infinity gives parallel eyes
+    pub convergence: f64,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Eye {
+    Left,
+    Right,
+}
+
+pub fn stereo_rig(camera: Camera, interocular: f64, convergence: f64) -> StereoRig {
+    StereoRig {
+        camera,
+        interocular,
+        convergence,
+    }
+}
+
+impl StereoRig {
+    // the eyes are turned inwards towards the convergence point, in the space of the rig's camera
+    pub fn eye_camera(&self, eye: Eye) -> Result<Camera, Error> {
+        // the camera looks along -z and sees +x on the left of the image
+        let offset = match eye {
+            Eye::Left => self.interocular / 2.0,
+            Eye::Right => -self.interocular / 2.0,
+        };
+        let position = point(offset, 0.0, 0.0);
+        let target = if self.convergence.is_finite() && self.convergence > 0.0 {
+            point(0.0, 0.0, -self.convergence)
+        } else {
+            point(offset, 0.0, -1.0)
+        };
+        let eye_view = view_transform(position, target, vector(0.0, 1.0, 0.0));
+        self.camera.set_transform(eye_view * self.camera.transform())
+    }
+
+    // `render` is called once per eye, so any of the camera's render methods can be used
+    pub fn render<F>(&self, render: F) -> Result<(Canvas, Canvas), Error>
+        where F: Fn(&Camera) -> Canvas {
+        Ok((render(&self.eye_camera(Eye::Left)?), render(&self.eye_camera(Eye::Right)?)))
+    }
+}
+
+// the left eye on the left half and the right eye on the right half
+pub fn side_by_side(left: &Canvas, right: &Canvas) -> Result<Canvas, Error> {
+    check_sizes(left, right)?;
+    let mut image = canvas(left.width * 2, left.height);
+    image.output = left.output;
+    for y in 0..left.height {
+        let row = image.row_mut(y);
+        row[..left.width as usize].copy_from_slice(left.row(y));
+        row[left.width as usize..].copy_from_slice(right.row(y));
+    }
+    Ok(image)
+}
+
+// red-cyan anaglyph, the red channel comes from the left eye and green and blue from the right eye
+pub fn anaglyph(left: &Canvas, right: &Canvas) -> Result<Canvas, Error> {
+    check_sizes(left, right)?;
+    let mut image = canvas(left.width, left.height);
+    image.output = left.output;
+    for ((pixel, l), r) in image.pixels.iter_mut().zip(&left.pixels).zip(&right.pixels) {
+        *pixel = colour(l.red, r.green, r.blue);
+    }
+    Ok(image)
+}
+
+fn check_sizes(left: &Canvas, right: &Canvas) -> Result<(), Error> {
+    if left.width != right.width || left.height != right.height {
+        return Err(Error::SizeMismatch(format!("Left eye of size {}x{} does not match the right eye of size {}x{}.",
+                                               left.width, left.height, right.width, right.height)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::camera::{camera, view_transform};
+    use crate::canvas::canvas;
+    use crate::colour::{colour, Colour};
+    use crate::error::Error;
+    use crate::stereo::{anaglyph, Eye, side_by_side, stereo_rig};
+    use crate::tuple::{point_i, vector_i};
+    use crate::world::default_world;
+
+    #[test]
+    fn eyes_converge_on_the_same_point() {
+        let c = camera(101, 51, PI / 3.0).set_transform(view_transform(point_i(0, 0, -5), point_i(0, 0, 0), vector_i(0, 1, 0))).unwrap();
+        let rig = stereo_rig(c, 0.5, 5.0);
+        let left = rig.eye_camera(Eye::Left).unwrap().ray_for_pixel(50.5, 25.5);
+        let right = rig.eye_camera(Eye::Right).unwrap().ray_for_pixel(50.5, 25.5);
+        // looking down +z from -5, the left eye sits at -x in the world
+        assert!(left.origin.x < 0.0 && right.origin.x > 0.0);
+        for (r, x) in [(&left, -0.25), (&right, 0.25)] {
+            assert!((r.origin.x - x).abs() < 1e-9 && r.origin.y.abs() < 1e-9 && (r.origin.z + 5.0).abs() < 1e-9);
+        }
+        for r in [left, right] {
+            let hit = r.origin + r.direction * 5.0_f64.hypot(0.25);
+            assert!(hit.x.abs() < 1e-9 && hit.y.abs() < 1e-9 && hit.z.abs() < 1e-9);
+        }
+
+        let parallel = stereo_rig(c, 0.5, f64::INFINITY);
+        let direction = parallel.eye_camera(Eye::Left).unwrap().ray_for_pixel(50.5, 25.5).direction;
+        assert!(direction.x.abs() < 1e-9 && direction.y.abs() < 1e-9 && (direction.z - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn combining_the_eyes() {
+        let mut left = canvas(2, 1);
+        let mut right = canvas(2, 1);
+        left.write_pixel(0, 0, colour(1.0, 0.2, 0.3)).unwrap();
+        right.write_pixel(0, 0, colour(0.4, 0.5, 0.6)).unwrap();
+        right.write_pixel(1, 0, colour(0.7, 0.8, 0.9)).unwrap();
+
+        let both = side_by_side(&left, &right).unwrap();
+        assert_eq!((both.width, both.height), (4, 1));
+        let channels = |c: Colour| (c.red, c.green, c.blue);
+        assert_eq!(channels(both.pixel_at(0, 0)), (1.0, 0.2, 0.3));
+        assert_eq!(channels(both.pixel_at(3, 0)), (0.7, 0.8, 0.9));
+
+        let mixed = anaglyph(&left, &right).unwrap();
+        assert_eq!(channels(mixed.pixel_at(0, 0)), (1.0, 0.5, 0.6));
+        assert!(matches!(anaglyph(&left, &canvas(3, 1)), Err(Error::SizeMismatch(_))));
+        assert!(matches!(side_by_side(&left, &canvas(2, 2)), Err(Error::SizeMismatch(_))));
+    }
+
+    #[test]
+    fn rendering_both_eyes() {
+        let w = default_world();
+        let c = camera(11, 11, PI / 2.0).set_transform(view_transform(point_i(0, 0, -5), point_i(0, 0, 0), vector_i(0, 1, 0))).unwrap();
+        let (left, right) = stereo_rig(c, 0.2, 5.0).render(|eye| {
+            let mut image = canvas(eye.hsize, eye.vsize);
+            for y in 0..eye.vsize {
+                for x in 0..eye.hsize {
+                    image.write_pixel(x, y, w.colour_at(&eye.ray_for_pixel(x as f64 + 0.5, y as f64 + 0.5))).unwrap();
+                }
+            }
+            image
+        }).unwrap();
+        // both eyes look at the middle of the sphere
+        assert!(left.pixel_at(5, 5).green > 0.3 && right.pixel_at(5, 5).green > 0.3);
+        // the light is up and to the left, so the left eye sees more of the lit side
+        assert!(left.pixel_at(5, 5).green > right.pixel_at(5, 5).green + 0.01);
+    }
+}
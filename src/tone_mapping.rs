@@ -0,0 +1,153 @@
+use crate::colour::{colour, Colour};
+
+pub const LINEAR_OUTPUT: OutputTransform = OutputTransform {
+    exposure: 0.0,
+    tone_mapping: ToneMapping::Clamp,
+    srgb: false,
+};
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ToneMapping {
+    Clamp,
+    Reinhard,
+    AcesFilmic,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct OutputTransform {
+    // in stops, every +1 doubles the brightness
+    pub exposure: f64,
+    pub tone_mapping: ToneMapping,
+    pub srgb: bool,
+}
+
+pub fn output_transform(exposure: f64, tone_mapping: ToneMapping, srgb: bool) -> OutputTransform {
+    OutputTransform {
+        exposure,
+        tone_mapping,
+        srgb,
+    }
+}
+
+impl OutputTransform {
+    pub fn apply(&self, value: Colour) -> Colour {
+        let exposed = value * 2.0_f64.powf(self.exposure);
+        let mapped = colour(
+            self.tone_mapping.map(exposed.red),
+            self.tone_mapping.map(exposed.green),
+            self.tone_mapping.map(exposed.blue),
+        );
+        if self.srgb {
+            colour(linear_to_srgb(mapped.red), linear_to_srgb(mapped.green), linear_to_srgb(mapped.blue))
+        } else {
+            mapped
+        }
+    }
+}
+
+impl ToneMapping {
+    pub fn map(&self, value: f64) -> f64 {
+        let value = value.max(0.0);
+        match self {
+            ToneMapping::Clamp => value.min(1.0),
+            ToneMapping::Reinhard => value / (1.0 + value),
+            ToneMapping::AcesFilmic => {
+                // curve fit of the ACES reference rendering transform by Krzysztof Narkowicz
+                let mapped = (value * (2.51 * value + 0.03)) / (value * (2.43 * value + 0.59) + 0.14);
+                mapped.clamp(0.0, 1.0)
+            }
+        }
+    }
+}
+
+pub fn linear_to_srgb(value: f64) -> f64 {
+    let value = value.clamp(0.0, 1.0);
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+pub fn srgb_to_linear(value: f64) -> f64 {
+    let value = value.clamp(0.0, 1.0);
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canvas::canvas;
+    use crate::colour::{colour, Colour};
+    use crate::tone_mapping::{linear_to_srgb, LINEAR_OUTPUT, output_transform, srgb_to_linear, ToneMapping};
+
+    // channel by channel, the colour equality would accept (0.1, 0.2, 0.3) for (0.2, 0.4, 0.6)
+    fn assert_colour_close(actual: Colour, expected: Colour) {
+        let differences = [actual.red - expected.red, actual.green - expected.green, actual.blue - expected.blue];
+        assert!(differences.iter().all(|difference| difference.abs() < 1e-9), "{:?} is not {:?}", actual, expected);
+    }
+
+    #[test]
+    fn linear_output_leaves_colours_in_range_untouched() {
+        let c = colour(0.25, 0.5, 0.75);
+        assert_colour_close(LINEAR_OUTPUT.apply(c), c);
+    }
+
+    #[test]
+    fn exposure_is_measured_in_stops() {
+        let transform = output_transform(1.0, ToneMapping::Clamp, false);
+        let c = transform.apply(colour(0.1, 0.2, 0.3));
+        assert_colour_close(c, colour(0.2, 0.4, 0.6));
+    }
+
+    #[test]
+    fn reinhard_compresses_highlights_below_one() {
+        assert_eq!(ToneMapping::Reinhard.map(1.0), 0.5);
+        assert_eq!(ToneMapping::Reinhard.map(3.0), 0.75);
+        assert!(ToneMapping::Reinhard.map(1000.0) < 1.0);
+        assert_eq!(ToneMapping::Reinhard.map(-1.0), 0.0);
+    }
+
+    #[test]
+    fn aces_filmic_is_monotonic_and_saturates() {
+        let mut previous = ToneMapping::AcesFilmic.map(0.0);
+        assert_eq!(previous, 0.0);
+        for i in 1..100 {
+            let mapped = ToneMapping::AcesFilmic.map(i as f64 * 0.1);
+            assert!(mapped >= previous);
+            previous = mapped;
+        }
+        assert_eq!(ToneMapping::AcesFilmic.map(100.0), 1.0);
+    }
+
+    #[test]
+    fn linear_to_srgb_encoding() {
+        assert_eq!(linear_to_srgb(0.0), 0.0);
+        assert!((linear_to_srgb(1.0) - 1.0).abs() < 1e-9);
+        assert!((linear_to_srgb(0.002) - 0.02584).abs() < 1e-9);
+        assert!((linear_to_srgb(0.214041) - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn srgb_encoding_round_trips() {
+        for i in 0..=20 {
+            let value = i as f64 / 20.0;
+            assert!((srgb_to_linear(linear_to_srgb(value)) - value).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn ppm_export_applies_output_transform() {
+        let mut c = canvas(2, 1);
+        c.output = output_transform(0.0, ToneMapping::Reinhard, true);
+        c.write_pixel(0, 0, colour(1.0, 3.0, 0.0)).unwrap();
+        let ppm = c.to_ppm();
+
+        let mut line_iterator = ppm.lines();
+        let fourth_line = line_iterator.nth(3).unwrap();
+        assert_eq!(fourth_line, "188 225 0 0 0 0");
+    }
+}
@@ -1,15 +1,27 @@
 use std::f64::consts::PI;
 use std::fs::File;
-use std::io::Write;
+use std::io::{Read, Write};
+
+use crate::error::Error;
+
+pub const EPSILON: f64 = 1e-5;
 
 pub fn a_equal_b(a: f64, b: f64) -> bool {
     a.abs() - b.abs() < 1e-5
 }
 
-pub fn write_file(filepath: &str, content: &String) -> std::io::Result<()> {
-    let mut file = File::create(filepath)?;
-    file.write_all(content.as_ref())?;
-    Ok(())
+pub fn write_file<T: AsRef<[u8]>>(filepath: &str, content: T) -> Result<(), Error> {
+    File::create(filepath)
+        .and_then(|mut file| file.write_all(content.as_ref()))
+        .map_err(|error| Error::Io(format!("Could not write '{}': {}", filepath, error)))
+}
+
+pub fn read_file(filepath: &str) -> Result<Vec<u8>, Error> {
+    let mut content = Vec::new();
+    File::open(filepath)
+        .and_then(|mut file| file.read_to_end(&mut content))
+        .map_err(|error| Error::Io(format!("Could not read '{}': {}", filepath, error)))?;
+    Ok(content)
 }
 
 pub fn radians_i(degrees: i32) -> f64 {
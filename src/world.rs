@@ -0,0 +1,259 @@
+use crate::colour::{BLACK, colour, Colour, WHITE};
+use crate::environment::{constant_environment, Environment};
+use crate::lights::{Light, point_light};
+use crate::materials::{lighting, material};
+use crate::matrix::scaling;
+use crate::objects::{Object, sphere};
+use crate::ray::{hit, Intersection, Ray, timed_ray};
+use crate::tuple::{dot_product, point, Tuple};
+use crate::util::EPSILON;
+
+pub struct World {
+    pub objects: Vec<Object>,
+    pub lights: Vec<Light>,
+    // seen by rays that miss every object
+    pub environment: Environment,
+}
+
+pub struct Computations {
+    pub time: f64,
+    pub object: Object,
+    pub point: Tuple,
+    // slightly above the surface, so rays starting here do not hit the surface they start on
+    pub over_point: Tuple,
+    pub eyev: Tuple,
+    pub normalv: Tuple,
+    pub inside: bool,
+    pub ray_time: f64,
+}
+
+pub fn world() -> World {
+    World {
+        objects: Vec::new(),
+        lights: Vec::new(),
+        environment: constant_environment(BLACK),
+    }
+}
+
+pub fn default_world() -> World {
+    let mut inner_material = material();
+    inner_material.colour = colour(0.8, 1.0, 0.6);
+    inner_material.diffuse = 0.7;
+    inner_material.specular = 0.2;
+    World {
+        objects: vec![
+            sphere().set_material(inner_material),
+            sphere().set_transform(scaling(0.5, 0.5, 0.5)).unwrap(),
+        ],
+        lights: vec![point_light(point(-10.0, 10.0, -10.0), WHITE)],
+        environment: constant_environment(BLACK),
+    }
+}
+
+pub fn prepare_computations(intersection: &Intersection, ray: &Ray) -> Computations {
+    let point = ray.position(intersection.time);
+    let eyev = -ray.direction;
+    let mut normalv = intersection.object.normal_at_time(point, ray.time);
+    let inside = dot_product(normalv, eyev) < 0.0;
+    if inside {
+        normalv = -normalv;
+    }
+    Computations {
+        time: intersection.time,
+        object: *intersection.object,
+        point,
+        over_point: point + normalv * EPSILON,
+        eyev,
+        normalv,
+        inside,
+        ray_time: ray.time,
+    }
+}
+
+impl World {
+    // all intersections of the ray with objects of the world, sorted by distance
+    pub fn intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let mut intersections = Vec::new();
+        for object in &self.objects {
+            intersections.extend_from_slice(ray.intersect(object).as_slice());
+        }
+        intersections.sort_by(|a, b| a.time.total_cmp(&b.time));
+        intersections
+    }
+
+    pub fn shade_hit(&self, comps: &Computations) -> Colour {
+        let material = comps.object.material();
+        let mut colour = material.emission;
+        for light in &self.lights {
+            let intensity = self.intensity_at(light, comps.over_point, comps.ray_time);
+            colour = colour + lighting(&material, light, comps.over_point, comps.eyev, comps.normalv, intensity);
+        }
+        colour
+    }
+
+    pub fn colour_at(&self, ray: &Ray) -> Colour {
+        let intersections = self.intersect(ray);
+        match hit(&intersections) {
+            Some(intersection) => self.shade_hit(&prepare_computations(&intersection, ray)),
+            None => self.environment.colour(ray.direction),
+        }
+    }
+
+    // indices of the objects with an emissive material, the path tracer samples them like lights
+    pub fn emitters(&self) -> Vec<usize> {
+        self.objects.iter().enumerate()
+            .filter(|(_, object)| {
+                let emission = object.material().emission;
+                emission.red > 0.0 || emission.green > 0.0 || emission.blue > 0.0
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    pub fn is_shadowed(&self, light_position: Tuple, point: Tuple, time: f64) -> bool {
+        let v = light_position - point;
+        self.is_occluded(point, v.normalize(), v.magnitude(), time)
+    }
+
+    // whether anything is hit along `direction` from `point` within `distance`
+    pub fn is_occluded(&self, point: Tuple, direction: Tuple, distance: f64, time: f64) -> bool {
+        let shadow_ray = timed_ray(point, direction, time);
+        match hit(&self.intersect(&shadow_ray)) {
+            Some(intersection) => intersection.time < distance,
+            None => false,
+        }
+    }
+
+    // fraction of the light's sample points that are visible from `point`, 0 is full shadow and 1 fully lit
+    pub fn intensity_at(&self, light: &Light, point: Tuple, time: f64) -> f64 {
+        if let Light::Directional { .. } = light {
+            let (direction, distance) = light.direction_from(point);
+            return if self.is_occluded(point, direction, distance, time) { 0.0 } else { 1.0 };
+        }
+        let samples = light.sample_points(point);
+        let visible = samples.iter().filter(|sample| !self.is_shadowed(**sample, point, time)).count();
+        visible as f64 / samples.len() as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::colour::{BLACK, colour, WHITE};
+    use crate::lights::point_light;
+    use crate::matrix::translation_i;
+    use crate::objects::sphere;
+    use crate::ray::{intersection, ray};
+    use crate::tuple::{point, point_i, vector_i};
+    use crate::util::EPSILON;
+    use crate::world::{default_world, prepare_computations, world};
+
+    #[test]
+    fn creating_a_world() {
+        let w = world();
+        assert!(w.objects.is_empty());
+        assert!(w.lights.is_empty());
+    }
+
+    #[test]
+    fn intersect_a_world_with_a_ray() {
+        let w = default_world();
+        let r = ray(point_i(0, 0, -5), vector_i(0, 0, 1));
+        let xs = w.intersect(&r);
+        let times: Vec<f64> = xs.iter().map(|i| i.time).collect();
+        assert_eq!(times, vec![4.0, 4.5, 5.5, 6.0]);
+    }
+
+    #[test]
+    fn precomputing_the_state_of_an_intersection() {
+        let r = ray(point_i(0, 0, -5), vector_i(0, 0, 1));
+        let comps = prepare_computations(&intersection(4.0, &sphere()), &r);
+        assert_eq!(comps.point, point_i(0, 0, -1));
+        assert_eq!(comps.eyev, vector_i(0, 0, -1));
+        assert_eq!(comps.normalv, vector_i(0, 0, -1));
+        assert!(!comps.inside);
+    }
+
+    #[test]
+    fn hit_occurs_on_the_inside() {
+        let r = ray(point_i(0, 0, 0), vector_i(0, 0, 1));
+        let comps = prepare_computations(&intersection(1.0, &sphere()), &r);
+        assert_eq!(comps.point, point_i(0, 0, 1));
+        assert_eq!(comps.normalv, vector_i(0, 0, -1));
+        assert!(comps.inside);
+    }
+
+    #[test]
+    fn hit_should_offset_the_point() {
+        let r = ray(point_i(0, 0, -5), vector_i(0, 0, 1));
+        let shape = sphere().set_transform(translation_i(0, 0, 1)).unwrap();
+        let comps = prepare_computations(&intersection(5.0, &shape), &r);
+        assert!(comps.over_point.z < -EPSILON / 2.0);
+        assert!(comps.point.z > comps.over_point.z);
+    }
+
+    #[test]
+    fn shading_an_intersection() {
+        let w = default_world();
+        let r = ray(point_i(0, 0, -5), vector_i(0, 0, 1));
+        let comps = prepare_computations(&intersection(4.0, &w.objects[0]), &r);
+        let c = w.shade_hit(&comps);
+        assert!((c.red - 0.38066).abs() < 1e-4);
+        assert!((c.green - 0.47583).abs() < 1e-4);
+        assert!((c.blue - 0.2855).abs() < 1e-4);
+    }
+
+    #[test]
+    fn colour_when_ray_misses() {
+        let w = default_world();
+        let r = ray(point_i(0, 0, -5), vector_i(0, 1, 0));
+        let c = w.colour_at(&r);
+        assert_eq!(c.red, BLACK.red);
+        assert_eq!(c.green, BLACK.green);
+    }
+
+    #[test]
+    fn colour_with_an_intersection_behind_the_ray() {
+        let mut w = default_world();
+        let mut m = w.objects[0].material();
+        m.ambient = 1.0;
+        w.objects[0] = w.objects[0].set_material(m);
+        let mut m = w.objects[1].material();
+        m.ambient = 1.0;
+        w.objects[1] = w.objects[1].set_material(m);
+        let r = ray(point(0.0, 0.0, 0.75), vector_i(0, 0, -1));
+        let c = w.colour_at(&r);
+        assert!((c.red - w.objects[1].material().colour.red).abs() < 1e-9);
+    }
+
+    #[test]
+    fn no_shadow_when_nothing_is_collinear_with_point_and_light() {
+        let w = default_world();
+        assert!(!w.is_shadowed(point_i(-10, 10, -10), point_i(0, 10, 0), 0.0));
+    }
+
+    #[test]
+    fn shadow_when_object_is_between_point_and_light() {
+        let w = default_world();
+        assert!(w.is_shadowed(point_i(-10, 10, -10), point_i(10, -10, 10), 0.0));
+    }
+
+    #[test]
+    fn no_shadow_when_object_is_behind_the_light_or_point() {
+        let w = default_world();
+        assert!(!w.is_shadowed(point_i(-10, 10, -10), point_i(-20, 20, -20), 0.0));
+        assert!(!w.is_shadowed(point_i(-10, 10, -10), point_i(-2, 2, -2), 0.0));
+    }
+
+    #[test]
+    fn shade_hit_is_given_an_intersection_in_shadow() {
+        let mut w = world();
+        w.lights.push(point_light(point_i(0, 0, -10), WHITE));
+        w.objects.push(sphere());
+        w.objects.push(sphere().set_transform(translation_i(0, 0, 10)).unwrap());
+        let r = ray(point_i(0, 0, 5), vector_i(0, 0, 1));
+        let comps = prepare_computations(&intersection(4.0, &w.objects[1]), &r);
+        let c = w.shade_hit(&comps);
+        assert_eq!(c, colour(0.1, 0.1, 0.1));
+        assert!((c.red - 0.1).abs() < 1e-9);
+    }
+}
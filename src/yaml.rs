@@ -0,0 +1,322 @@
+use crate::error::Error;
+
+// the subset of yaml the scene files need: mappings and lists nested by indentation, lists in brackets, plain and
+// quoted scalars and comments. every node remembers where it started for error messages
+#[derive(Debug, PartialEq, Clone)]
+pub struct Node {
+    pub value: Value,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value {
+    Scalar(String),
+    List(Vec<Node>),
+    // keys keep the order of the file
+    Map(Vec<(String, Node)>),
+}
+
+struct Line {
+    number: usize,
+    indent: usize,
+    text: String,
+}
+
+pub fn parse_yaml(text: &str) -> Result<Node, Error> {
+    let mut lines = Vec::new();
+    for (index, raw) in text.lines().enumerate() {
+        let content = strip_comment(raw);
+        let trimmed = content.trim_start_matches(' ');
+        if trimmed.trim().is_empty() {
+            continue;
+        }
+        let indent = content.len() - trimmed.len();
+        if trimmed.starts_with('\t') {
+            return Err(position_error(index + 1, indent + 1, "Tabs are not allowed for indentation."));
+        }
+        lines.push(Line {
+            number: index + 1,
+            indent,
+            text: trimmed.trim_end().to_string(),
+        });
+    }
+    if lines.is_empty() {
+        return Ok(Node { value: Value::List(Vec::new()), line: 1, column: 1 });
+    }
+
+    let mut position = 0;
+    let indent = lines[0].indent;
+    let node = parse_block(&mut lines, &mut position, indent)?;
+    if let Some(line) = lines.get(position) {
+        return Err(position_error(line.number, line.indent + 1, "Unexpected indentation."));
+    }
+    Ok(node)
+}
+
+pub fn position_error(line: usize, column: usize, message: &str) -> Error {
+    Error::Parse(format!("Line {}, column {}: {}", line, column, message))
+}
+
+impl Node {
+    pub fn error(&self, message: &str) -> Error {
+        position_error(self.line, self.column, message)
+    }
+}
+
+fn parse_block(lines: &mut [Line], position: &mut usize, indent: usize) -> Result<Node, Error> {
+    if is_item(&lines[*position].text) {
+        parse_list(lines, position, indent)
+    } else {
+        parse_map(lines, position, indent)
+    }
+}
+
+fn parse_list(lines: &mut [Line], position: &mut usize, indent: usize) -> Result<Node, Error> {
+    let line = lines[*position].number;
+    let mut items = Vec::new();
+    while *position < lines.len() && lines[*position].indent >= indent {
+        let current = &mut lines[*position];
+        if current.indent > indent {
+            return Err(position_error(current.number, current.indent + 1, "Unexpected indentation."));
+        }
+        if !is_item(&current.text) {
+            return Err(position_error(current.number, current.indent + 1, "Expected a list item starting with '-'."));
+        }
+        let rest = current.text[1..].trim_start().to_string();
+        if rest.is_empty() {
+            let (number, column) = (current.number, current.indent + 1);
+            *position += 1;
+            items.push(parse_nested(lines, position, indent, false, number, column)?);
+            continue;
+        }
+        // the rest of the line is parsed as if it started a line of its own, indented up to where it starts
+        current.indent += current.text.len() - rest.len();
+        current.text = rest;
+        if is_item(&current.text) || split_key(&current.text).is_some() {
+            let nested_indent = current.indent;
+            items.push(parse_block(lines, position, nested_indent)?);
+        } else {
+            let node = parse_flow(&current.text, current.number, current.indent + 1)?;
+            *position += 1;
+            items.push(node);
+        }
+    }
+    Ok(Node { value: Value::List(items), line, column: indent + 1 })
+}
+
+fn parse_map(lines: &mut [Line], position: &mut usize, indent: usize) -> Result<Node, Error> {
+    let line = lines[*position].number;
+    let mut entries: Vec<(String, Node)> = Vec::new();
+    while *position < lines.len() && lines[*position].indent >= indent {
+        let current = &lines[*position];
+        let (number, column) = (current.number, current.indent + 1);
+        if current.indent > indent {
+            return Err(position_error(number, column, "Unexpected indentation."));
+        }
+        if is_item(&current.text) {
+            // a list can only follow a key that has no value on its own line
+            return Err(position_error(number, column, "Expected 'key: value'."));
+        }
+        let (key, rest_start) = split_key(&current.text).ok_or_else(|| position_error(number, column, "Expected 'key: value'."))?;
+        if entries.iter().any(|(existing, _)| *existing == key) {
+            return Err(position_error(number, column, &format!("Duplicate key '{}'.", key)));
+        }
+        let rest = current.text[rest_start..].trim_start();
+        let value = if rest.is_empty() {
+            *position += 1;
+            parse_nested(lines, position, indent, true, number, column)?
+        } else {
+            let node = parse_flow(rest, number, column + current.text.len() - rest.len())?;
+            *position += 1;
+            node
+        };
+        entries.push((key, value));
+    }
+    Ok(Node { value: Value::Map(entries), line, column: indent + 1 })
+}
+
+// the value of a key or list item that continues on the following lines, a list may start at the same indentation
+// as the key it belongs to
+fn parse_nested(lines: &mut [Line], position: &mut usize, indent: usize, allow_same_indent_list: bool,
+                line: usize, column: usize) -> Result<Node, Error> {
+    match lines.get(*position) {
+        Some(next) if next.indent > indent || (allow_same_indent_list && next.indent == indent && is_item(&next.text)) => {
+            let nested_indent = next.indent;
+            parse_block(lines, position, nested_indent)
+        }
+        _ => Err(position_error(line, column, "Missing value.")),
+    }
+}
+
+fn is_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+// the key and the index just after its colon, if the line is a mapping entry
+fn split_key(text: &str) -> Option<(String, usize)> {
+    if text.starts_with('[') || text.starts_with('"') || text.starts_with('{') {
+        return None;
+    }
+    let bytes = text.as_bytes();
+    let colon = (0..bytes.len()).find(|&i| bytes[i] == b':' && (i + 1 == bytes.len() || bytes[i + 1] == b' '))?;
+    let key = text[..colon].trim();
+    if key.is_empty() {
+        return None;
+    }
+    Some((key.to_string(), colon + 1))
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    let mut previous = ' ';
+    for (index, character) in line.char_indices() {
+        if character == '"' && previous != '\\' {
+            quoted = !quoted;
+        } else if character == '#' && !quoted && previous.is_whitespace() {
+            return &line[..index];
+        }
+        previous = character;
+    }
+    line
+}
+
+// a scalar or a bracketed list on a single line, `column` is where `text` starts
+fn parse_flow(text: &str, line: usize, column: usize) -> Result<Node, Error> {
+    let mut flow = Flow {
+        characters: text.chars().collect(),
+        index: 0,
+        line,
+        column,
+    };
+    let node = flow.value(false)?;
+    flow.skip_whitespace();
+    if flow.index < flow.characters.len() {
+        return Err(flow.error("Unexpected characters after the value."));
+    }
+    Ok(node)
+}
+
+struct Flow {
+    characters: Vec<char>,
+    index: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Flow {
+    fn error(&self, message: &str) -> Error {
+        position_error(self.line, self.column + self.index, message)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.characters.get(self.index).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|character| character.is_whitespace()) {
+            self.index += 1;
+        }
+    }
+
+    // inside brackets plain scalars end at commas and closing brackets
+    fn value(&mut self, in_list: bool) -> Result<Node, Error> {
+        self.skip_whitespace();
+        let (line, column) = (self.line, self.column + self.index);
+        match self.peek() {
+            None => Err(self.error("Missing value.")),
+            Some('[') => {
+                self.index += 1;
+                let mut items = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    if self.peek() == Some(']') && items.is_empty() {
+                        self.index += 1;
+                        break;
+                    }
+                    items.push(self.value(true)?);
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(',') => self.index += 1,
+                        Some(']') => {
+                            self.index += 1;
+                            break;
+                        }
+                        None => return Err(self.error("Unclosed '['.")),
+                        Some(_) => return Err(self.error("Expected ',' or ']'.")),
+                    }
+                }
+                Ok(Node { value: Value::List(items), line, column })
+            }
+            Some('{') => Err(self.error("Mappings in braces are not supported.")),
+            Some('"') => {
+                self.index += 1;
+                let mut text = String::new();
+                loop {
+                    match self.peek() {
+                        None => return Err(position_error(line, column, "Unclosed quote.")),
+                        Some('"') => {
+                            self.index += 1;
+                            break;
+                        }
+                        Some('\\') => {
+                            self.index += 1;
+                            match self.peek() {
+                                Some('n') => text.push('\n'),
+                                Some(character @ ('"' | '\\')) => text.push(character),
+                                _ => return Err(self.error("Unknown escape sequence.")),
+                            }
+                            self.index += 1;
+                        }
+                        Some(character) => {
+                            text.push(character);
+                            self.index += 1;
+                        }
+                    }
+                }
+                Ok(Node { value: Value::Scalar(text), line, column })
+            }
+            Some(_) => {
+                let start = self.index;
+                while self.peek().is_some_and(|character| !(in_list && (character == ',' || character == ']'))) {
+                    self.index += 1;
+                }
+                let text: String = self.characters[start..self.index].iter().collect();
+                let text = text.trim_end();
+                if text.is_empty() {
+                    return Err(position_error(line, column, "Missing value."));
+                }
+                Ok(Node { value: Value::Scalar(text.to_string()), line, column })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::yaml::{parse_yaml, Value};
+
+    #[test]
+    fn parsing_nested_values() {
+        let node = parse_yaml("a: 1\nb:\n  - [x, [1, 2], \"q, r\"]\n  - c: d\n    e: f\n").unwrap();
+        let Value::Map(entries) = &node.value else { panic!("expected a mapping") };
+        assert_eq!(entries[0].0, "a");
+        assert_eq!(entries[0].1.value, Value::Scalar(String::from("1")));
+        let Value::List(items) = &entries[1].1.value else { panic!("expected a list") };
+        let Value::List(flow) = &items[0].value else { panic!("expected a list") };
+        assert_eq!(flow[2].value, Value::Scalar(String::from("q, r")));
+        assert_eq!((flow[1].line, flow[1].column), (3, 9));
+        let Value::Map(nested) = &items[1].value else { panic!("expected a mapping") };
+        assert_eq!(nested.len(), 2);
+        assert_eq!((nested[1].1.line, nested[1].1.column), (5, 8));
+    }
+
+    #[test]
+    fn yaml_errors_have_positions() {
+        assert_eq!(parse_yaml("a: [1, 2\n").unwrap_err().to_string(), "Line 1, column 9: Unclosed '['.");
+        assert_eq!(parse_yaml("a: 1\n   b: 2\n").unwrap_err().to_string(), "Line 2, column 4: Unexpected indentation.");
+        assert_eq!(parse_yaml("a: 1\na: 2\n").unwrap_err().to_string(), "Line 2, column 1: Duplicate key 'a'.");
+        assert_eq!(parse_yaml("a:\n").unwrap_err().to_string(), "Line 1, column 1: Missing value.");
+        assert_eq!(parse_yaml("- a\nb: c\n").unwrap_err().to_string(), "Line 2, column 1: Expected a list item starting with '-'.");
+    }
+}